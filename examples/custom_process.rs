@@ -0,0 +1,123 @@
+//! A session over a custom process which runs a program on a "remote" host.
+//!
+//! The remote host is simulated by an agent thread which is reached via TCP,
+//! but the same approach works for a container or an SSH channel.
+//! A process needs a stream to talk to a program,
+//! and optionally a [`Healthcheck`] to tell whether it's still alive.
+
+#[cfg(not(feature = "async"))]
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    thread::{self, JoinHandle},
+};
+
+#[cfg(not(feature = "async"))]
+use expectrl::{
+    process::{Healthcheck, Process},
+    Eof, Error, Session,
+};
+
+#[cfg(not(feature = "async"))]
+/// A process which runs a program on a remote agent.
+struct RemoteProcess {
+    conn: TcpStream,
+    agent: JoinHandle<()>,
+}
+
+#[cfg(not(feature = "async"))]
+impl Process for RemoteProcess {
+    type Command = String;
+    type Stream = TcpStream;
+
+    fn spawn<S>(cmd: S) -> io::Result<Self>
+    where
+        S: AsRef<str>,
+    {
+        Self::spawn_command(cmd.as_ref().to_owned())
+    }
+
+    fn spawn_command(command: Self::Command) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let agent = thread::spawn(move || {
+            if let Ok((conn, _)) = listener.accept() {
+                let _ = run_agent(conn);
+            }
+        });
+
+        // the first line tells the agent what to run
+        let mut conn = TcpStream::connect(addr)?;
+        writeln!(conn, "{}", command)?;
+
+        Ok(Self { conn, agent })
+    }
+
+    fn open_stream(&mut self) -> io::Result<Self::Stream> {
+        // TcpStream already implements NonBlocking
+        self.conn.try_clone()
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl Healthcheck for RemoteProcess {
+    type Status = bool;
+
+    fn get_status(&self) -> io::Result<Self::Status> {
+        self.is_alive()
+    }
+
+    fn is_alive(&self) -> io::Result<bool> {
+        Ok(!self.agent.is_finished())
+    }
+}
+
+#[cfg(not(feature = "async"))]
+/// An agent which runs a `shout` program, which repeats lines in upper case until `exit`.
+fn run_agent(conn: TcpStream) -> io::Result<()> {
+    let mut output = conn.try_clone()?;
+    let mut lines = BufReader::new(conn).lines();
+
+    let program = lines.next().transpose()?.unwrap_or_default();
+    if program != "shout" {
+        return writeln!(output, "{}: command not found", program);
+    }
+
+    write!(output, "shout> ")?;
+    for line in lines {
+        let line = line?;
+        if line == "exit" {
+            break;
+        }
+
+        write!(output, "{}\nshout> ", line.to_uppercase())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "async"))]
+fn main() -> Result<(), Error> {
+    use expectrl::Expect;
+
+    let mut process = RemoteProcess::spawn("shout")?;
+    let stream = process.open_stream()?;
+    let mut session = Session::new(process, stream)?;
+
+    session.expect("shout> ")?;
+    session.send_line("Hello World")?;
+    let m = session.expect("shout> ")?;
+    println!("{}", String::from_utf8_lossy(m.before()).trim());
+
+    println!("alive={}", session.is_alive()?);
+
+    session.send_line("exit")?;
+    session.expect(Eof)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "async")]
+fn main() {
+    panic!("An example is built for a sync session; an async one needs a stream implementing IntoAsyncStream")
+}
@@ -0,0 +1,79 @@
+//! A session over a custom in-memory stream.
+//!
+//! Anything which implements [`Read`], [`Write`] and [`NonBlocking`] can be used in a session,
+//! no file descriptor is required.
+
+#[cfg(not(feature = "async"))]
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+};
+
+#[cfg(not(feature = "async"))]
+use expectrl::{
+    process::{NoProcess, NonBlocking},
+    Error, Session,
+};
+
+#[cfg(not(feature = "async"))]
+/// A loopback stream which returns everything which was written to it.
+#[derive(Default)]
+struct Loopback {
+    buf: VecDeque<u8>,
+    blocking: bool,
+}
+
+#[cfg(not(feature = "async"))]
+impl Read for Loopback {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.buf.is_empty() && !self.blocking {
+            return Err(io::Error::new(io::ErrorKind::WouldBlock, "no data yet"));
+        }
+
+        self.buf.read(buf)
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl Write for Loopback {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl NonBlocking for Loopback {
+    fn set_blocking(&mut self, on: bool) -> io::Result<()> {
+        self.blocking = on;
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "async"))]
+fn main() -> Result<(), Error> {
+    use expectrl::Expect;
+
+    let mut session = Session::new(NoProcess, Loopback::default())?;
+    session.set_expect_timeout(Some(std::time::Duration::from_millis(100)));
+
+    session.send_line("Hello World")?;
+    let m = session.expect("World")?;
+    println!("before={:?}", String::from_utf8_lossy(m.before()));
+
+    match session.expect("Something else") {
+        Err(Error::ExpectTimeout) => println!("nothing else was written"),
+        result => println!("unexpected result {:?}", result),
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "async")]
+fn main() {
+    panic!("An example is built for a sync session; an async one relies on AsyncRead and AsyncWrite instead")
+}
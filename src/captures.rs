@@ -1,6 +1,6 @@
-use std::ops::Index;
+use std::{borrow::Cow, ops::Index};
 
-use crate::needle::Match;
+use crate::needle::{Groups, Match};
 
 /// Captures is a represention of matched pattern.
 ///
@@ -9,12 +9,66 @@ use crate::needle::Match;
 pub struct Captures {
     buf: Vec<u8>,
     matches: Vec<Match>,
+    needle_index: Option<usize>,
+    before_trim: usize,
+    groups: Option<Groups>,
+    distance: Option<usize>,
 }
 
 impl Captures {
     /// New returns an instance of Found.
     pub(crate) fn new(buf: Vec<u8>, matches: Vec<Match>) -> Self {
-        Self { buf, matches }
+        Self {
+            buf,
+            matches,
+            needle_index: None,
+            before_trim: 0,
+            groups: None,
+            distance: None,
+        }
+    }
+
+    /// Sets groups of the matches, see [crate::needle::Needle::groups].
+    pub(crate) fn with_groups(mut self, groups: Option<Groups>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Sets a distance between the matches and a pattern, see [crate::needle::Needle::distance].
+    pub(crate) fn with_distance(mut self, distance: Option<usize>) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Hides a pattern from the end of [Self::before], if it ends with it.
+    pub(crate) fn with_before_trim(mut self, pattern: &[u8]) -> Self {
+        if !pattern.is_empty() && self.before().ends_with(pattern) {
+            self.before_trim = pattern.len();
+        }
+
+        self
+    }
+
+    /// Sets an index of a needle which produced the matches.
+    pub(crate) fn with_needle_index(mut self, index: Option<usize>) -> Self {
+        self.needle_index = index;
+        self
+    }
+
+    /// Returns an index of a needle which matched,
+    /// in case a needle combines a few others, like [crate::Any].
+    ///
+    /// It's `None` for plain needles.
+    pub fn needle_index(&self) -> Option<usize> {
+        self.needle_index
+    }
+
+    /// Returns a distance between a match and a pattern,
+    /// in case a needle tolerates differences, like [crate::Fuzzy].
+    ///
+    /// It's `None` for exact needles.
+    pub fn distance(&self) -> Option<usize> {
+        self.distance
     }
 
     /// is_empty verifies if any matches were actually found.
@@ -29,14 +83,64 @@ impl Captures {
             .map(|m| &self.buf[m.start()..m.end()])
     }
 
+    /// Returns a match of a group by its index,
+    /// in case a needle has groups, like [crate::Regex].
+    ///
+    /// The group `0` is the whole match.
+    /// If a needle matched a few times the first match is taken.
+    ///
+    /// It's `None` if there's no such group or it didn't participate in the match.
+    pub fn group(&self, index: usize) -> Option<&[u8]> {
+        let groups = self.groups.as_ref()?;
+        self.matches
+            .iter()
+            .enumerate()
+            .find(|(i, _)| groups.group_of(*i) == Some(index))
+            .map(|(_, m)| &self.buf[m.start()..m.end()])
+    }
+
+    /// Returns a match of a named group, e.g. `(?P<port>\d+)` of a [crate::Regex].
+    ///
+    /// If a needle matched a few times the first match is taken.
+    ///
+    /// It's `None` if there's no such group or it didn't participate in the match.
+    pub fn name(&self, name: &str) -> Option<&[u8]> {
+        let index = self.groups.as_ref()?.index_of(name)?;
+        self.group(index)
+    }
+
     /// Matches returns a list of matches.
     pub fn matches(&self) -> MatchIter<'_> {
         MatchIter::new(self)
     }
 
     /// before returns a bytes before match.
+    ///
+    /// A pattern set by [`Session::set_output_trim`](crate::Session::set_output_trim) is excluded from it.
     pub fn before(&self) -> &[u8] {
-        &self.buf[..self.left_most_index()]
+        &self.buf[..self.left_most_index() - self.before_trim]
+    }
+
+    /// Returns [Self::before] as a string,
+    /// invalid UTF-8 sequences are replaced with `U+FFFD`.
+    pub fn before_string(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.before())
+    }
+
+    /// Returns the matched bytes as a string,
+    /// from the start of the left most match to the end of the right most one.
+    ///
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD`.
+    /// It's empty if there are no matches.
+    pub fn matched_string(&self) -> Cow<'_, str> {
+        let end = Self::right_most_index(&self.matches);
+        String::from_utf8_lossy(&self.buf[self.left_most_index()..end])
+    }
+
+    /// Returns a match by index as a string, like [Self::get] does,
+    /// invalid UTF-8 sequences are replaced with `U+FFFD`.
+    pub fn get_str(&self, index: usize) -> Option<Cow<'_, str>> {
+        self.get(index).map(String::from_utf8_lossy)
     }
 
     /// as_bytes returns all bytes involved in a match, e.g. before the match and
@@ -49,6 +153,17 @@ impl Captures {
         &self.buf
     }
 
+    /// examined returns the raw region of a buffer a match was found in,
+    /// from its start up to the end of the match.
+    ///
+    /// Unlike [Self::before] and [Self::matches] it's not derived from the matches,
+    /// so it includes the bytes in between them and
+    /// the ones hidden by [`Session::set_output_trim`](crate::Session::set_output_trim).
+    /// It's handy for debugging custom [crate::Needle] implementations.
+    pub fn examined(&self) -> &[u8] {
+        &self.buf
+    }
+
     fn left_most_index(&self) -> usize {
         self.matches
             .iter()
@@ -62,6 +177,51 @@ impl Captures {
     }
 }
 
+/// Captures are serialized with the bytes as lossy UTF-8 strings,
+/// which is handy for golden tests.
+///
+/// ```json
+/// {
+///   "bytes": "Hello World",
+///   "before": "Hello ",
+///   "matches": [{ "start": 6, "end": 11, "text": "World" }],
+///   "needle_index": null
+/// }
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for Captures {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        #[derive(serde::Serialize)]
+        struct SerializedMatch {
+            start: usize,
+            end: usize,
+            text: String,
+        }
+
+        let matches = self
+            .matches
+            .iter()
+            .map(|m| SerializedMatch {
+                start: m.start(),
+                end: m.end(),
+                text: String::from_utf8_lossy(&self.buf[m.start()..m.end()]).into_owned(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut s = serializer.serialize_struct("Captures", 4)?;
+        s.serialize_field("bytes", &String::from_utf8_lossy(&self.buf))?;
+        s.serialize_field("before", &String::from_utf8_lossy(self.before()))?;
+        s.serialize_field("matches", &matches)?;
+        s.serialize_field("needle_index", &self.needle_index)?;
+        s.end()
+    }
+}
+
 impl Index<usize> for Captures {
     type Output = [u8];
 
@@ -135,6 +295,30 @@ mod tests {
         assert_eq!(m.get(2), None);
     }
 
+    #[test]
+    fn test_captures_group() {
+        let m = Captures::new(
+            b"port=8080".to_vec(),
+            vec![Match::new(0, 9), Match::new(0, 4), Match::new(5, 9)],
+        )
+        .with_groups(Some(Groups::new(
+            vec![0, 1, 3],
+            vec![None, Some("key".into()), None, Some("value".into())],
+        )));
+
+        assert_eq!(m.group(0), Some(b"port=8080".as_ref()));
+        assert_eq!(m.group(1), Some(b"port".as_ref()));
+        assert_eq!(m.group(2), None);
+        assert_eq!(m.group(3), Some(b"8080".as_ref()));
+        assert_eq!(m.name("key"), Some(b"port".as_ref()));
+        assert_eq!(m.name("value"), Some(b"8080".as_ref()));
+        assert_eq!(m.name("port"), None);
+
+        let m = Captures::new(b"port=8080".to_vec(), vec![Match::new(5, 9)]);
+        assert_eq!(m.group(0), None);
+        assert_eq!(m.name("port"), None);
+    }
+
     #[test]
     #[should_panic]
     fn test_captures_get_panics_on_invalid_match() {
@@ -199,6 +383,59 @@ mod tests {
         assert_eq!(m.before(), b"".as_ref());
     }
 
+    #[test]
+    fn test_strings() {
+        let m = Captures::new(
+            b"port=8080\xff".to_vec(),
+            vec![Match::new(0, 4), Match::new(5, 10)],
+        );
+        assert_eq!(m.before_string(), "");
+        assert_eq!(m.matched_string(), "port=8080\u{fffd}");
+        assert_eq!(m.get_str(0).as_deref(), Some("port"));
+        assert_eq!(m.get_str(1).as_deref(), Some("8080\u{fffd}"));
+        assert_eq!(m.get_str(2), None);
+
+        let m = Captures::new("Привет мир".as_bytes().to_vec(), vec![Match::new(13, 19)]);
+        assert_eq!(m.before_string(), "Привет ");
+        assert_eq!(m.matched_string(), "мир");
+
+        let m = Captures::new(b"Hello".to_vec(), vec![]);
+        assert_eq!(m.before_string(), "");
+        assert_eq!(m.matched_string(), "");
+        assert_eq!(m.get_str(0), None);
+    }
+
+    #[test]
+    fn test_examined() {
+        let m =
+            Captures::new(b"$ You can".to_vec(), vec![Match::new(6, 9)]).with_before_trim(b"You ");
+        assert_eq!(m.examined(), b"$ You can".as_ref());
+        assert_eq!(m.before(), b"$ ".as_ref());
+
+        let m = crate::needle::find(&"can", b"You can do it", false).unwrap();
+        assert_eq!(m.examined(), b"You can".as_ref());
+
+        let m = Captures::new(vec![], vec![]);
+        assert_eq!(m.examined(), b"".as_ref());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let m = Captures::new(b"Hello World".to_vec(), vec![Match::new(6, 11)])
+            .with_needle_index(Some(1));
+        assert_eq!(
+            serde_json::to_string(&m).unwrap(),
+            r#"{"bytes":"Hello World","before":"Hello ","matches":[{"start":6,"end":11,"text":"World"}],"needle_index":1}"#
+        );
+
+        let m = Captures::new(b"\xffab".to_vec(), vec![]);
+        assert_eq!(
+            serde_json::to_string(&m).unwrap(),
+            "{\"bytes\":\"\u{fffd}ab\",\"before\":\"\",\"matches\":[],\"needle_index\":null}"
+        );
+    }
+
     #[test]
     fn test_matches() {
         let m = Captures::new(b"You can use iterator".to_vec(), vec![Match::new(4, 7)]);
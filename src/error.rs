@@ -17,6 +17,12 @@ pub enum Error {
     ExpectTimeout,
     /// Unhandled EOF error.
     Eof,
+    /// A maximum number of reads was reached while waiting in expect call.
+    MaxIterations,
+    /// A process has already exited so nothing can be sent to it.
+    ProcessExited,
+    /// A lifetime of a session was exceeded, see `Session::set_lifetime`.
+    LifetimeExceeded,
     /// It maybe OS specific error or a general erorr.
     Other {
         /// The reason of the erorr.
@@ -24,9 +30,49 @@ pub enum Error {
         /// An underlying error message.
         err: String,
     },
+    /// An error annotated with a context, see [Error::context].
+    Context {
+        /// A description of what was being done when the error occurred.
+        context: String,
+        /// The underlying error.
+        err: Box<Error>,
+    },
 }
 
 impl Error {
+    /// Wraps the error with a context message,
+    /// so it's easier to trace where a multi-step automation failed.
+    ///
+    /// The message is prepended to the error when it's displayed,
+    /// and contexts can be layered on top of each other.
+    /// The original error can be reached via [Error::root].
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(feature = "async", doc = "```ignore")]
+    #[cfg_attr(not(feature = "async"), doc = "```no_run")]
+    /// use expectrl::{spawn, Expect};
+    ///
+    /// let mut p = spawn("ssh user@localhost").unwrap();
+    /// p.expect("password:")
+    ///     .map_err(|e| e.context("during login"))
+    ///     .unwrap();
+    /// ```
+    pub fn context(self, context: impl Into<String>) -> Self {
+        Self::Context {
+            context: context.into(),
+            err: Box::new(self),
+        }
+    }
+
+    /// Returns the original error, skipping all the contexts it was wrapped with.
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context { err, .. } => err.root(),
+            err => err,
+        }
+    }
+
     #[cfg(unix)]
     pub(crate) fn unknown(message: impl Into<String>, err: impl Into<String>) -> Error {
         Self::Other {
@@ -44,12 +90,29 @@ impl Display for Error {
             Error::RegexParsing => write!(f, "Can't parse a regex expression"),
             Error::ExpectTimeout => write!(f, "Reached a timeout for expect type of command"),
             Error::Eof => write!(f, "EOF was reached; the read may successed later"),
+            Error::MaxIterations => {
+                write!(
+                    f,
+                    "Reached a maximum number of reads for expect type of command"
+                )
+            }
+            Error::ProcessExited => write!(f, "The process has already exited"),
+            Error::LifetimeExceeded => write!(f, "The session lifetime was exceeded"),
             Error::Other { message, err } => write!(f, "Unexpected error; {}; {}", message, err),
+            Error::Context { context, err } => write!(f, "{}: {}", context, err),
         }
     }
 }
 
-impl error::Error for Error {}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::IO(err) => Some(err),
+            Error::Context { err, .. } => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
@@ -59,10 +122,58 @@ impl From<io::Error> for Error {
 
 impl From<Error> for io::Error {
     fn from(err: Error) -> Self {
-        io::Error::new(io::ErrorKind::Other, err.to_string())
+        io::Error::other(err.to_string())
+    }
+}
+
+/// Converts an error of a write to a process,
+/// so an error caused by the process being gone becomes [`Error::ProcessExited`].
+pub(crate) fn send_error(err: io::Error) -> Error {
+    let is_exited = err.kind() == io::ErrorKind::BrokenPipe;
+    #[cfg(unix)]
+    let is_exited = is_exited || err.raw_os_error() == Some(nix::libc::EIO);
+
+    match is_exited {
+        true => Error::ProcessExited,
+        false => Error::IO(err),
     }
 }
 
 pub(crate) fn to_io_error<E: Display>(message: &'static str) -> impl FnOnce(E) -> io::Error {
-    move |e: E| io::Error::new(io::ErrorKind::Other, format!("{}; {}", message, e))
+    move |e: E| io::Error::other(format!("{}; {}", message, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context() {
+        let err = Error::ExpectTimeout
+            .context("waiting for a prompt")
+            .context("during login");
+
+        assert_eq!(
+            err.to_string(),
+            "during login: waiting for a prompt: Reached a timeout for expect type of command"
+        );
+        assert!(matches!(err.root(), Error::ExpectTimeout));
+        assert!(matches!(Error::Eof.root(), Error::Eof));
+    }
+
+    #[test]
+    fn test_source() {
+        use std::error::Error as _;
+
+        let err = Error::ExpectTimeout.context("waiting for a prompt");
+        let source = err.source().unwrap();
+        assert_eq!(
+            source.to_string(),
+            "Reached a timeout for expect type of command"
+        );
+        assert!(source.source().is_none());
+
+        let err = Error::IO(io::Error::other("broken"));
+        assert_eq!(err.source().unwrap().to_string(), "broken");
+    }
 }
@@ -1,4 +1,7 @@
-use crate::{Captures, Error, Needle};
+use crate::{Any, Captures, ControlCode, Error, Needle};
+
+#[cfg(feature = "async")]
+use std::future::Future;
 
 /// Expect trait provides common expect functions.
 pub trait Expect {
@@ -21,8 +24,14 @@ pub trait Expect {
     ///
     /// # Example
     ///
-    #[cfg_attr(any(windows, feature = "async"), doc = "```ignore")]
-    #[cfg_attr(not(any(windows, feature = "async")), doc = "```")]
+    #[cfg_attr(
+        any(windows, feature = "async", not(feature = "regex")),
+        doc = "```ignore"
+    )]
+    #[cfg_attr(
+        not(any(windows, feature = "async", not(feature = "regex"))),
+        doc = "```"
+    )]
     /// use expectrl::{Expect, spawn, Regex};
     ///
     /// let mut p = spawn("echo 123").unwrap();
@@ -30,8 +39,14 @@ pub trait Expect {
     /// assert_eq!(m.get(0).unwrap(), b"123");
     /// ```
     ///
-    #[cfg_attr(any(windows, feature = "async"), doc = "```ignore")]
-    #[cfg_attr(not(any(windows, feature = "async")), doc = "```")]
+    #[cfg_attr(
+        any(windows, feature = "async", not(feature = "regex")),
+        doc = "```ignore"
+    )]
+    #[cfg_attr(
+        not(any(windows, feature = "async", not(feature = "regex"))),
+        doc = "```"
+    )]
     /// use expectrl::{Expect, spawn, Regex};
     ///
     /// let mut p = spawn("echo 123").unwrap();
@@ -45,6 +60,11 @@ pub trait Expect {
     /// It returns an error if timeout is reached.
     /// You can specify a timeout value by [`Session::set_expect_timeout`] method.
     ///
+    /// Output which a process wrote before it exited can still be matched,
+    /// even if the process had exited (and was waited for) before the first call.
+    /// Be aware that macOS may discard the output of a process which is gone,
+    /// so there it's better to expect the output before waiting for the process.
+    ///
     /// [`Session::set_expect_timeout`]: crate::Session::set_expect_timeout
     /// [`Session::set_expect_lazy`]: crate::Session::set_expect_lazy
     fn expect<N>(&mut self, needle: N) -> Result<Captures, Error>
@@ -61,10 +81,23 @@ pub trait Expect {
     /// # Example
     ///
     #[cfg_attr(
-        any(windows, target_os = "macos", feature = "async"),
+        any(
+            windows,
+            target_os = "macos",
+            feature = "async",
+            not(feature = "regex")
+        ),
         doc = "```ignore"
     )]
-    #[cfg_attr(not(any(windows, target_os = "macos", feature = "async")), doc = "```")]
+    #[cfg_attr(
+        not(any(
+            windows,
+            target_os = "macos",
+            feature = "async",
+            not(feature = "regex")
+        )),
+        doc = "```"
+    )]
     /// use expectrl::{spawn, Regex, Expect};
     /// use std::time::Duration;
     ///
@@ -100,8 +133,14 @@ pub trait Expect {
     ///
     /// # Example
     ///
-    #[cfg_attr(any(windows, feature = "async"), doc = "```ignore")]
-    #[cfg_attr(not(any(windows, feature = "async")), doc = "```")]
+    #[cfg_attr(
+        any(windows, feature = "async", not(feature = "regex")),
+        doc = "```ignore"
+    )]
+    #[cfg_attr(
+        not(any(windows, feature = "async", not(feature = "regex"))),
+        doc = "```"
+    )]
     /// use expectrl::{spawn, Regex, Expect};
     /// use std::time::Duration;
     ///
@@ -155,6 +194,85 @@ pub trait Expect {
     fn send_line<B>(&mut self, buf: B) -> Result<(), Error>
     where
         B: AsRef<[u8]>;
+
+    /// Expects any of given needles,
+    /// returning an index of the needle which matched along with the captures.
+    ///
+    /// Needles are checked in order,
+    /// so if several of them match the one with the lowest index wins, like in [`Any`].
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(any(windows, feature = "async"), doc = "```ignore")]
+    #[cfg_attr(not(any(windows, feature = "async")), doc = "```")]
+    /// use expectrl::{spawn, Expect, Needle};
+    ///
+    /// let mut proc = spawn("echo no").unwrap();
+    /// let needles: [Box<dyn Needle>; 2] = [Box::new("yes"), Box::new("no")];
+    /// let (index, _) = proc.expect_any(needles).unwrap();
+    /// assert_eq!(index, 1);
+    /// ```
+    ///
+    /// [`Any`]: crate::Any
+    fn expect_any<I>(&mut self, needles: I) -> Result<(usize, Captures), Error>
+    where
+        I: IntoIterator<Item = Box<dyn Needle>>,
+    {
+        let needles = needles.into_iter().collect::<Vec<_>>();
+        let found = self.expect(Any(needles))?;
+        let index = found.needle_index().unwrap_or_default();
+        Ok((index, found))
+    }
+
+    /// Sends an interrupt (Ctrl-C), which is [`ControlCode::EndOfText`] (`ETX`, `0x03`).
+    ///
+    /// A terminal turns it into a `SIGINT` unless a program disabled it.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(any(windows, feature = "async"), doc = "```ignore")]
+    #[cfg_attr(not(any(windows, feature = "async")), doc = "```")]
+    /// use expectrl::{spawn, Expect};
+    ///
+    /// let mut proc = spawn("cat").unwrap();
+    /// proc.interrupt().unwrap();
+    /// ```
+    ///
+    /// [`ControlCode::EndOfText`]: crate::ControlCode::EndOfText
+    fn interrupt(&mut self) -> Result<(), Error> {
+        self.send(ControlCode::EndOfText)
+    }
+
+    /// Sends an end of file (Ctrl-D), which is [`ControlCode::EndOfTransmission`] (`EOT`, `0x04`).
+    ///
+    /// A terminal in a canonical mode turns it into an EOF for a reading program.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(any(windows, feature = "async"), doc = "```ignore")]
+    #[cfg_attr(not(any(windows, feature = "async")), doc = "```")]
+    /// use expectrl::{spawn, Eof, Expect};
+    ///
+    /// let mut proc = spawn("cat").unwrap();
+    /// proc.eof().unwrap();
+    /// proc.expect(Eof).unwrap();
+    /// ```
+    ///
+    /// [`ControlCode::EndOfTransmission`]: crate::ControlCode::EndOfTransmission
+    fn eof(&mut self) -> Result<(), Error> {
+        self.send(ControlCode::EndOfTransmission)
+    }
+
+    /// Sends a suspend (Ctrl-Z), which is [`ControlCode::Substitute`] (`SUB`, `0x1A`).
+    ///
+    /// A terminal turns it into a `SIGTSTP` unless a program disabled it.
+    /// The signal is discarded for an orphaned process group,
+    /// so a spawned program itself isn't stopped, but a job of a spawned shell is.
+    ///
+    /// [`ControlCode::Substitute`]: crate::ControlCode::Substitute
+    fn suspend(&mut self) -> Result<(), Error> {
+        self.send(ControlCode::Substitute)
+    }
 }
 
 impl<T> Expect for &mut T
@@ -195,6 +313,18 @@ where
     {
         T::send_line(self, buf)
     }
+
+    fn interrupt(&mut self) -> Result<(), Error> {
+        T::interrupt(self)
+    }
+
+    fn eof(&mut self) -> Result<(), Error> {
+        T::eof(self)
+    }
+
+    fn suspend(&mut self) -> Result<(), Error> {
+        T::suspend(self)
+    }
 }
 
 #[cfg(feature = "async")]
@@ -220,8 +350,9 @@ pub trait AsyncExpect {
     ///
     /// # Example
     ///
-    #[cfg_attr(windows, doc = "```no_run")]
-    #[cfg_attr(unix, doc = "```")]
+    #[cfg_attr(not(feature = "regex"), doc = "```ignore")]
+    #[cfg_attr(all(windows, feature = "regex"), doc = "```no_run")]
+    #[cfg_attr(all(unix, feature = "regex"), doc = "```")]
     /// # futures_lite::future::block_on(async {
     /// use expectrl::{AsyncExpect, spawn, Regex};
     ///
@@ -231,8 +362,9 @@ pub trait AsyncExpect {
     /// # });
     /// ```
     ///
-    #[cfg_attr(windows, doc = "```no_run")]
-    #[cfg_attr(unix, doc = "```")]
+    #[cfg_attr(not(feature = "regex"), doc = "```ignore")]
+    #[cfg_attr(all(windows, feature = "regex"), doc = "```no_run")]
+    #[cfg_attr(all(unix, feature = "regex"), doc = "```")]
     /// # futures_lite::future::block_on(async {
     /// use expectrl::{AsyncExpect, spawn, Regex};
     ///
@@ -247,6 +379,18 @@ pub trait AsyncExpect {
     ///
     /// It returns an error if timeout is reached.
     /// You can specify a timeout value by [Session::set_expect_timeout] method.
+    ///
+    /// Output which a process wrote before it exited can still be matched,
+    /// even if the process had exited (and was waited for) before the first call.
+    /// Be aware that macOS may discard the output of a process which is gone,
+    /// so there it's better to expect the output before waiting for the process.
+    ///
+    /// # Cancellation
+    ///
+    /// The method is cancellation safe.
+    /// If the future is dropped before it's resolved (e.g. in a `select!`),
+    /// bytes which were already read are kept in the buffer,
+    /// so a next call will see them.
     async fn expect<N>(&mut self, needle: N) -> Result<Captures, Error>
     where
         N: Needle;
@@ -258,8 +402,15 @@ pub trait AsyncExpect {
     /// But its strategy of matching is different from it.
     /// It makes search agains all bytes available.
     ///
-    #[cfg_attr(any(target_os = "macos", windows), doc = "```no_run")]
-    #[cfg_attr(not(any(target_os = "macos", windows)), doc = "```")]
+    #[cfg_attr(not(feature = "regex"), doc = "```ignore")]
+    #[cfg_attr(
+        all(any(target_os = "macos", windows), feature = "regex"),
+        doc = "```no_run"
+    )]
+    #[cfg_attr(
+        all(not(any(target_os = "macos", windows)), feature = "regex"),
+        doc = "```"
+    )]
     /// # futures_lite::future::block_on(async {
     /// use expectrl::{AsyncExpect, spawn, Regex};
     ///
@@ -319,6 +470,57 @@ pub trait AsyncExpect {
     async fn send_line<B>(&mut self, buf: B) -> Result<(), Error>
     where
         B: AsRef<[u8]>;
+
+    /// Expects any of given needles,
+    /// returning an index of the needle which matched along with the captures.
+    ///
+    /// Needles are checked in order,
+    /// so if several of them match the one with the lowest index wins, like in [`Any`].
+    ///
+    /// [`Any`]: crate::Any
+    fn expect_any<I>(
+        &mut self,
+        needles: I,
+    ) -> impl Future<Output = Result<(usize, Captures), Error>>
+    where
+        I: IntoIterator<Item = Box<dyn Needle>>,
+    {
+        let needles = needles.into_iter().collect::<Vec<_>>();
+        async move {
+            let found = self.expect(Any(needles)).await?;
+            let index = found.needle_index().unwrap_or_default();
+            Ok((index, found))
+        }
+    }
+
+    /// Sends an interrupt (Ctrl-C), which is [`ControlCode::EndOfText`] (`ETX`, `0x03`).
+    ///
+    /// A terminal turns it into a `SIGINT` unless a program disabled it.
+    ///
+    /// [`ControlCode::EndOfText`]: crate::ControlCode::EndOfText
+    fn interrupt(&mut self) -> impl Future<Output = Result<(), Error>> {
+        self.send(ControlCode::EndOfText)
+    }
+
+    /// Sends an end of file (Ctrl-D), which is [`ControlCode::EndOfTransmission`] (`EOT`, `0x04`).
+    ///
+    /// A terminal in a canonical mode turns it into an EOF for a reading program.
+    ///
+    /// [`ControlCode::EndOfTransmission`]: crate::ControlCode::EndOfTransmission
+    fn eof(&mut self) -> impl Future<Output = Result<(), Error>> {
+        self.send(ControlCode::EndOfTransmission)
+    }
+
+    /// Sends a suspend (Ctrl-Z), which is [`ControlCode::Substitute`] (`SUB`, `0x1A`).
+    ///
+    /// A terminal turns it into a `SIGTSTP` unless a program disabled it.
+    /// The signal is discarded for an orphaned process group,
+    /// so a spawned program itself isn't stopped, but a job of a spawned shell is.
+    ///
+    /// [`ControlCode::Substitute`]: crate::ControlCode::Substitute
+    fn suspend(&mut self) -> impl Future<Output = Result<(), Error>> {
+        self.send(ControlCode::Substitute)
+    }
 }
 
 #[cfg(feature = "async")]
@@ -360,4 +562,117 @@ where
     {
         T::send_line(self, buf).await
     }
+
+    async fn interrupt(&mut self) -> Result<(), Error> {
+        T::interrupt(self).await
+    }
+
+    async fn eof(&mut self) -> Result<(), Error> {
+        T::eof(self).await
+    }
+
+    async fn suspend(&mut self) -> Result<(), Error> {
+        T::suspend(self).await
+    }
+}
+
+/// SyncAdapter provides a blocking [`Expect`] implementation on top of an [`AsyncExpect`] one.
+///
+/// It's handy when the `async` feature is on but some code needs to work synchronously.
+/// Each call is run to completion via [`futures_lite::future::block_on`].
+///
+/// It must not be used from within an async context,
+/// as it blocks the thread and so may block an executor.
+/// For the same reason with `tokio` feature the session must be created outside of a tokio runtime,
+/// otherwise it's bound to the runtime's reactor which isn't driven while the thread is blocked.
+///
+/// # Example
+///
+/// ```no_run
+/// use expectrl::{spawn, Expect, SyncAdapter};
+///
+/// let mut p = SyncAdapter::new(spawn("cat").unwrap());
+/// p.send_line("Hello World").unwrap();
+/// p.expect("Hello World").unwrap();
+/// ```
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct SyncAdapter<T> {
+    inner: T,
+}
+
+#[cfg(feature = "async")]
+impl<T> SyncAdapter<T> {
+    /// Creates a new adapter.
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    /// Returns a reference to the wrapped value.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped value.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped value.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> Expect for SyncAdapter<T>
+where
+    T: AsyncExpect,
+{
+    fn expect<N>(&mut self, needle: N) -> Result<Captures, Error>
+    where
+        N: Needle,
+    {
+        futures_lite::future::block_on(self.inner.expect(needle))
+    }
+
+    fn check<N>(&mut self, needle: N) -> Result<Captures, Error>
+    where
+        N: Needle,
+    {
+        futures_lite::future::block_on(self.inner.check(needle))
+    }
+
+    fn is_matched<N>(&mut self, needle: N) -> Result<bool, Error>
+    where
+        N: Needle,
+    {
+        futures_lite::future::block_on(self.inner.is_matched(needle))
+    }
+
+    fn send<B>(&mut self, buf: B) -> Result<(), Error>
+    where
+        B: AsRef<[u8]>,
+    {
+        futures_lite::future::block_on(self.inner.send(buf))
+    }
+
+    fn send_line<B>(&mut self, buf: B) -> Result<(), Error>
+    where
+        B: AsRef<[u8]>,
+    {
+        futures_lite::future::block_on(self.inner.send_line(buf))
+    }
+
+    fn interrupt(&mut self) -> Result<(), Error> {
+        futures_lite::future::block_on(self.inner.interrupt())
+    }
+
+    fn eof(&mut self) -> Result<(), Error> {
+        futures_lite::future::block_on(self.inner.eof())
+    }
+
+    fn suspend(&mut self) -> Result<(), Error> {
+        futures_lite::future::block_on(self.inner.suspend())
+    }
 }
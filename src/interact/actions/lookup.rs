@@ -1,6 +1,6 @@
 //! The module contains [`Lookup`].
 
-use crate::{Captures, Error, Needle};
+use crate::{needle::capture, Captures, Error, Needle};
 
 /// A helper action for an [`InteractSession`]
 ///
@@ -48,15 +48,10 @@ where
 {
     // we ignore the check if buf is empty in just in case someone is matching 0 bytes.
 
-    let found = needle.check(buf, eof)?;
-    if found.is_empty() {
-        return Ok(None);
+    let found = capture(&needle, buf, eof)?;
+    if let Some(found) = &found {
+        let _ = buf.drain(..found.as_bytes().len());
     }
 
-    let end_index = Captures::right_most_index(&found);
-    let involved_bytes = buf[..end_index].to_vec();
-    let found = Captures::new(involved_bytes, found);
-    let _ = buf.drain(..end_index);
-
-    Ok(Some(found))
+    Ok(found)
 }
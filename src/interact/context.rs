@@ -25,6 +25,10 @@ pub struct Context<'a, Session, Input, Output, State> {
     pub buf: &'a [u8],
     /// A flag for EOF of a user session or running process.
     pub eof: bool,
+    /// A number of bytes read from the input so far.
+    pub bytes_in: usize,
+    /// A number of bytes read from the running process so far.
+    pub bytes_out: usize,
 }
 
 impl<'a, Session, Input, Output, State> Context<'a, Session, Input, Output, State> {
@@ -44,6 +48,8 @@ impl<'a, Session, Input, Output, State> Context<'a, Session, Input, Output, Stat
             buf,
             eof,
             state,
+            bytes_in: 0,
+            bytes_out: 0,
         }
     }
 }
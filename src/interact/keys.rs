@@ -0,0 +1,219 @@
+//! This module contains a key map which intercepts user's keys in an interact session.
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+type KeyCallback = Box<dyn FnMut(&[u8]) -> Vec<u8>>;
+
+/// An action which is taken when a key from a key map is pressed.
+///
+/// See [`InteractSession::set_key_map`].
+///
+/// [`InteractSession::set_key_map`]: crate::interact::InteractSession::set_key_map
+pub enum KeyAction {
+    /// The key is sent to the process as it is.
+    PassThrough,
+    /// The key is swallowed, nothing is sent to the process.
+    Drop,
+    /// The callback is called with the key,
+    /// and the bytes it returns are sent to the process instead of the key.
+    ///
+    /// An empty vector means nothing is sent.
+    Callback(KeyCallback),
+}
+
+impl fmt::Debug for KeyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PassThrough => write!(f, "PassThrough"),
+            Self::Drop => write!(f, "Drop"),
+            Self::Callback(_) => write!(f, "Callback"),
+        }
+    }
+}
+
+/// A time a partially matched key is held for waiting for the rest of it.
+///
+/// Keys which consist of multiple bytes are usually sent at once,
+/// so if nothing comes in this time the held bytes are treated as they are, e.g. a lone `ESC`.
+pub(crate) const KEY_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// KeyMap looks up keys in user's input and applies their actions.
+///
+/// A key may consist of multiple bytes, e.g. an escape sequence of a function key.
+/// A partially matched key is kept between reads, until [`KEY_TIMEOUT`] passes.
+#[derive(Debug, Default)]
+pub(crate) struct KeyMap {
+    keys: HashMap<Vec<u8>, KeyAction>,
+    pending: Vec<u8>,
+    pending_since: Option<Instant>,
+}
+
+impl KeyMap {
+    pub(crate) fn new(keys: HashMap<Vec<u8>, KeyAction>) -> Self {
+        Self {
+            keys,
+            pending: Vec::new(),
+            pending_since: None,
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.pending.clear();
+        self.pending_since = None;
+    }
+
+    /// Returns bytes which must be sent to a process.
+    ///
+    /// If a few keys match the longest one is used.
+    /// Bytes at the end of the buffer which may be a beginning of a key are held until the next call.
+    pub(crate) fn apply<'a>(&mut self, buf: &'a [u8]) -> Cow<'a, [u8]> {
+        if self.keys.is_empty() {
+            return Cow::Borrowed(buf);
+        }
+
+        let mut data = std::mem::take(&mut self.pending);
+        data.extend_from_slice(buf);
+
+        Cow::Owned(self.resolve(&data, true))
+    }
+
+    /// Returns the held bytes, applying keys they contain, so nothing is left pending.
+    ///
+    /// It must be called once the input is over.
+    pub(crate) fn flush(&mut self) -> Vec<u8> {
+        let data = std::mem::take(&mut self.pending);
+        self.resolve(&data, false)
+    }
+
+    /// Returns the held bytes if they were held longer than [`KEY_TIMEOUT`],
+    /// like [`KeyMap::flush`] does.
+    pub(crate) fn flush_expired(&mut self) -> Option<Vec<u8>> {
+        match self.pending_timeout() {
+            Some(timeout) if timeout.is_zero() => Some(self.flush()),
+            _ => None,
+        }
+    }
+
+    /// Returns a time left until the held bytes must be flushed,
+    /// it's `None` if nothing is held.
+    pub(crate) fn pending_timeout(&self) -> Option<Duration> {
+        if self.pending.is_empty() {
+            return None;
+        }
+
+        let since = self.pending_since?;
+        Some(KEY_TIMEOUT.saturating_sub(since.elapsed()))
+    }
+
+    fn resolve(&mut self, data: &[u8], hold: bool) -> Vec<u8> {
+        self.pending_since = None;
+
+        let mut out = Vec::with_capacity(data.len());
+        let mut i = 0;
+        while i < data.len() {
+            let rest = &data[i..];
+
+            let is_partial = hold
+                && self
+                    .keys
+                    .keys()
+                    .any(|key| key.len() > rest.len() && key.starts_with(rest));
+            if is_partial {
+                self.pending = rest.to_vec();
+                self.pending_since = Some(Instant::now());
+                break;
+            }
+
+            let key = self
+                .keys
+                .keys()
+                .filter(|key| !key.is_empty() && rest.starts_with(key))
+                .max_by_key(|key| key.len())
+                .cloned();
+
+            let key = match key {
+                Some(key) => key,
+                None => {
+                    out.push(data[i]);
+                    i += 1;
+                    continue;
+                }
+            };
+
+            match self.keys.get_mut(&key) {
+                Some(KeyAction::PassThrough) => out.extend_from_slice(&key),
+                Some(KeyAction::Drop) | None => {}
+                Some(KeyAction::Callback(f)) => out.extend(f(&key)),
+            }
+
+            i += key.len();
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_map() {
+        let mut keys = HashMap::new();
+        let _ = keys.insert(b"\x0c".to_vec(), KeyAction::Drop);
+        let _ = keys.insert(
+            b"\x1bOP".to_vec(),
+            KeyAction::Callback(Box::new(|_| b"help\n".to_vec())),
+        );
+        let _ = keys.insert(b"\x1b".to_vec(), KeyAction::PassThrough);
+        let mut map = KeyMap::new(keys);
+
+        assert_eq!(map.apply(b"ls\x0c\n"), Cow::Borrowed(&b"ls\n"[..]));
+        assert_eq!(map.apply(b"a\x1bOPb"), Cow::Borrowed(&b"ahelp\nb"[..]));
+
+        // a key split across reads
+        assert_eq!(map.apply(b"a\x1b"), Cow::Borrowed(&b"a"[..]));
+        assert_eq!(map.apply(b"O"), Cow::Borrowed(&b""[..]));
+        assert_eq!(map.apply(b"P"), Cow::Borrowed(&b"help\n"[..]));
+
+        // a prefix which didn't become a longer key
+        assert_eq!(map.apply(b"\x1b"), Cow::Borrowed(&b""[..]));
+        assert_eq!(map.apply(b"x"), Cow::Borrowed(&b"\x1bx"[..]));
+
+        let mut map = KeyMap::default();
+        assert_eq!(map.apply(b"\x0c"), Cow::Borrowed(&b"\x0c"[..]));
+    }
+
+    #[test]
+    fn test_key_map_flush() {
+        let mut keys = HashMap::new();
+        let _ = keys.insert(
+            b"\x1bOP".to_vec(),
+            KeyAction::Callback(Box::new(|_| b"help\n".to_vec())),
+        );
+        let _ = keys.insert(b"O".to_vec(), KeyAction::Drop);
+        let mut map = KeyMap::new(keys);
+
+        assert_eq!(map.pending_timeout(), None);
+        assert_eq!(map.flush_expired(), None);
+
+        // a lone prefix is sent once the input is over
+        assert_eq!(map.apply(b"a\x1bO"), Cow::Borrowed(&b"a"[..]));
+        assert!(map.pending_timeout().is_some());
+        assert_eq!(map.flush(), b"\x1b".to_vec());
+        assert_eq!(map.pending_timeout(), None);
+
+        // or once it's held for too long
+        assert_eq!(map.apply(b"\x1b"), Cow::Borrowed(&b""[..]));
+        assert_eq!(map.flush_expired(), None);
+        std::thread::sleep(KEY_TIMEOUT);
+        assert_eq!(map.pending_timeout(), Some(Duration::ZERO));
+        assert_eq!(map.flush_expired(), Some(b"\x1b".to_vec()));
+        assert_eq!(map.flush_expired(), None);
+    }
+}
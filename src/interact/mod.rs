@@ -1,7 +1,13 @@
 //! This module contains a routines for running and utilizing an interacting session with a [`Session`].
 //!
-#![cfg_attr(all(unix, not(feature = "async")), doc = "```no_run")]
-#![cfg_attr(not(all(unix, not(feature = "async"))), doc = "```ignore")]
+#![cfg_attr(
+    all(unix, not(feature = "async"), feature = "regex"),
+    doc = "```no_run"
+)]
+#![cfg_attr(
+    not(all(unix, not(feature = "async"), feature = "regex")),
+    doc = "```ignore"
+)]
 //! use expectrl::{
 //!     interact::actions::lookup::Lookup,
 //!     spawn, stream::stdin::Stdin, Regex
@@ -49,7 +55,9 @@
 
 pub mod actions;
 mod context;
+mod keys;
 mod session;
 
 pub use context::Context;
+pub use keys::KeyAction;
 pub use session::InteractSession;
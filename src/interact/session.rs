@@ -4,17 +4,16 @@
 
 use std::{
     borrow::Cow,
-    io::{ErrorKind, Write},
+    collections::HashMap,
+    io::{self, ErrorKind, Write},
+    time::Instant,
 };
 
 #[cfg(not(feature = "async"))]
 use std::io::Read;
 
 #[cfg(feature = "async")]
-use std::{io, time::Duration};
-
-#[cfg(feature = "async")]
-use futures_timer::Delay;
+use std::time::Duration;
 
 #[cfg(feature = "async")]
 use futures_lite::{
@@ -34,7 +33,7 @@ use crate::Expect;
 #[cfg(feature = "async")]
 use crate::AsyncExpect;
 
-use crate::interact::Context;
+use crate::interact::{keys::KeyMap, Context, KeyAction};
 #[cfg(all(not(feature = "async"), not(feature = "polling")))]
 use crate::process::NonBlocking;
 
@@ -48,7 +47,15 @@ pub struct InteractSession<Session, Input, Output, State> {
     session: Session,
     input: Input,
     output: Output,
-    escape_character: u8,
+    escape: EscapeSequence,
+    keys: KeyMap,
+    bytes_in: usize,
+    bytes_out: usize,
+    transcript: Option<Transcript>,
+    outputs: Outputs,
+    echo_input: bool,
+    #[cfg(all(unix, not(feature = "async")))]
+    forward_resize: Option<ResizeFn<Session>>,
     #[cfg(unix)]
     status: Option<WaitStatus>,
     opts: InteractOptions<Session, Input, Output, State>,
@@ -68,6 +75,9 @@ type OptAction<S, I, O, C> = Box<dyn FnMut(Context<'_, S, I, O, C>) -> ExpectRes
 
 type OptFilter = Box<dyn FnMut(&[u8]) -> ExpectResult<Cow<'_, [u8]>>>;
 
+#[cfg(all(unix, not(feature = "async")))]
+type ResizeFn<S> = fn(&mut S, u16, u16) -> Result<(), Error>;
+
 impl<S, I, O, C> InteractSession<S, I, O, C> {
     /// Default escape character. <Ctrl-\]>
     pub const ESCAPE: u8 = 29;
@@ -78,7 +88,15 @@ impl<S, I, O, C> InteractSession<S, I, O, C> {
             input,
             output,
             session,
-            escape_character: Self::ESCAPE,
+            escape: EscapeSequence::new(vec![Self::ESCAPE]),
+            keys: KeyMap::default(),
+            bytes_in: 0,
+            bytes_out: 0,
+            transcript: None,
+            outputs: Outputs::default(),
+            echo_input: false,
+            #[cfg(all(unix, not(feature = "async")))]
+            forward_resize: None,
             opts: InteractOptions {
                 state,
                 input_filter: None,
@@ -94,8 +112,40 @@ impl<S, I, O, C> InteractSession<S, I, O, C> {
 
     /// Sets an escape character after seen which the interact interactions will be stopped
     /// and controll will be returned to a caller process.
+    ///
+    /// See [`InteractSession::set_escape_sequence`] to use a few keys, e.g. tmux-like `Ctrl-A q`.
     pub fn set_escape_character(mut self, c: u8) -> Self {
-        self.escape_character = c;
+        self.escape = EscapeSequence::new(vec![c]);
+        self
+    }
+
+    /// Sets an escape sequence after seen which the interact interactions will be stopped
+    /// and controll will be returned to a caller process.
+    ///
+    /// The sequence may consist of multiple bytes, e.g. `b"~."` like in SSH.
+    /// It's matched across separate reads.
+    /// Bytes which were partially matched but didn't complete the sequence are still sent to the process.
+    ///
+    /// An empty sequence disables the escape.
+    pub fn set_escape_sequence<B>(mut self, sequence: B) -> Self
+    where
+        B: AsRef<[u8]>,
+    {
+        self.escape = EscapeSequence::new(sequence.as_ref().to_vec());
+        self
+    }
+
+    /// Sets a key map which intercepts user's keys before they are sent to the process.
+    ///
+    /// Each key is a sequence of bytes, e.g. `b"\x1bOP"` for `F1`,
+    /// and its [`KeyAction`] decides whether it's passed through, dropped or handled by a callback.
+    /// If a few keys match the longest one is used.
+    ///
+    /// A key may be split across separate reads,
+    /// so bytes which may be a beginning of a key are held until the next input arrives.
+    /// The escape sequence is checked before the key map.
+    pub fn set_key_map(&mut self, keys: HashMap<Vec<u8>, KeyAction>) -> &mut Self {
+        self.keys = KeyMap::new(keys);
         self
     }
 
@@ -109,13 +159,41 @@ impl<S, I, O, C> InteractSession<S, I, O, C> {
     pub fn get_status(&self) -> Option<WaitStatus> {
         self.status
     }
+
+    /// Returns a number of bytes read from the input during the last [`Self::spawn`] call.
+    ///
+    /// The bytes are counted before filters are applied.
+    ///
+    /// [`Self::spawn`]: crate::interact::InteractSession::spawn
+    pub fn bytes_in(&self) -> usize {
+        self.bytes_in
+    }
+
+    /// Returns a number of bytes read from the process during the last [`Self::spawn`] call.
+    ///
+    /// The bytes are counted before filters are applied.
+    ///
+    /// [`Self::spawn`]: crate::interact::InteractSession::spawn
+    pub fn bytes_out(&self) -> usize {
+        self.bytes_out
+    }
 }
 
 impl<S, I, O, C> InteractSession<S, I, O, C> {
     /// Set a state
     pub fn with_state<State>(self, state: State) -> InteractSession<S, I, O, State> {
         let mut s = InteractSession::new(self.session, self.input, self.output, state);
-        s.escape_character = self.escape_character;
+        s.escape = self.escape;
+        s.keys = self.keys;
+        s.bytes_in = self.bytes_in;
+        s.bytes_out = self.bytes_out;
+        s.transcript = self.transcript;
+        s.outputs = self.outputs;
+        s.echo_input = self.echo_input;
+        #[cfg(all(unix, not(feature = "async")))]
+        {
+            s.forward_resize = self.forward_resize;
+        }
         #[cfg(unix)]
         {
             s.status = self.status;
@@ -190,6 +268,59 @@ impl<S, I, O, C> InteractSession<S, I, O, C> {
         self
     }
 
+    /// Sets a writer for a transcript of the session.
+    ///
+    /// Each chunk of input and output is written on a separate line
+    /// prefixed with a time elapsed since the call (in seconds) and a direction.
+    /// Bytes are logged as they were read, before filters are applied.
+    ///
+    /// ```text
+    /// [0.512] input: "ls\r"
+    /// [0.514] output: "ls\r\nCargo.toml  src\r\n"
+    /// ```
+    ///
+    /// Errors of the writer are ignored.
+    pub fn set_log<W>(&mut self, writer: W) -> &mut Self
+    where
+        W: Write + 'static,
+    {
+        self.transcript = Some(Transcript::new(Box::new(writer)));
+        self
+    }
+
+    /// Adds an output which will get a copy of everything written to the main output.
+    ///
+    /// It may be used to mirror a session, e.g. to let others watch it.
+    /// By default a failure of any added output aborts the session,
+    /// see [`Self::set_ignore_output_errors`].
+    pub fn add_output<W>(&mut self, writer: W) -> &mut Self
+    where
+        W: Write + 'static,
+    {
+        self.outputs.writers.push(Box::new(writer));
+        self
+    }
+
+    /// Sets whether user's input is written to the output after it's sent to the process.
+    ///
+    /// It makes the input visible regardless of the terminal ECHO setting,
+    /// e.g. when a program turned echo off for a password prompt and didn't turn it back on.
+    /// Notice that if the terminal echoes the input it will be shown twice.
+    ///
+    /// Default is `false`.
+    pub fn set_echo_input(&mut self, on: bool) -> &mut Self {
+        self.echo_input = on;
+        self
+    }
+
+    /// Sets whether failures of outputs added via [`Self::add_output`] are ignored.
+    ///
+    /// Errors of the main output are never ignored.
+    pub fn set_ignore_output_errors(&mut self, ignore: bool) -> &mut Self {
+        self.outputs.ignore_errors = ignore;
+        self
+    }
+
     /// Puts a handler which will be called on each interaction when no input is detected.
     pub fn set_idle_action<F>(&mut self, action: F) -> &mut Self
     where
@@ -200,6 +331,28 @@ impl<S, I, O, C> InteractSession<S, I, O, C> {
     }
 }
 
+#[cfg(all(unix, not(feature = "async")))]
+impl<St, I, O, C>
+    InteractSession<&mut crate::Session<crate::process::unix::UnixProcess, St>, I, O, C>
+{
+    /// Sets whether a size of the terminal the interact session runs in is forwarded to the process,
+    /// so full-screen programs (e.g. `vim` or `htop`) are redrawn properly when the terminal is resized.
+    ///
+    /// The size is taken from `STDIN` or `STDOUT`, whichever is a terminal,
+    /// and it's set on the process when the session starts and every time it changes,
+    /// see [`Session::set_window_size`](crate::Session::set_window_size).
+    /// Nothing is done if neither of them is a terminal.
+    ///
+    /// Default is `false`.
+    pub fn forward_resize(&mut self, on: bool) -> &mut Self {
+        self.forward_resize = match on {
+            true => Some(|session, cols, rows| session.set_window_size(cols, rows)),
+            false => None,
+        };
+        self
+    }
+}
+
 #[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
 impl<S, I, O, C> InteractSession<S, I, O, C>
 where
@@ -333,6 +486,48 @@ where
     }
 }
 
+#[cfg(all(unix, feature = "async"))]
+impl<S, I, O, C> InteractSession<S, I, O, C>
+where
+    I: AsyncRead + Unpin,
+    O: AsyncWrite + Unpin,
+    S: Healthcheck<Status = WaitStatus> + AsyncWrite + AsyncRead + Unpin,
+{
+    /// Runs the session as a plain async byte bridge between the streams.
+    ///
+    /// Unlike [`InteractSession::spawn`] it makes no assumptions about a terminal,
+    /// so echo and other terminal settings of the process are left untouched
+    /// and the session isn't required to be a pty.
+    /// It's handy for proxying a process to a remote peer, e.g. over a websocket.
+    ///
+    /// It stops once either of the streams reaches an EOF,
+    /// the process exits or an action asks to stop.
+    pub async fn spawn_headless(&mut self) -> Result<bool, Error> {
+        interact_async(self).await
+    }
+}
+
+#[cfg(all(windows, feature = "async"))]
+impl<S, I, O, C> InteractSession<S, I, O, C>
+where
+    I: AsyncRead + Unpin,
+    O: AsyncWrite + Unpin,
+    S: Healthcheck + AsyncWrite + AsyncRead + Unpin,
+{
+    /// Runs the session as a plain async byte bridge between the streams.
+    ///
+    /// Unlike [`InteractSession::spawn`] it makes no assumptions about a terminal,
+    /// so echo and other terminal settings of the process are left untouched
+    /// and the session isn't required to be a pty.
+    /// It's handy for proxying a process to a remote peer, e.g. over a websocket.
+    ///
+    /// It stops once either of the streams reaches an EOF,
+    /// the process exits or an action asks to stop.
+    pub async fn spawn_headless(&mut self) -> Result<bool, Error> {
+        interact_async(self).await
+    }
+}
+
 #[cfg(all(windows, feature = "polling", not(feature = "async")))]
 impl<I, O, C> InteractSession<crate::session::OsSession, I, O, C>
 where
@@ -362,7 +557,18 @@ where
         let _ = s.field("session", &self.session)
             .field("input", &self.input)
             .field("output", &self.output)
-            .field("escape_character", &self.escape_character);
+            .field("escape_sequence", &self.escape.sequence)
+            .field("key_map", &self.keys)
+            .field("bytes_in", &self.bytes_in)
+            .field("bytes_out", &self.bytes_out)
+            .field("transcript", &self.transcript.is_some())
+            .field("outputs", &self.outputs.writers.len())
+            .field("echo_input", &self.echo_input);
+
+        #[cfg(all(unix, not(feature = "async")))]
+        {
+            let _ = s.field("forward_resize", &self.forward_resize.is_some());
+        }
 
         #[cfg(unix)]
         {
@@ -389,17 +595,32 @@ where
     I: Read,
 {
     let mut buf = [0; 512];
+    let mut window_size = None;
+    s.escape.reset();
+    s.keys.reset();
+    s.bytes_in = 0;
+    s.bytes_out = 0;
 
     loop {
+        if let Some(keys) = s.keys.flush_expired() {
+            forward_input(s, &keys)?;
+        }
+
         let status = get_status(&s.session)?;
         if !matches!(status, Some(WaitStatus::StillAlive)) {
             s.status = status;
             return Ok(false);
         }
 
+        if let Some(resize) = s.forward_resize {
+            forward_window_size(&mut s.session, resize, &mut window_size)?;
+        }
+
         if let Some(n) = try_read(&mut s.session, &mut buf)? {
             let eof = n == 0;
+            s.bytes_out += n;
             let buf = &buf[..n];
+            record_transcript(&mut s.transcript, "output", buf);
             let buf = call_filter(s.opts.output_filter.as_mut(), buf)?;
 
             let exit = run_action_output(s, &buf, eof)?;
@@ -409,33 +630,36 @@ where
 
             spin_write(&mut s.output, &buf)?;
             spin_flush(&mut s.output)?;
+            s.outputs.write(&buf)?;
         }
 
-        // We dont't print user input back to the screen.
+        // User input isn't printed back to the screen unless `echo_input` is on.
         // In terminal mode it will be ECHOed back automatically.
         // This way we preserve terminal seetings for example when user inputs password.
         // The terminal must have been prepared before.
         match s.input.read(&mut buf) {
             Ok(n) => {
                 let eof = n == 0;
+                s.bytes_in += n;
                 let buf = &buf[..n];
+                record_transcript(&mut s.transcript, "input", buf);
                 let buf = call_filter(s.opts.input_filter.as_mut(), buf)?;
 
                 #[rustfmt::skip]
                 let exit = run_action_input(s, &buf, eof)?;
+                if eof {
+                    let keys = flush_pending_input(&mut s.escape, &mut s.keys);
+                    forward_input(s, &keys)?;
+                }
                 if eof | exit {
                     return Ok(true);
                 }
 
-                let escape_char_position = buf.iter().position(|c| *c == s.escape_character);
-                match escape_char_position {
-                    Some(pos) => {
-                        s.session.write_all(&buf[..pos])?;
-                        return Ok(true);
-                    }
-                    None => {
-                        s.session.write_all(&buf[..])?;
-                    }
+                let (buf, escaped) = s.escape.scan(&buf);
+                let buf = s.keys.apply(&buf);
+                forward_input(s, &buf)?;
+                if escaped {
+                    return Ok(true);
                 }
             }
             Err(err) if err.kind() == ErrorKind::WouldBlock => {}
@@ -457,15 +681,25 @@ where
     I: Read,
 {
     let mut buf = [0; 512];
+    s.escape.reset();
+    s.keys.reset();
+    s.bytes_in = 0;
+    s.bytes_out = 0;
 
     loop {
+        if let Some(keys) = s.keys.flush_expired() {
+            forward_input(s, &keys)?;
+        }
+
         if !s.session.is_alive()? {
             return Ok(false);
         }
 
         if let Some(n) = try_read(&mut s.session, &mut buf)? {
             let eof = n == 0;
+            s.bytes_out += n;
             let buf = &buf[..n];
+            record_transcript(&mut s.transcript, "output", buf);
             let buf = call_filter(s.opts.output_filter.as_mut(), buf)?;
 
             let exit = run_action_output(s, &buf, eof)?;
@@ -475,32 +709,35 @@ where
 
             spin_write(&mut s.output, &buf)?;
             spin_flush(&mut s.output)?;
+            s.outputs.write(&buf)?;
         }
 
-        // We dont't print user input back to the screen.
+        // User input isn't printed back to the screen unless `echo_input` is on.
         // In terminal mode it will be ECHOed back automatically.
         // This way we preserve terminal seetings for example when user inputs password.
         // The terminal must have been prepared before.
         match s.input.read(&mut buf) {
             Ok(n) => {
                 let eof = n == 0;
+                s.bytes_in += n;
                 let buf = &buf[..n];
+                record_transcript(&mut s.transcript, "input", buf);
                 let buf = call_filter(s.opts.input_filter.as_mut(), buf)?;
 
                 let exit = run_action_input(s, &buf, eof)?;
+                if eof {
+                    let keys = flush_pending_input(&mut s.escape, &mut s.keys);
+                    forward_input(s, &keys)?;
+                }
                 if eof | exit {
                     return Ok(true);
                 }
 
-                let escape_char_position = buf.iter().position(|c| *c == s.escape_character);
-                match escape_char_position {
-                    Some(pos) => {
-                        s.session.write_all(&buf[..pos])?;
-                        return Ok(true);
-                    }
-                    None => {
-                        s.session.write_all(&buf[..])?;
-                    }
+                let (buf, escaped) = s.escape.scan(&buf);
+                let buf = s.keys.apply(&buf);
+                forward_input(s, &buf)?;
+                if escaped {
+                    return Ok(true);
                 }
             }
             Err(err) if err.kind() == ErrorKind::WouldBlock => {}
@@ -529,44 +766,68 @@ where
     poller.add(s.session.as_raw_fd(), Event::readable(1))?;
 
     let mut buf = [0; 512];
+    let mut window_size = None;
+    s.escape.reset();
+    s.keys.reset();
+    s.bytes_in = 0;
+    s.bytes_out = 0;
+
+    // A resize doesn't wake the poller up, so it's checked periodically.
+    let timeout = match s.forward_resize.is_some() {
+        true => RESIZE_CHECK_INTERVAL,
+        false => std::time::Duration::from_secs(5),
+    };
 
     // The event loop.
     let mut events = Vec::new();
     loop {
+        if let Some(keys) = s.keys.flush_expired() {
+            forward_input(s, &keys)?;
+        }
+
         let status = get_status(&s.session)?;
         if !matches!(status, Some(WaitStatus::StillAlive)) {
             s.status = status;
             return Ok(false);
         }
 
+        if let Some(resize) = s.forward_resize {
+            forward_window_size(&mut s.session, resize, &mut window_size)?;
+        }
+
         // Wait for at least one I/O event.
         events.clear();
-        let _ = poller.wait(&mut events, Some(std::time::Duration::from_secs(5)))?;
+        let wait_timeout = s.keys.pending_timeout().map_or(timeout, |t| t.min(timeout));
+        let _ = poller.wait(&mut events, Some(wait_timeout))?;
 
         for ev in &events {
             if ev.key == 0 {
-                // We dont't print user input back to the screen.
+                // User input isn't printed back to the screen unless `echo_input` is on.
                 // In terminal mode it will be ECHOed back automatically.
                 // This way we preserve terminal seetings for example when user inputs password.
                 // The terminal must have been prepared before.
                 match s.input.read(&mut buf) {
                     Ok(n) => {
                         let eof = n == 0;
+                        s.bytes_in += n;
                         let buf = &buf[..n];
+                        record_transcript(&mut s.transcript, "input", buf);
                         let buf = call_filter(s.opts.input_filter.as_mut(), buf)?;
 
                         let exit = run_action_input(s, &buf, eof)?;
+                        if eof {
+                            let keys = flush_pending_input(&mut s.escape, &mut s.keys);
+                            forward_input(s, &keys)?;
+                        }
                         if eof || exit {
                             return Ok(true);
                         }
 
-                        let escape_char_pos = buf.iter().position(|c| *c == s.escape_character);
-                        match escape_char_pos {
-                            Some(pos) => {
-                                s.session.write_all(&buf[..pos]).map_err(Error::IO)?;
-                                return Ok(true);
-                            }
-                            None => s.session.write_all(&buf[..])?,
+                        let (buf, escaped) = s.escape.scan(&buf);
+                        let buf = s.keys.apply(&buf);
+                        forward_input(s, &buf)?;
+                        if escaped {
+                            return Ok(true);
                         }
                     }
                     Err(err) if err.kind() == ErrorKind::WouldBlock => {}
@@ -581,7 +842,9 @@ where
                 match s.session.read(&mut buf) {
                     Ok(n) => {
                         let eof = n == 0;
+                        s.bytes_out += n;
                         let buf = &buf[..n];
+                        record_transcript(&mut s.transcript, "output", buf);
                         let buf = call_filter(s.opts.output_filter.as_mut(), buf)?;
 
                         let exit = run_action_output(s, &buf, eof)?;
@@ -592,6 +855,7 @@ where
 
                         spin_write(&mut s.output, &buf)?;
                         spin_flush(&mut s.output)?;
+                        s.outputs.write(&buf)?;
                     }
                     Err(err) if err.kind() == ErrorKind::WouldBlock => {}
                     Err(err) => return Err(err.into()),
@@ -629,8 +893,16 @@ where
         .try_clone()
         .map_err(to_io_error(""))?;
     let mut poller = Wait2::new(s.input.clone(), stream);
+    s.escape.reset();
+    s.keys.reset();
+    s.bytes_in = 0;
+    s.bytes_out = 0;
 
     loop {
+        if let Some(keys) = s.keys.flush_expired() {
+            forward_input(s, &keys)?;
+        }
+
         // In case where proceses exits we are trying to
         // fill buffer to run callbacks if there was something in.
         //
@@ -647,23 +919,26 @@ where
                     let buf = b.map_or([0], |b| [b]);
                     let eof = b.is_none();
                     let n = if eof { 0 } else { 1 };
+                    s.bytes_in += n;
                     let buf = &buf[..n];
+                    record_transcript(&mut s.transcript, "input", buf);
 
                     let buf = call_filter(s.opts.input_filter.as_mut(), buf)?;
 
                     let exit = run_action_input(s, &buf, eof)?;
+                    if eof {
+                        let keys = flush_pending_input(&mut s.escape, &mut s.keys);
+                        forward_input(s, &keys)?;
+                    }
                     if eof || exit {
                         return Ok(true);
                     }
 
-                    // todo: replace all of these by 1 by 1 write
-                    let escape_char_pos = buf.iter().position(|c| *c == s.escape_character);
-                    match escape_char_pos {
-                        Some(pos) => {
-                            s.session.write_all(&buf[..pos])?;
-                            return Ok(true);
-                        }
-                        None => s.session.write_all(&buf[..])?,
+                    let (buf, escaped) = s.escape.scan(&buf);
+                    let buf = s.keys.apply(&buf);
+                    forward_input(s, &buf)?;
+                    if escaped {
+                        return Ok(true);
                     }
                 }
                 Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
@@ -674,7 +949,9 @@ where
                     let buf = b.map_or([0], |b| [b]);
                     let eof = b.is_none();
                     let n = if eof { 0 } else { 1 };
+                    s.bytes_out += n;
                     let buf = &buf[..n];
+                    record_transcript(&mut s.transcript, "output", buf);
 
                     let buf = call_filter(s.opts.output_filter.as_mut(), buf)?;
 
@@ -685,6 +962,7 @@ where
 
                     s.output.write_all(&buf)?;
                     s.output.flush()?;
+                    s.outputs.write(&buf)?;
                 }
                 Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
                 Err(err) => return Err(err.into()),
@@ -716,8 +994,16 @@ where
     const TIMEOUT: Duration = Duration::from_secs(5);
     let mut input_buf = [0; 512];
     let mut proc_buf = [0; 512];
+    s.escape.reset();
+    s.keys.reset();
+    s.bytes_in = 0;
+    s.bytes_out = 0;
 
     loop {
+        if let Some(keys) = s.keys.flush_expired() {
+            forward_input(s, &keys).await?;
+        }
+
         let status = get_status(&s.session)?;
         if !matches!(status, Some(WaitStatus::StillAlive)) {
             s.status = status;
@@ -726,7 +1012,8 @@ where
 
         let read_process = async { (ReadFrom::Proc, s.session.read(&mut proc_buf).await) };
         let read_input = async { (ReadFrom::Input, s.input.read(&mut input_buf).await) };
-        let timeout = async { (ReadFrom::Timeout, async_timeout(TIMEOUT).await) };
+        let idle_timeout = s.keys.pending_timeout().map_or(TIMEOUT, |t| t.min(TIMEOUT));
+        let timeout = async { (ReadFrom::Timeout, async_timeout(idle_timeout).await) };
 
         let read_any = future::or(read_process, read_input);
         let read_output = future::or(read_any, timeout).await;
@@ -737,7 +1024,9 @@ where
             ReadFrom::Proc => {
                 let n = read_result?;
                 let eof = n == 0;
+                s.bytes_out += n;
                 let buf = &proc_buf[..n];
+                record_transcript(&mut s.transcript, "output", buf);
                 let buf = call_filter(s.opts.output_filter.as_mut(), buf)?;
 
                 let exit = run_action_output(s, &buf, eof)?;
@@ -746,36 +1035,41 @@ where
                     return Ok(true);
                 }
 
-                s.output.write(&buf).await?;
+                let _ = s.output.write(&buf).await?;
                 s.output.flush().await?;
+                s.outputs.write(&buf)?;
             }
             ReadFrom::Input => {
-                // We dont't print user input back to the screen.
+                // User input isn't printed back to the screen unless `echo_input` is on.
                 // In terminal mode it will be ECHOed back automatically.
                 // This way we preserve terminal seetings for example when user inputs password.
                 // The terminal must have been prepared before.
                 match read_result {
                     Ok(n) => {
                         let eof = n == 0;
+                        s.bytes_in += n;
                         let buf = &input_buf[..n];
+                        record_transcript(&mut s.transcript, "input", buf);
                         let buf = call_filter(s.opts.output_filter.as_mut(), buf)?;
 
                         let exit = run_action_input(s, &buf, eof)?;
+                        if eof {
+                            let keys = flush_pending_input(&mut s.escape, &mut s.keys);
+                            forward_input(s, &keys).await?;
+                        }
 
                         if eof || exit {
                             return Ok(true);
                         }
 
-                        let escape_char_pos = buf.iter().position(|c| *c == s.escape_character);
-                        match escape_char_pos {
-                            Some(pos) => {
-                                s.session.write_all(&buf[..pos]).await?;
-                                return Ok(true);
-                            }
-                            None => s.session.write_all(&buf[..]).await?,
+                        let (buf, escaped) = s.escape.scan(&buf);
+                        let buf = s.keys.apply(&buf);
+                        forward_input(s, &buf).await?;
+                        if escaped {
+                            return Ok(true);
                         }
                     }
-                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => {}
                     Err(err) => return Err(err.into()),
                 }
             }
@@ -806,15 +1100,24 @@ where
     const TIMEOUT: Duration = Duration::from_secs(5);
     let mut input_buf = [0; 512];
     let mut proc_buf = [0; 512];
+    s.escape.reset();
+    s.keys.reset();
+    s.bytes_in = 0;
+    s.bytes_out = 0;
 
     loop {
+        if let Some(keys) = s.keys.flush_expired() {
+            forward_input(s, &keys).await?;
+        }
+
         if !s.session.is_alive()? {
             return Ok(false);
         }
 
         let read_process = async { (ReadFrom::Proc, s.session.read(&mut proc_buf).await) };
         let read_input = async { (ReadFrom::Input, s.input.read(&mut input_buf).await) };
-        let timeout = async { (ReadFrom::Timeout, async_timeout(TIMEOUT).await) };
+        let idle_timeout = s.keys.pending_timeout().map_or(TIMEOUT, |t| t.min(TIMEOUT));
+        let timeout = async { (ReadFrom::Timeout, async_timeout(idle_timeout).await) };
 
         let read_any = future::or(read_process, read_input);
         let read_output = future::or(read_any, timeout).await;
@@ -825,7 +1128,9 @@ where
             ReadFrom::Proc => {
                 let n = read_result?;
                 let eof = n == 0;
+                s.bytes_out += n;
                 let buf = &proc_buf[..n];
+                record_transcript(&mut s.transcript, "output", buf);
                 let buf = call_filter(s.opts.output_filter.as_mut(), buf)?;
 
                 let exit = run_action_output(s, &buf, eof)?;
@@ -836,34 +1141,39 @@ where
 
                 s.output.write(&buf).await?;
                 s.output.flush().await?;
+                s.outputs.write(&buf)?;
             }
             ReadFrom::Input => {
-                // We dont't print user input back to the screen.
+                // User input isn't printed back to the screen unless `echo_input` is on.
                 // In terminal mode it will be ECHOed back automatically.
                 // This way we preserve terminal seetings for example when user inputs password.
                 // The terminal must have been prepared before.
                 match read_result {
                     Ok(n) => {
                         let eof = n == 0;
+                        s.bytes_in += n;
                         let buf = &input_buf[..n];
+                        record_transcript(&mut s.transcript, "input", buf);
                         let buf = call_filter(s.opts.output_filter.as_mut(), buf)?;
 
                         let exit = run_action_input(s, &buf, eof)?;
+                        if eof {
+                            let keys = flush_pending_input(&mut s.escape, &mut s.keys);
+                            forward_input(s, &keys).await?;
+                        }
 
                         if eof || exit {
                             return Ok(true);
                         }
 
-                        let escape_char_pos = buf.iter().position(|c| *c == s.escape_character);
-                        match escape_char_pos {
-                            Some(pos) => {
-                                s.session.write_all(&buf[..pos]).await?;
-                                return Ok(true);
-                            }
-                            None => s.session.write_all(&buf[..]).await?,
+                        let (buf, escaped) = s.escape.scan(&buf);
+                        let buf = s.keys.apply(&buf);
+                        forward_input(s, &buf).await?;
+                        if escaped {
+                            return Ok(true);
                         }
                     }
-                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => {}
                     Err(err) => return Err(err.into()),
                 }
             }
@@ -877,33 +1187,129 @@ where
     }
 }
 
+/// Outputs is a list of additional outputs of an interact session.
+#[derive(Default)]
+struct Outputs {
+    writers: Vec<Box<dyn Write>>,
+    ignore_errors: bool,
+}
+
+impl Outputs {
+    fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+        for writer in &mut self.writers {
+            let result = spin_write(&mut *writer, buf).and_then(|_| spin_flush(&mut *writer));
+            match result {
+                Err(_) if self.ignore_errors => {}
+                result => result?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Transcript is a timestamped log of an interact session.
+struct Transcript {
+    writer: Box<dyn Write>,
+    start: Instant,
+}
+
+impl Transcript {
+    fn new(writer: Box<dyn Write>) -> Self {
+        Self {
+            writer,
+            start: Instant::now(),
+        }
+    }
+
+    fn record(&mut self, direction: &str, data: &[u8]) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let _ = match std::str::from_utf8(data) {
+            Ok(data) => writeln!(self.writer, "[{:.3}] {}: {:?}", elapsed, direction, data),
+            Err(..) => writeln!(
+                self.writer,
+                "[{:.3}] {}(bytes): {:?}",
+                elapsed, direction, data
+            ),
+        };
+        let _ = self.writer.flush();
+    }
+}
+
+fn record_transcript(transcript: &mut Option<Transcript>, direction: &str, data: &[u8]) {
+    if data.is_empty() {
+        return;
+    }
+
+    if let Some(transcript) = transcript {
+        transcript.record(direction, data);
+    }
+}
+
 #[cfg(feature = "async")]
 async fn async_timeout(timeout: Duration) -> io::Result<usize> {
-    Delay::new(timeout).await;
+    crate::runtime::sleep(timeout).await;
     io::Result::Ok(0)
 }
 
-fn spin_write<W>(mut writer: W, buf: &[u8]) -> std::io::Result<()>
+/// Sends user's input to the process, echoing it to all outputs if it's asked for.
+#[cfg(not(feature = "async"))]
+fn forward_input<S, I, O, C>(s: &mut InteractSession<S, I, O, C>, buf: &[u8]) -> ExpectResult<()>
+where
+    S: Write,
+    O: Write,
+{
+    s.session.write_all(buf)?;
+    if s.echo_input {
+        spin_write(&mut s.output, buf)?;
+        spin_flush(&mut s.output)?;
+        s.outputs.write(buf)?;
+    }
+
+    Ok(())
+}
+
+/// Sends user's input to the process, echoing it to all outputs if it's asked for.
+#[cfg(feature = "async")]
+async fn forward_input<S, I, O, C>(
+    s: &mut InteractSession<S, I, O, C>,
+    buf: &[u8],
+) -> ExpectResult<()>
+where
+    S: AsyncWrite + Unpin,
+    O: AsyncWrite + Unpin,
+{
+    s.session.write_all(buf).await?;
+    if s.echo_input {
+        s.output.write_all(buf).await?;
+        s.output.flush().await?;
+        s.outputs.write(buf)?;
+    }
+
+    Ok(())
+}
+
+fn spin_write<W>(mut writer: W, buf: &[u8]) -> io::Result<()>
 where
     W: Write,
 {
     loop {
         match writer.write_all(buf) {
             Ok(_) => return Ok(()),
-            Err(err) if err.kind() != std::io::ErrorKind::WouldBlock => return Err(err),
+            Err(err) if err.kind() != ErrorKind::WouldBlock => return Err(err),
             Err(_) => (),
         }
     }
 }
 
-fn spin_flush<W>(mut writer: W) -> std::io::Result<()>
+fn spin_flush<W>(mut writer: W) -> io::Result<()>
 where
     W: Write,
 {
     loop {
         match writer.flush() {
             Ok(_) => return Ok(()),
-            Err(err) if err.kind() != std::io::ErrorKind::WouldBlock => return Err(err),
+            Err(err) if err.kind() != ErrorKind::WouldBlock => return Err(err),
             Err(_) => (),
         }
     }
@@ -911,19 +1317,25 @@ where
 
 #[rustfmt::skip]
 fn run_action_input<S, I, O, C>(s: &mut InteractSession<S, I, O, C>, buf: &[u8], eof: bool) -> ExpectResult<bool> {
-    let ctx = Context::new(&mut s.session, &mut s.input, &mut s.output, &mut s.opts.state, buf, eof);
+    let mut ctx = Context::new(&mut s.session, &mut s.input, &mut s.output, &mut s.opts.state, buf, eof);
+    ctx.bytes_in = s.bytes_in;
+    ctx.bytes_out = s.bytes_out;
     opt_action(ctx, &mut s.opts.input_action)
 }
 
 #[rustfmt::skip]
 fn run_action_output<S, I, O, C>(s: &mut InteractSession<S, I, O, C>, buf: &[u8], eof: bool) -> ExpectResult<bool> {
-    let ctx = Context::new(&mut s.session, &mut s.input, &mut s.output, &mut s.opts.state, buf, eof);
+    let mut ctx = Context::new(&mut s.session, &mut s.input, &mut s.output, &mut s.opts.state, buf, eof);
+    ctx.bytes_in = s.bytes_in;
+    ctx.bytes_out = s.bytes_out;
     opt_action(ctx, &mut s.opts.output_action)
 }
 
 #[rustfmt::skip]
 fn run_action_idle<S, I, O, C>(s: &mut InteractSession<S, I, O, C>, buf: &[u8], eof: bool) -> ExpectResult<bool> {
-    let ctx = Context::new(&mut s.session, &mut s.input, &mut s.output, &mut s.opts.state, buf, eof);
+    let mut ctx = Context::new(&mut s.session, &mut s.input, &mut s.output, &mut s.opts.state, buf, eof);
+    ctx.bytes_in = s.bytes_in;
+    ctx.bytes_out = s.bytes_out;
     opt_action(ctx, &mut s.opts.idle_action)
 }
 
@@ -959,6 +1371,54 @@ where
     }
 }
 
+/// An interval a terminal size is checked with, see [`InteractSession::forward_resize`].
+#[cfg(all(unix, not(feature = "async"), feature = "polling"))]
+const RESIZE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Sets a size of the terminal on the session if it changed since the last call.
+#[cfg(all(unix, not(feature = "async")))]
+fn forward_window_size<S>(
+    session: &mut S,
+    resize: ResizeFn<S>,
+    last: &mut Option<(u16, u16)>,
+) -> Result<(), Error> {
+    let size = match terminal_size() {
+        Some(size) => size,
+        None => return Ok(()),
+    };
+
+    if *last != Some(size) {
+        resize(session, size.0, size.1)?;
+        *last = Some(size);
+    }
+
+    Ok(())
+}
+
+/// Returns a size of the terminal `STDIN` or `STDOUT` is attached to.
+#[cfg(all(unix, not(feature = "async")))]
+fn terminal_size() -> Option<(u16, u16)> {
+    use nix::libc;
+
+    [libc::STDIN_FILENO, libc::STDOUT_FILENO]
+        .into_iter()
+        .find_map(|fd| {
+            let mut size = libc::winsize {
+                ws_row: 0,
+                ws_col: 0,
+                ws_xpixel: 0,
+                ws_ypixel: 0,
+            };
+
+            // SAFETY: the winsize struct outlives the call.
+            let ret = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) };
+            match ret == 0 && size.ws_col > 0 && size.ws_row > 0 {
+                true => Some((size.ws_col, size.ws_row)),
+                false => None,
+            }
+        })
+}
+
 #[cfg(all(not(feature = "async"), not(feature = "polling")))]
 fn try_read<S>(session: &mut S, buf: &mut [u8]) -> ExpectResult<Option<usize>>
 where
@@ -977,9 +1437,158 @@ where
     }
 }
 
+/// Returns all held input, so nothing is lost when the input is closed.
+///
+/// Bytes held as a partial escape sequence go through the key map
+/// as they would if the next input didn't complete the sequence.
+fn flush_pending_input(escape: &mut EscapeSequence, keys: &mut KeyMap) -> Vec<u8> {
+    let pending = escape.flush();
+    let mut data = keys.apply(&pending).into_owned();
+    data.extend(keys.flush());
+    data
+}
+
+/// EscapeSequence looks up an escape sequence in user's input.
+///
+/// A partially matched sequence is kept between reads.
+#[derive(Debug, Clone)]
+struct EscapeSequence {
+    sequence: Vec<u8>,
+    pending: Vec<u8>,
+}
+
+impl EscapeSequence {
+    fn new(sequence: Vec<u8>) -> Self {
+        Self {
+            sequence,
+            pending: Vec::new(),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Returns bytes which were held as a partial match of the sequence.
+    fn flush(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Returns bytes which must be sent to a process,
+    /// and a flag whether the escape sequence was found.
+    fn scan<'a>(&mut self, buf: &'a [u8]) -> (Cow<'a, [u8]>, bool) {
+        let data = match self.pending.is_empty() {
+            true => Cow::Borrowed(buf),
+            false => {
+                let mut data = std::mem::take(&mut self.pending);
+                data.extend_from_slice(buf);
+                Cow::Owned(data)
+            }
+        };
+
+        match contains_in_bytes(&data, &self.sequence) {
+            SequenceMatch::Found(pos) => (truncate(data, pos), true),
+            SequenceMatch::MaybeLater(pos) => {
+                self.pending = data[pos..].to_vec();
+                (truncate(data, pos), false)
+            }
+            SequenceMatch::No => (data, false),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum SequenceMatch {
+    /// A sequence starts at the position.
+    Found(usize),
+    /// A sequence may start at the position if the next bytes complete it.
+    MaybeLater(usize),
+    No,
+}
+
+fn contains_in_bytes(data: &[u8], sequence: &[u8]) -> SequenceMatch {
+    if sequence.is_empty() {
+        return SequenceMatch::No;
+    }
+
+    if let Some(pos) = data.windows(sequence.len()).position(|w| w == sequence) {
+        return SequenceMatch::Found(pos);
+    }
+
+    let start = data.len().saturating_sub(sequence.len() - 1);
+    for pos in start..data.len() {
+        if sequence.starts_with(&data[pos..]) {
+            return SequenceMatch::MaybeLater(pos);
+        }
+    }
+
+    SequenceMatch::No
+}
+
+fn truncate(data: Cow<'_, [u8]>, len: usize) -> Cow<'_, [u8]> {
+    match data {
+        Cow::Borrowed(data) => Cow::Borrowed(&data[..len]),
+        Cow::Owned(mut data) => {
+            data.truncate(len);
+            Cow::Owned(data)
+        }
+    }
+}
+
 fn get_pointer<T>(ptr: &Option<Box<T>>) -> usize
 where
     T: ?Sized,
 {
     ptr.as_ref().map_or(0, |f| std::ptr::addr_of!(f) as usize)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains_in_bytes() {
+        assert_eq!(contains_in_bytes(b"abc~.d", b"~."), SequenceMatch::Found(3));
+        assert_eq!(
+            contains_in_bytes(b"abc~", b"~."),
+            SequenceMatch::MaybeLater(3)
+        );
+        assert_eq!(contains_in_bytes(b"abc", b"~."), SequenceMatch::No);
+        assert_eq!(
+            contains_in_bytes(b"ab\x01", b"\x01q"),
+            SequenceMatch::MaybeLater(2)
+        );
+        assert_eq!(contains_in_bytes(b"\x1d", &[29]), SequenceMatch::Found(0));
+        assert_eq!(contains_in_bytes(b"abc", b""), SequenceMatch::No);
+    }
+
+    #[test]
+    fn test_escape_sequence_across_reads() {
+        let mut escape = EscapeSequence::new(b"~.".to_vec());
+        assert_eq!(
+            escape.scan(b"hello~"),
+            (Cow::Borrowed(&b"hello"[..]), false)
+        );
+        assert_eq!(escape.scan(b".world"), (Cow::Borrowed(&b""[..]), true));
+
+        let mut escape = EscapeSequence::new(b"~.".to_vec());
+        assert_eq!(
+            escape.scan(b"hello~"),
+            (Cow::Borrowed(&b"hello"[..]), false)
+        );
+        assert_eq!(escape.scan(b"~"), (Cow::Borrowed(&b"~"[..]), false));
+        assert_eq!(escape.scan(b"x"), (Cow::Borrowed(&b"~x"[..]), false));
+
+        let mut escape = EscapeSequence::new(b"\x01q".to_vec());
+        assert_eq!(escape.scan(b"ls\x01"), (Cow::Borrowed(&b"ls"[..]), false));
+        assert_eq!(escape.scan(b"\x01"), (Cow::Borrowed(&b"\x01"[..]), false));
+        assert_eq!(escape.scan(b"w"), (Cow::Borrowed(&b"\x01w"[..]), false));
+        assert_eq!(escape.scan(b"\x01"), (Cow::Borrowed(&b""[..]), false));
+        assert_eq!(escape.scan(b"q"), (Cow::Borrowed(&b""[..]), true));
+
+        let mut escape = EscapeSequence::new(b"abc".to_vec());
+        assert_eq!(escape.scan(b"a"), (Cow::Borrowed(&b""[..]), false));
+        assert_eq!(escape.scan(b"b"), (Cow::Borrowed(&b""[..]), false));
+        assert_eq!(escape.scan(b"cd"), (Cow::Borrowed(&b""[..]), true));
+    }
+}
@@ -0,0 +1,111 @@
+//! A module which contains [Key] type.
+
+/// Key represents a special key of a keyboard,
+/// which a terminal sends to a program as a VT100/xterm escape sequence.
+///
+/// # Example
+///
+/// ```
+/// use expectrl::Key;
+///
+/// assert_eq!(Key::Up.as_bytes(false), b"\x1b[A");
+/// assert_eq!(Key::Up.as_bytes(true), b"\x1bOA");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// An up arrow.
+    Up,
+    /// A down arrow.
+    Down,
+    /// A left arrow.
+    Left,
+    /// A right arrow.
+    Right,
+    /// A home key.
+    Home,
+    /// An end key.
+    End,
+    /// A page up key.
+    PageUp,
+    /// A page down key.
+    PageDown,
+    /// A F1 key.
+    F1,
+    /// A F2 key.
+    F2,
+    /// A F3 key.
+    F3,
+    /// A F4 key.
+    F4,
+    /// A F5 key.
+    F5,
+    /// A F6 key.
+    F6,
+    /// A F7 key.
+    F7,
+    /// A F8 key.
+    F8,
+    /// A F9 key.
+    F9,
+    /// A F10 key.
+    F10,
+    /// A F11 key.
+    F11,
+    /// A F12 key.
+    F12,
+    /// A tab key.
+    Tab,
+    /// An enter key, it's sent as a carriage return.
+    Enter,
+    /// A backspace key, it's sent as `DEL` (`0x7F`) as xterm does.
+    Backspace,
+    /// A delete key.
+    Delete,
+    /// An escape key.
+    Esc,
+}
+
+impl Key {
+    /// Returns a sequence of bytes a terminal sends for the key.
+    ///
+    /// Arrows, home and end keys are sent differently
+    /// when a program switched a terminal into an application cursor mode (`DECCKM`),
+    /// e.g. `\x1bOA` instead of `\x1b[A` for [`Key::Up`].
+    pub fn as_bytes(&self, application_cursor: bool) -> &'static [u8] {
+        use Key::*;
+
+        match (self, application_cursor) {
+            (Up, false) => b"\x1b[A",
+            (Up, true) => b"\x1bOA",
+            (Down, false) => b"\x1b[B",
+            (Down, true) => b"\x1bOB",
+            (Right, false) => b"\x1b[C",
+            (Right, true) => b"\x1bOC",
+            (Left, false) => b"\x1b[D",
+            (Left, true) => b"\x1bOD",
+            (Home, false) => b"\x1b[H",
+            (Home, true) => b"\x1bOH",
+            (End, false) => b"\x1b[F",
+            (End, true) => b"\x1bOF",
+            (PageUp, _) => b"\x1b[5~",
+            (PageDown, _) => b"\x1b[6~",
+            (F1, _) => b"\x1bOP",
+            (F2, _) => b"\x1bOQ",
+            (F3, _) => b"\x1bOR",
+            (F4, _) => b"\x1bOS",
+            (F5, _) => b"\x1b[15~",
+            (F6, _) => b"\x1b[17~",
+            (F7, _) => b"\x1b[18~",
+            (F8, _) => b"\x1b[19~",
+            (F9, _) => b"\x1b[20~",
+            (F10, _) => b"\x1b[21~",
+            (F11, _) => b"\x1b[23~",
+            (F12, _) => b"\x1b[24~",
+            (Tab, _) => b"\t",
+            (Enter, _) => b"\r",
+            (Backspace, _) => b"\x7f",
+            (Delete, _) => b"\x1b[3~",
+            (Esc, _) => b"\x1b",
+        }
+    }
+}
@@ -31,6 +31,7 @@
 //!
 //! - `async`: Enables a async/await public API.
 //! - `polling`: Enables polling backend in interact session. Be cautious to use it on windows.
+//! - `tokio`: Runs the async API on a tokio reactor and timers when it's used within a tokio runtime.
 //!
 //! ## Examples
 //!
@@ -115,27 +116,50 @@ mod check_macros;
 mod control_code;
 mod error;
 mod expect;
-mod needle;
+mod key;
+mod line_ending;
+#[cfg(feature = "async")]
+mod runtime;
+mod trace;
 
 #[cfg(all(windows, feature = "polling"))]
 mod waiter;
 
 pub mod interact;
+pub mod needle;
 pub mod process;
+#[cfg(feature = "render")]
+pub mod render;
 pub mod repl;
+pub mod script;
 pub mod session;
 pub mod stream;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod util;
 
 pub use captures::Captures;
 pub use control_code::ControlCode;
 pub use error::Error;
-pub use needle::{Any, Eof, NBytes, Needle, Regex};
+pub use key::Key;
+pub use line_ending::LineEnding;
+pub use needle::{
+    Any, Endian, Eof, Fuzzy, Ignoring, LengthPrefixed, Line, NBytes, Needle, NoCase, Stripped,
+    TrimmedMatch,
+};
+
+#[cfg(feature = "regex")]
+pub use needle::Regex;
 
 pub use expect::Expect;
 pub use session::Session;
 
 #[cfg(feature = "async")]
-pub use expect::AsyncExpect;
+pub use expect::{AsyncExpect, SyncAdapter};
+
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::time::Duration;
 
 use session::OsSession;
 
@@ -169,3 +193,110 @@ where
 {
     Session::spawn_cmd(cmd.as_ref())
 }
+
+/// Spawns a new session like [`spawn`],
+/// but with additional environment variables and a working directory.
+///
+/// It's handy when a [`std::process::Command`] is an overkill
+/// but a command still needs e.g. a tweaked `PATH`.
+/// See [`Session::spawn_with`] to configure a [`std::process::Command`] fully.
+///
+/// # Example
+///
+/// ```no_run
+/// use expectrl::spawn_with_env;
+///
+/// let env = [(String::from("LC_ALL"), String::from("C"))];
+/// let p = spawn_with_env("ls -l", &env, Some("/tmp".into())).unwrap();
+/// ```
+#[cfg(unix)]
+pub fn spawn_with_env<S>(
+    cmd: S,
+    env: &[(String, String)],
+    cwd: Option<PathBuf>,
+) -> Result<OsSession, Error>
+where
+    S: AsRef<str>,
+{
+    let args = process::unix::tokenize_command(cmd.as_ref());
+    if args.is_empty() {
+        return Err(Error::CommandParsing);
+    }
+
+    Session::spawn_with(&args[0], |command| {
+        let _ = command.args(&args[1..]).envs(env.iter().cloned());
+        if let Some(cwd) = cwd {
+            let _ = command.current_dir(cwd);
+        }
+    })
+}
+
+/// Spawns a command and expects a needle within a given timeout.
+///
+/// It's a shortcut for a common "run X and confirm it printed Y" case.
+/// On failure the returned error is wrapped in a context with the command and
+/// the output which was read so far, see [`Error::root`] to get the original one.
+///
+/// # Example
+///
+/// ```no_run,ignore
+/// use std::time::Duration;
+/// use expectrl::spawn_expect;
+///
+/// let found = spawn_expect("echo Hello World", "World", Duration::from_secs(1)).unwrap();
+/// assert_eq!(found.get(0).unwrap(), b"World");
+/// ```
+#[cfg(not(feature = "async"))]
+pub fn spawn_expect<S, N>(cmd: S, needle: N, timeout: Duration) -> Result<Captures, Error>
+where
+    S: AsRef<str>,
+    N: Needle,
+{
+    let cmd = cmd.as_ref();
+    let mut session = spawn(cmd).map_err(|err| spawn_expect_error(cmd, err, &[]))?;
+    session.set_expect_timeout(Some(timeout));
+
+    match session.expect(needle) {
+        Ok(captures) => Ok(captures),
+        Err(err) => Err(spawn_expect_error(cmd, err, session.get_available())),
+    }
+}
+
+/// Spawns a command and expects a needle within a given timeout.
+///
+/// It's a shortcut for a common "run X and confirm it printed Y" case.
+/// On failure the returned error is wrapped in a context with the command and
+/// the output which was read so far, see [`Error::root`] to get the original one.
+///
+/// # Example
+///
+/// ```no_run,ignore
+/// use std::time::Duration;
+/// use expectrl::spawn_expect;
+///
+/// let found = spawn_expect("echo Hello World", "World", Duration::from_secs(1)).await.unwrap();
+/// assert_eq!(found.get(0).unwrap(), b"World");
+/// ```
+#[cfg(feature = "async")]
+pub async fn spawn_expect<S, N>(cmd: S, needle: N, timeout: Duration) -> Result<Captures, Error>
+where
+    S: AsRef<str>,
+    N: Needle,
+{
+    let cmd = cmd.as_ref();
+    let mut session = spawn(cmd).map_err(|err| spawn_expect_error(cmd, err, &[]))?;
+    session.set_expect_timeout(Some(timeout));
+
+    match session.expect(needle).await {
+        Ok(captures) => Ok(captures),
+        Err(err) => Err(spawn_expect_error(cmd, err, session.get_available())),
+    }
+}
+
+fn spawn_expect_error(cmd: &str, err: Error, buf: &[u8]) -> Error {
+    err.context(format!(
+        "spawn_expect failed for command {:?}, output: {:?}",
+        cmd,
+        String::from_utf8_lossy(buf)
+    ))
+}
@@ -0,0 +1,44 @@
+//! A module which contains [LineEnding] type.
+
+/// LineEnding is a sequence of bytes sent after a line by `send_line`.
+///
+/// The default is [`LineEnding::CrLf`] on windows and [`LineEnding::Lf`] on other platforms.
+///
+/// # Example
+///
+/// ```
+/// use expectrl::LineEnding;
+///
+/// assert_eq!(LineEnding::CrLf.as_bytes(), b"\r\n");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    /// A line feed `\n`.
+    Lf,
+    /// A carriage return followed by a line feed `\r\n`,
+    /// which DOS programs and some serial consoles expect.
+    CrLf,
+    /// A bare carriage return `\r`, which is what the Enter key sends.
+    Cr,
+}
+
+impl LineEnding {
+    /// Returns bytes of the line ending.
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            LineEnding::Lf => b"\n",
+            LineEnding::CrLf => b"\r\n",
+            LineEnding::Cr => b"\r",
+        }
+    }
+}
+
+impl Default for LineEnding {
+    fn default() -> Self {
+        if cfg!(windows) {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+}
@@ -3,12 +3,117 @@
 //!
 //! The list of provided implementations can be found in the documentation.
 
-use crate::error::Error;
+use std::{
+    cell::Cell,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{error::Error, util::strip_ansi_indexed, Captures};
 
 /// Needle an interface for search of a match in a buffer.
 pub trait Needle {
     /// Function returns all matches that were occured.
     fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error>;
+
+    /// Function returns all matches that were occured
+    /// along with an index of an inner needle which produced them.
+    ///
+    /// It's meaningful only for needles which combine other needles, like [Any].
+    /// By default no index is reported.
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        self.check(buf, eof).map(|found| (found, None))
+    }
+
+    /// Function returns a group of each match [Needle::check] returns for the same buffer.
+    ///
+    /// It's meaningful only for needles which have groups, like [Regex].
+    /// It's called only once a needle matched, so the matches don't carry the groups themselves.
+    /// By default there are no groups.
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        let _ = (buf, eof);
+        Ok(None)
+    }
+
+    /// Function returns an index in a buffer up to which bytes are consumed
+    /// once the needle matched, for the same buffer [Needle::check] was given.
+    ///
+    /// It's meaningful only for needles which consume bytes past their matches, like [Line].
+    /// By default it's `None`, which means the end of the right most match.
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        let _ = (buf, eof);
+        Ok(None)
+    }
+
+    /// Function returns a distance between a match [Needle::check] returns for the same buffer
+    /// and a pattern of the needle.
+    ///
+    /// It's meaningful only for needles which tolerate differences, like [Fuzzy].
+    /// It's called only once a needle matched, like [Needle::groups].
+    /// By default there's no distance.
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        let _ = (buf, eof);
+        Ok(None)
+    }
+
+    /// Function returns a human readable form of the needle, e.g. to be logged.
+    ///
+    /// By default it's a name of the needle type.
+    fn describe(&self) -> String {
+        std::any::type_name::<Self>().to_owned()
+    }
+}
+
+/// Runs a needle against a given slice of bytes,
+/// independently of a [`Session`] and its buffer.
+///
+/// It returns the same [`Captures`] an expect call would,
+/// or an empty [`Captures`] if nothing was found.
+/// `eof` tells whether no more data will come, as [`Eof`] relies on it.
+///
+/// # Example
+///
+/// ```
+/// use expectrl::needle::find;
+///
+/// let found = find(&"World", b"Hello World!", false).unwrap();
+/// assert_eq!(found.before(), b"Hello ");
+/// assert_eq!(&found[0], b"World");
+/// ```
+///
+/// [`Session`]: crate::Session
+pub fn find(needle: &dyn Needle, data: &[u8], eof: bool) -> Result<Captures, Error> {
+    let found = capture(needle, data, eof)?;
+    Ok(found.unwrap_or_else(|| Captures::new(Vec::new(), Vec::new())))
+}
+
+/// Runs a needle against a buffer,
+/// collecting the bytes up to the end of a match into [`Captures`].
+///
+/// The collected bytes are the ones to be consumed, see [Needle::end],
+/// so their length is an index a buffer is consumed up to.
+/// It returns `None` if nothing was found.
+pub(crate) fn capture<N>(needle: &N, data: &[u8], eof: bool) -> Result<Option<Captures>, Error>
+where
+    N: Needle + ?Sized,
+{
+    let (found, needle_index) = needle.check_indexed(data, eof)?;
+    if found.is_empty() {
+        return Ok(None);
+    }
+
+    let end = Captures::right_most_index(&found);
+    let end = needle
+        .end(data, eof)?
+        .map_or(end, |consumed| consumed.max(end));
+    let groups = needle.groups(data, eof)?;
+    let distance = needle.distance(data, eof)?;
+
+    let found = Captures::new(data[..end].to_vec(), found)
+        .with_needle_index(needle_index)
+        .with_groups(groups)
+        .with_distance(distance);
+
+    Ok(Some(found))
 }
 
 /// Match structure represent a range of bytes where match was found.
@@ -35,16 +140,47 @@ impl Match {
     }
 }
 
+/// Groups is a side table which tells a group each match belongs to,
+/// for needles which have groups, like [Regex].
+///
+/// See [Needle::groups].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Groups {
+    indexes: Vec<usize>,
+    names: Vec<Option<String>>,
+}
+
+impl Groups {
+    /// Creates a table from a group index of each match, in the order of the matches,
+    /// and names of the groups, in the order of the group indexes.
+    pub fn new(indexes: Vec<usize>, names: Vec<Option<String>>) -> Self {
+        Self { indexes, names }
+    }
+
+    /// Returns an index of a group a match at a given position belongs to.
+    pub fn group_of(&self, match_index: usize) -> Option<usize> {
+        self.indexes.get(match_index).copied()
+    }
+
+    /// Returns an index of a group by its name.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n.as_deref() == Some(name))
+    }
+}
+
+#[cfg(feature = "regex")]
 impl From<regex::bytes::Match<'_>> for Match {
     fn from(m: regex::bytes::Match<'_>) -> Self {
         Self::new(m.start(), m.end())
     }
 }
 
+#[cfg(feature = "regex")]
 /// Regex tries to look up a match by a regex.
 #[derive(Debug)]
 pub struct Regex<Re: AsRef<str>>(pub Re);
 
+#[cfg(feature = "regex")]
 impl<Re: AsRef<str>> Needle for Regex<Re> {
     fn check(&self, buf: &[u8], _: bool) -> Result<Vec<Match>, Error> {
         let regex = regex::bytes::Regex::new(self.0.as_ref()).map_err(|_| Error::RegexParsing)?;
@@ -54,6 +190,28 @@ impl<Re: AsRef<str>> Needle for Regex<Re> {
             .collect();
         Ok(matches)
     }
+
+    fn groups(&self, buf: &[u8], _: bool) -> Result<Option<Groups>, Error> {
+        let regex = regex::bytes::Regex::new(self.0.as_ref()).map_err(|_| Error::RegexParsing)?;
+        let names = regex
+            .capture_names()
+            .map(|name| name.map(ToOwned::to_owned))
+            .collect();
+        let indexes = regex
+            .captures_iter(buf)
+            .flat_map(|c| {
+                c.iter()
+                    .enumerate()
+                    .filter_map(|(i, m)| m.map(|_| i))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        Ok(Some(Groups::new(indexes, names)))
+    }
+
+    fn describe(&self) -> String {
+        format!("Regex({:?})", self.0.as_ref())
+    }
 }
 
 /// Eof consider a match when an EOF is reached.
@@ -67,6 +225,10 @@ impl Needle for Eof {
             false => Ok(Vec::new()),
         }
     }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
 }
 
 /// NBytes matches N bytes from the stream.
@@ -86,6 +248,321 @@ impl Needle for NBytes {
             false => Ok(Vec::new()),
         }
     }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Line matches the next complete line.
+///
+/// The match is the line without a terminator, either `\n` or `\r\n`,
+/// though the terminator is consumed along with it.
+///
+/// On EOF the remaining partial line is matched.
+///
+/// # Example
+///
+/// ```
+/// use expectrl::{needle::find, Line};
+///
+/// let found = find(&Line, b"Hello\r\nWorld", false).unwrap();
+/// assert_eq!(found.matches().collect::<Vec<_>>(), [b"Hello"]);
+/// assert_eq!(found.as_bytes(), b"Hello\r\n");
+/// ```
+#[derive(Debug)]
+pub struct Line;
+
+impl Needle for Line {
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        match buf.iter().position(|&b| b == b'\n') {
+            Some(pos) => {
+                let end = match pos > 0 && buf[pos - 1] == b'\r' {
+                    true => pos - 1,
+                    false => pos,
+                };
+
+                Ok(vec![Match::new(0, end)])
+            }
+            None if eof && !buf.is_empty() => Ok(vec![Match::new(0, buf.len())]),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn end(&self, buf: &[u8], _: bool) -> Result<Option<usize>, Error> {
+        Ok(buf.iter().position(|&b| b == b'\n').map(|pos| pos + 1))
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// TrimmedMatch matches a string regardless of its trailing whitespace.
+///
+/// Spaces and tabs which follow the match in a buffer are included into the match.
+/// It's handy for prompts which may come with or without a trailing space, like `$` and `$ `.
+///
+/// # Example
+///
+/// ```no_run,ignore
+/// use expectrl::{spawn, TrimmedMatch};
+///
+/// let mut p = spawn("sh").unwrap();
+/// p.expect(TrimmedMatch("$ ")).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct TrimmedMatch<S: AsRef<str>>(pub S);
+
+impl<S: AsRef<str>> Needle for TrimmedMatch<S> {
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        let needle = self.0.as_ref().trim_end();
+        let found = needle.check(buf, eof)?;
+        let m = match found.first() {
+            Some(m) => m,
+            None => return Ok(found),
+        };
+
+        let spaces = buf[m.end()..]
+            .iter()
+            .take_while(|&&b| b == b' ' || b == b'\t')
+            .count();
+
+        Ok(vec![Match::new(m.start(), m.end() + spaces)])
+    }
+
+    fn describe(&self) -> String {
+        format!("TrimmedMatch({:?})", self.0.as_ref())
+    }
+}
+
+/// NoCase matches a string ignoring its case.
+///
+/// Only ASCII letters are compared case-insensitively,
+/// so a match has the same length as the pattern
+/// and the capture is the matched bytes in their original case.
+///
+/// # Example
+///
+/// ```no_run,ignore
+/// use expectrl::{spawn, NoCase};
+///
+/// let mut p = spawn("cat").unwrap();
+/// p.send_line("PASSWORD:").unwrap();
+/// let m = p.expect(NoCase("password:")).unwrap();
+/// assert_eq!(&m[0], b"PASSWORD:");
+/// ```
+#[derive(Debug)]
+pub struct NoCase<S: AsRef<str>>(pub S);
+
+impl<S: AsRef<str>> Needle for NoCase<S> {
+    fn check(&self, buf: &[u8], _: bool) -> Result<Vec<Match>, Error> {
+        let needle = self.0.as_ref().as_bytes();
+        if buf.len() < needle.len() {
+            return Ok(Vec::new());
+        }
+
+        for l_bound in 0..=buf.len() - needle.len() {
+            let r_bound = l_bound + needle.len();
+            if buf[l_bound..r_bound].eq_ignore_ascii_case(needle) {
+                return Ok(vec![Match::new(l_bound, r_bound)]);
+            }
+        }
+
+        Ok(Vec::new())
+    }
+
+    fn describe(&self) -> String {
+        format!("NoCase({:?})", self.0.as_ref())
+    }
+}
+
+/// LengthPrefixed matches a frame of a binary protocol
+/// which starts with a length of its payload.
+///
+/// The frame is expected at the start of a buffer.
+/// The prefix is an unsigned integer of `len_bytes` bytes (from 1 to 8),
+/// and the match is the payload which follows it,
+/// so the prefix itself is available via [`Captures::before`].
+///
+/// # Example
+///
+/// ```no_run,ignore
+/// use expectrl::{spawn, Endian, LengthPrefixed};
+///
+/// let mut p = spawn("my-binary-ipc").unwrap();
+/// let frame = p.expect(LengthPrefixed { len_bytes: 4, endian: Endian::Big }).unwrap();
+/// let payload = &frame[0];
+/// ```
+///
+/// [`Captures::before`]: crate::Captures::before
+#[derive(Debug, Clone, Copy)]
+pub struct LengthPrefixed {
+    /// A size of the length prefix in bytes.
+    pub len_bytes: usize,
+    /// A byte order of the length prefix.
+    pub endian: Endian,
+}
+
+/// A byte order of an integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    /// The most significant byte goes first.
+    Big,
+    /// The least significant byte goes first.
+    Little,
+}
+
+impl Needle for LengthPrefixed {
+    fn check(&self, buf: &[u8], _: bool) -> Result<Vec<Match>, Error> {
+        if !(1..=8).contains(&self.len_bytes) {
+            return Err(Error::Other {
+                message: String::from("invalid length prefix"),
+                err: format!("a prefix must be 1 to 8 bytes but it's {}", self.len_bytes),
+            });
+        }
+
+        let prefix = match buf.get(..self.len_bytes) {
+            Some(prefix) => prefix,
+            None => return Ok(Vec::new()),
+        };
+
+        let fold = |len: u64, &b: &u8| (len << 8) | b as u64;
+        let len = match self.endian {
+            Endian::Big => prefix.iter().fold(0, fold),
+            Endian::Little => prefix.iter().rev().fold(0, fold),
+        };
+
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| len.checked_add(self.len_bytes));
+        match end {
+            Some(end) if end <= buf.len() => Ok(vec![Match::new(self.len_bytes, end)]),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Fuzzy matches a substring which differs from a pattern
+/// by at most `max_distance` edits (insertions, deletions or substitutions of a byte),
+/// which is known as a Levenshtein distance.
+///
+/// It's handy for noisy output, e.g. a serial line which occasionally corrupts a character.
+/// The distance of a match is available via [`Captures::distance`].
+///
+/// The first place where the distance fits into `max_distance` is used,
+/// and the match is extended while the following bytes bring it closer to the pattern.
+/// A match is reported only once at least a pattern length of bytes was received from its start,
+/// so `hell` doesn't match a partially received `hello` with `max_distance: 1`;
+/// except an EOF, when no more bytes come.
+///
+/// # Performance
+///
+/// A check takes `O(pattern.len() * buffer.len())`,
+/// and as the buffer is checked again after each read,
+/// expecting on a large amount of output without a match gets slow.
+///
+/// Set a `window` to examine only the last bytes of a buffer,
+/// which bounds a check by `O(pattern.len() * window)` however much output is buffered.
+/// Bytes before the window are never examined,
+/// so it must fit the pattern with `max_distance` extra bytes along with
+/// the bytes a single read may bring after it.
+///
+/// # Example
+///
+/// ```
+/// use expectrl::{needle::find, Fuzzy};
+///
+/// let fuzzy = Fuzzy {
+///     pattern: "login:".to_string(),
+///     max_distance: 1,
+///     window: Some(256),
+/// };
+///
+/// let found = find(&fuzzy, b"Welcome\nlog1n: ", false).unwrap();
+/// assert_eq!(&found[0], b"log1n:");
+/// assert_eq!(found.distance(), Some(1));
+/// ```
+///
+/// [`Captures::distance`]: crate::Captures::distance
+#[derive(Debug, Clone)]
+pub struct Fuzzy {
+    /// A pattern to look for.
+    pub pattern: String,
+    /// A maximum number of edits allowed between the pattern and a match.
+    pub max_distance: usize,
+    /// A number of the last bytes of a buffer to look in,
+    /// or `None` to look through the whole buffer.
+    pub window: Option<usize>,
+}
+
+impl Fuzzy {
+    /// Returns the closest match along with its distance to the pattern.
+    fn find(&self, buf: &[u8], eof: bool) -> Option<(Match, usize)> {
+        let pattern = self.pattern.as_bytes();
+        if pattern.is_empty() {
+            return Some((Match::new(0, 0), 0));
+        }
+
+        let offset = self
+            .window
+            .map_or(0, |window| buf.len().saturating_sub(window));
+        let buf = &buf[offset..];
+
+        // column[i] is a distance between pattern[..i] and the closest substring ending at the current byte,
+        // along with a start of the substring.
+        let mut column = (0..=pattern.len()).map(|i| (i, 0)).collect::<Vec<_>>();
+        let closest = |a: (usize, usize), b: (usize, usize)| match (a.0, b.1) < (b.0, a.1) {
+            true => a,
+            false => b,
+        };
+
+        let mut found: Option<(Match, usize)> = None;
+        for (end, &b) in buf.iter().enumerate() {
+            let mut diagonal = column[0];
+            column[0] = (0, end + 1);
+            for i in 1..=pattern.len() {
+                let substitution = (diagonal.0 + usize::from(pattern[i - 1] != b), diagonal.1);
+                let deletion = (column[i - 1].0 + 1, column[i - 1].1);
+                let insertion = (column[i].0 + 1, column[i].1);
+
+                diagonal = column[i];
+                column[i] = closest(closest(substitution, deletion), insertion);
+            }
+
+            let (distance, start) = column[pattern.len()];
+            // more bytes may still bring a match closer to the pattern
+            let is_complete = eof || buf.len() - start >= pattern.len();
+            match &found {
+                Some((_, found)) if distance < *found => {}
+                Some(_) => break,
+                None if distance <= self.max_distance && is_complete => {}
+                None => continue,
+            }
+
+            found = Some((Match::new(offset + start, offset + end + 1), distance));
+        }
+
+        found
+    }
+}
+
+impl Needle for Fuzzy {
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        Ok(self.find(buf, eof).into_iter().map(|(m, _)| m).collect())
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        Ok(self.find(buf, eof).map(|(_, distance)| distance))
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
 }
 
 impl Needle for [u8] {
@@ -107,48 +584,79 @@ impl Needle for [u8] {
 
         Ok(Vec::new())
     }
+
+    fn describe(&self) -> String {
+        format!("{:?}", String::from_utf8_lossy(self))
+    }
 }
 
 impl Needle for &[u8] {
     fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
         (*self).check(buf, eof)
     }
+
+    fn describe(&self) -> String {
+        (*self).describe()
+    }
 }
 
 impl Needle for str {
     fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
         self.as_bytes().check(buf, eof)
     }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
 }
 
 impl Needle for &str {
     fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
         self.as_bytes().check(buf, eof)
     }
+
+    fn describe(&self) -> String {
+        (*self).describe()
+    }
 }
 
 impl Needle for String {
     fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
         self.as_bytes().check(buf, eof)
     }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
 }
 
 impl Needle for u8 {
     fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
         ([*self][..]).check(buf, eof)
     }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
 }
 
 impl Needle for char {
     fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
         char::to_string(self).check(buf, eof)
     }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
 }
 
 /// Any matches uses all provided lookups and returns a match
 /// from a first successfull match.
 ///
-/// It does checks lookups in order they were provided.
+/// It does checks lookups in order they were provided,
+/// so the first lookup in the list which matches wins,
+/// even if a later one matches earlier in the buffer or a longer text.
+/// Use [Any::longest] when alternatives overlap (e.g. `"Error"` and `"Error: fatal"`).
 ///
 /// # Example
 ///
@@ -159,6 +667,22 @@ impl Needle for char {
 /// p.expect(Any(["we", "are", "here"])).unwrap();
 /// ```
 ///
+/// An index of a lookup which matched is available via [`Captures::needle_index`].
+/// A slice of strings or a [Vec] of [String]s can be used as a shortcut for [Any].
+///
+/// ```no_run,ignore
+/// use expectrl::spawn;
+///
+/// let mut p = spawn("cat").unwrap();
+/// let found = p.expect(&["yes", "no"][..]).unwrap();
+/// match found.needle_index() {
+///     Some(0) => println!("yes"),
+///     _ => println!("no"),
+/// }
+/// ```
+///
+/// [`Captures::needle_index`]: crate::Captures::needle_index
+///
 /// To be able to combine different types of lookups you can call [Any::boxed].
 ///
 /// ```no_run,ignore
@@ -177,65 +701,1066 @@ impl Any<Vec<Box<dyn Needle>>> {
     }
 }
 
-impl<T> Needle for Any<&[T]>
+impl<I> Any<I> {
+    /// Creates an [Any] where the first lookup in the list which matches wins.
+    ///
+    /// It's the same as constructing [Any] directly.
+    pub fn ordered(needles: I) -> Self {
+        Self(needles)
+    }
+
+    /// Creates a needle which returns the longest match at the earliest position,
+    /// see [AnyLongest].
+    pub fn longest(needles: I) -> AnyLongest<I> {
+        AnyLongest(needles)
+    }
+}
+
+/// AnyLongest uses all provided lookups and returns the match which starts first in the buffer,
+/// and if a few of them start at the same position the longest one.
+///
+/// On a tie the lookup which goes first in the list wins.
+/// An index of a lookup which matched is available via [`Captures::needle_index`].
+///
+/// Notice that a shorter alternative may still win if the rest of a longer one hasn't been read yet.
+///
+/// # Example
+///
+/// ```no_run,ignore
+/// use expectrl::{spawn, Any};
+///
+/// let mut p = spawn("my-app").unwrap();
+/// let found = p.expect(Any::longest(["Error", "Error: fatal"])).unwrap();
+/// ```
+///
+/// [`Captures::needle_index`]: crate::Captures::needle_index
+#[derive(Debug)]
+pub struct AnyLongest<I>(pub I);
+
+impl<T> Needle for AnyLongest<&[T]>
 where
     T: Needle,
 {
     fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
-        for needle in self.0.iter() {
+        self.check_indexed(buf, eof).map(|(found, _)| found)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        let mut best: Option<(Vec<Match>, usize)> = None;
+        for (i, needle) in self.0.iter().enumerate() {
             let found = needle.check(buf, eof)?;
-            if !found.is_empty() {
-                return Ok(found);
+            let m = match found.first() {
+                Some(m) => m,
+                None => continue,
+            };
+
+            let is_better = match &best {
+                Some((best, _)) => {
+                    let b = &best[0];
+                    m.start() < b.start() || (m.start() == b.start() && m.end() > b.end())
+                }
+                None => true,
+            };
+
+            if is_better {
+                best = Some((found, i));
             }
         }
 
-        Ok(Vec::new())
+        match best {
+            Some((found, i)) => Ok((found, Some(i))),
+            None => Ok((Vec::new(), None)),
+        }
     }
-}
 
-impl<T> Needle for Any<Vec<T>>
-where
-    T: Needle,
-{
-    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
-        Any(self.0.as_slice()).check(buf, eof)
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        match self.check_indexed(buf, eof)?.1 {
+            Some(i) => self.0[i].groups(buf, eof),
+            None => Ok(None),
+        }
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        match self.check_indexed(buf, eof)?.1 {
+            Some(i) => self.0[i].end(buf, eof),
+            None => Ok(None),
+        }
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        match self.check_indexed(buf, eof)?.1 {
+            Some(i) => self.0[i].distance(buf, eof),
+            None => Ok(None),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("AnyLongest({})", describe_all(self.0))
     }
 }
 
-impl<T, const N: usize> Needle for Any<[T; N]>
+impl<T> Needle for AnyLongest<Vec<T>>
 where
     T: Needle,
 {
     fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
-        Any(&self.0[..]).check(buf, eof)
+        AnyLongest(self.0.as_slice()).check(buf, eof)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        AnyLongest(self.0.as_slice()).check_indexed(buf, eof)
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        AnyLongest(self.0.as_slice()).groups(buf, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        AnyLongest(self.0.as_slice()).end(buf, eof)
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        AnyLongest(self.0.as_slice()).distance(buf, eof)
+    }
+
+    fn describe(&self) -> String {
+        AnyLongest(self.0.as_slice()).describe()
     }
 }
 
-impl<T, const N: usize> Needle for Any<&'_ [T; N]>
+impl<T, const N: usize> Needle for AnyLongest<[T; N]>
 where
     T: Needle,
 {
     fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
-        Any(&self.0[..]).check(buf, eof)
+        AnyLongest(&self.0[..]).check(buf, eof)
     }
-}
 
-impl<T: Needle> Needle for &T {
-    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
-        T::check(self, buf, eof)
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        AnyLongest(&self.0[..]).check_indexed(buf, eof)
     }
-}
 
-impl Needle for Box<dyn Needle + '_> {
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        AnyLongest(&self.0[..]).groups(buf, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        AnyLongest(&self.0[..]).end(buf, eof)
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        AnyLongest(&self.0[..]).distance(buf, eof)
+    }
+
+    fn describe(&self) -> String {
+        AnyLongest(&self.0[..]).describe()
+    }
+}
+
+impl<T> Needle for Any<&[T]>
+where
+    T: Needle,
+{
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        self.check_indexed(buf, eof).map(|(found, _)| found)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        for (i, needle) in self.0.iter().enumerate() {
+            let found = needle.check(buf, eof)?;
+            if !found.is_empty() {
+                return Ok((found, Some(i)));
+            }
+        }
+
+        Ok((Vec::new(), None))
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        match self.check_indexed(buf, eof)?.1 {
+            Some(i) => self.0[i].groups(buf, eof),
+            None => Ok(None),
+        }
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        match self.check_indexed(buf, eof)?.1 {
+            Some(i) => self.0[i].end(buf, eof),
+            None => Ok(None),
+        }
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        match self.check_indexed(buf, eof)?.1 {
+            Some(i) => self.0[i].distance(buf, eof),
+            None => Ok(None),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("Any({})", describe_all(self.0))
+    }
+}
+
+impl<T> Needle for Any<Vec<T>>
+where
+    T: Needle,
+{
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        Any(self.0.as_slice()).check(buf, eof)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        Any(self.0.as_slice()).check_indexed(buf, eof)
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        Any(self.0.as_slice()).groups(buf, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        Any(self.0.as_slice()).end(buf, eof)
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        Any(self.0.as_slice()).distance(buf, eof)
+    }
+
+    fn describe(&self) -> String {
+        Any(self.0.as_slice()).describe()
+    }
+}
+
+impl<T, const N: usize> Needle for Any<[T; N]>
+where
+    T: Needle,
+{
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        Any(&self.0[..]).check(buf, eof)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        Any(&self.0[..]).check_indexed(buf, eof)
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        Any(&self.0[..]).groups(buf, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        Any(&self.0[..]).end(buf, eof)
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        Any(&self.0[..]).distance(buf, eof)
+    }
+
+    fn describe(&self) -> String {
+        Any(&self.0[..]).describe()
+    }
+}
+
+impl<T, const N: usize> Needle for Any<&'_ [T; N]>
+where
+    T: Needle,
+{
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        Any(&self.0[..]).check(buf, eof)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        Any(&self.0[..]).check_indexed(buf, eof)
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        Any(&self.0[..]).groups(buf, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        Any(&self.0[..]).end(buf, eof)
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        Any(&self.0[..]).distance(buf, eof)
+    }
+
+    fn describe(&self) -> String {
+        Any(&self.0[..]).describe()
+    }
+}
+
+fn describe_all<T: Needle>(needles: &[T]) -> String {
+    let needles = needles.iter().map(Needle::describe).collect::<Vec<_>>();
+    format!("[{}]", needles.join(", "))
+}
+
+/// Ignoring runs a needle against a view of the buffer
+/// where a given set of bytes is elided.
+///
+/// It's handy for output which is overwritten in place,
+/// e.g. progress bars which use carriage returns.
+/// The returned matches are mapped back onto the original buffer,
+/// so the buffer itself is left intact.
+///
+/// # Example
+///
+/// ```no_run,ignore
+/// use expectrl::{spawn, Ignoring};
+///
+/// let mut p = spawn("cat").unwrap();
+/// p.expect(Ignoring("100%", b"\r")).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Ignoring<N, B>(pub N, pub B);
+
+impl<N, B> Needle for Ignoring<N, B>
+where
+    N: Needle,
+    B: AsRef<[u8]>,
+{
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        self.check_indexed(buf, eof).map(|(found, _)| found)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        let (view, index) = self.view(buf);
+        check_view(&self.0, buf, &view, &index, eof)
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        let (view, _) = self.view(buf);
+        self.0.groups(&view, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        let (view, index) = self.view(buf);
+        let end = self.0.end(&view, eof)?;
+        Ok(end.map(|end| view_end(buf, &index, end)))
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        let (view, _) = self.view(buf);
+        self.0.distance(&view, eof)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "Ignoring({}, {:?})",
+            self.0.describe(),
+            String::from_utf8_lossy(self.1.as_ref())
+        )
+    }
+}
+
+impl<N, B> Ignoring<N, B>
+where
+    B: AsRef<[u8]>,
+{
+    /// Returns the buffer without the ignored bytes,
+    /// along with an index of each left byte in the buffer.
+    fn view(&self, buf: &[u8]) -> (Vec<u8>, Vec<usize>) {
+        let ignore = self.1.as_ref();
+
+        let mut view = Vec::with_capacity(buf.len());
+        let mut index = Vec::with_capacity(buf.len());
+        for (i, &b) in buf.iter().enumerate() {
+            if !ignore.contains(&b) {
+                view.push(b);
+                index.push(i);
+            }
+        }
+
+        (view, index)
+    }
+}
+
+/// Stripped runs a needle against a view of the buffer
+/// where ANSI escape sequences (colors, cursor movements, etc.) are removed,
+/// see [`strip_ansi`].
+///
+/// The returned matches are mapped back onto the original buffer,
+/// so the sequences before a match are consumed along with it.
+///
+/// # Example
+///
+/// ```no_run,ignore
+/// use expectrl::{spawn, Stripped};
+///
+/// let mut p = spawn("ls --color=always").unwrap();
+/// p.expect(Stripped("Cargo.toml")).unwrap();
+/// ```
+///
+/// [`strip_ansi`]: crate::util::strip_ansi
+#[derive(Debug)]
+pub struct Stripped<N>(pub N);
+
+impl<N> Needle for Stripped<N>
+where
+    N: Needle,
+{
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        self.check_indexed(buf, eof).map(|(found, _)| found)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        let (view, index) = strip_ansi_indexed(buf);
+        check_view(&self.0, buf, &view, &index, eof)
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        let (view, _) = strip_ansi_indexed(buf);
+        self.0.groups(&view, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        let (view, index) = strip_ansi_indexed(buf);
+        let end = self.0.end(&view, eof)?;
+        Ok(end.map(|end| view_end(buf, &index, end)))
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        let (view, _) = strip_ansi_indexed(buf);
+        self.0.distance(&view, eof)
+    }
+
+    fn describe(&self) -> String {
+        format!("Stripped({})", self.0.describe())
+    }
+}
+
+/// Runs a needle against a view of the buffer,
+/// mapping the matches back onto the buffer.
+///
+/// `index` holds a position in the buffer of each byte of the view.
+fn check_view<N>(
+    needle: &N,
+    buf: &[u8],
+    view: &[u8],
+    index: &[usize],
+    eof: bool,
+) -> Result<(Vec<Match>, Option<usize>), Error>
+where
+    N: Needle,
+{
+    let raw_start = |pos: usize| index.get(pos).copied().unwrap_or(buf.len());
+
+    let (found, needle_index) = needle.check_indexed(view, eof)?;
+    let matches = found
+        .into_iter()
+        .map(|m| match m.start() == m.end() {
+            true => Match::new(raw_start(m.start()), raw_start(m.start())),
+            false => Match::new(raw_start(m.start()), view_end(buf, index, m.end())),
+        })
+        .collect();
+
+    Ok((matches, needle_index))
+}
+
+/// Maps an end of a range in a view onto the buffer,
+/// so the range covers the last byte of the view it includes.
+///
+/// `index` holds a position in the buffer of each byte of the view.
+fn view_end(buf: &[u8], index: &[usize], pos: usize) -> usize {
+    match pos {
+        0 => 0,
+        pos => index.get(pos - 1).map_or(buf.len(), |i| i + 1),
+    }
+}
+
+/// CollapseCr runs a needle against a view of the buffer
+/// where lines overwritten via carriage returns are rendered as a terminal would display them.
+///
+/// A `\r` which is not followed by `\n` moves a cursor to the start of the line,
+/// so the following bytes overwrite the line.
+/// The returned matches are mapped back onto the original buffer.
+///
+/// The needle is checked as is when the collapsing is turned off.
+#[derive(Debug)]
+pub(crate) struct CollapseCr<N> {
+    needle: N,
+    enabled: bool,
+}
+
+impl<N> CollapseCr<N> {
+    pub(crate) fn new(needle: N, enabled: bool) -> Self {
+        Self { needle, enabled }
+    }
+}
+
+impl<N> Needle for CollapseCr<N>
+where
+    N: Needle,
+{
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        self.check_indexed(buf, eof).map(|(found, _)| found)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        if !self.enabled {
+            return self.needle.check_indexed(buf, eof);
+        }
+
+        let view = collapse_cr(buf);
+        let bytes = view.iter().map(|&(b, _)| b).collect::<Vec<_>>();
+        let (found, needle_index) = self.needle.check_indexed(&bytes, eof)?;
+        let matches = found
+            .into_iter()
+            .map(|m| {
+                let cells = &view[m.start()..m.end()];
+                let start = cells.iter().map(|&(_, i)| i).min();
+                let end = cells.iter().map(|&(_, i)| i + 1).max();
+                match (start, end) {
+                    (Some(start), Some(end)) => Match::new(start, end),
+                    _ => {
+                        let pos = view.get(m.start()).map_or(buf.len(), |&(_, i)| i);
+                        Match::new(pos, pos)
+                    }
+                }
+            })
+            .collect();
+
+        Ok((matches, needle_index))
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        if !self.enabled {
+            return self.needle.groups(buf, eof);
+        }
+
+        let bytes = collapse_cr(buf).iter().map(|&(b, _)| b).collect::<Vec<_>>();
+        self.needle.groups(&bytes, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        if !self.enabled {
+            return self.needle.end(buf, eof);
+        }
+
+        let view = collapse_cr(buf);
+        let bytes = view.iter().map(|&(b, _)| b).collect::<Vec<_>>();
+        let end = self.needle.end(&bytes, eof)?;
+        let end = end.map(|end| view[..end].iter().map(|&(_, i)| i + 1).max().unwrap_or(0));
+
+        Ok(end)
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        if !self.enabled {
+            return self.needle.distance(buf, eof);
+        }
+
+        let bytes = collapse_cr(buf).iter().map(|&(b, _)| b).collect::<Vec<_>>();
+        self.needle.distance(&bytes, eof)
+    }
+
+    fn describe(&self) -> String {
+        self.needle.describe()
+    }
+}
+
+/// Renders lines overwritten via carriage returns,
+/// returning each rendered byte along with its index in the original buffer.
+fn collapse_cr(buf: &[u8]) -> Vec<(u8, usize)> {
+    let mut view: Vec<(u8, usize)> = Vec::with_capacity(buf.len());
+    let mut line: Vec<(u8, usize)> = Vec::new();
+    let mut column = 0;
+    for (i, &b) in buf.iter().enumerate() {
+        match b {
+            b'\r' if buf.get(i + 1) != Some(&b'\n') => column = 0,
+            b'\r' | b'\n' => {
+                view.append(&mut line);
+                view.push((b, i));
+                column = 0;
+            }
+            _ => {
+                match line.get_mut(column) {
+                    Some(cell) => *cell = (b, i),
+                    None => line.push((b, i)),
+                }
+                column += 1;
+            }
+        }
+    }
+    view.append(&mut line);
+
+    view
+}
+
+/// A transformation of a buffer view used for matching.
+type Transform = Box<dyn FnMut(&[u8]) -> Vec<u8> + Send>;
+
+/// MatchTransforms is a chain of transformations applied to a buffer view before matching.
+///
+/// It's shared between a session and the needles it wraps,
+/// so a needle doesn't borrow the session.
+#[derive(Default, Clone)]
+pub(crate) struct MatchTransforms(Arc<Mutex<Vec<Transform>>>);
+
+impl MatchTransforms {
+    pub(crate) fn push(&mut self, transform: Transform) {
+        self.lock().push(transform);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Transform>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl std::fmt::Debug for MatchTransforms {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MatchTransforms")
+            .field(&self.lock().len())
+            .finish()
+    }
+}
+
+/// Transformed runs a needle against a view of the buffer
+/// produced by a chain of user transformations.
+///
+/// The returned matches are mapped back onto the original buffer,
+/// assuming that a transformation of a prefix is a prefix of a transformation of the whole buffer.
+#[derive(Debug)]
+pub(crate) struct Transformed<N> {
+    needle: N,
+    transforms: MatchTransforms,
+}
+
+impl<N> Transformed<N> {
+    pub(crate) fn new(needle: N, transforms: &MatchTransforms) -> Self {
+        Self {
+            needle,
+            transforms: transforms.clone(),
+        }
+    }
+}
+
+impl<N> Needle for Transformed<N>
+where
+    N: Needle,
+{
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        self.check_indexed(buf, eof).map(|(found, _)| found)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        let mut transforms = self.transforms.lock();
+        if transforms.is_empty() {
+            return self.needle.check_indexed(buf, eof);
+        }
+
+        let mut view = |bytes: &[u8]| {
+            transforms
+                .iter_mut()
+                .fold(bytes.to_vec(), |bytes, transform| transform(&bytes))
+        };
+
+        let (found, needle_index) = self.needle.check_indexed(&view(buf), eof)?;
+        let matches = found
+            .into_iter()
+            .map(|m| {
+                // the shortest prefix which view covers the match
+                let end =
+                    partition_point(buf.len(), |n| view(&buf[..n]).len() >= m.end()).min(buf.len());
+                // the longest prefix which view is before the match
+                let start = partition_point(end, |n| view(&buf[..n]).len() > m.start());
+                let start = start.saturating_sub(1);
+
+                Match::new(start, end)
+            })
+            .collect();
+
+        Ok((matches, needle_index))
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        let mut transforms = self.transforms.lock();
+        if transforms.is_empty() {
+            return self.needle.groups(buf, eof);
+        }
+
+        let view = transforms
+            .iter_mut()
+            .fold(buf.to_vec(), |bytes, transform| transform(&bytes));
+        self.needle.groups(&view, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        let mut transforms = self.transforms.lock();
+        if transforms.is_empty() {
+            return self.needle.end(buf, eof);
+        }
+
+        let mut view = |bytes: &[u8]| {
+            transforms
+                .iter_mut()
+                .fold(bytes.to_vec(), |bytes, transform| transform(&bytes))
+        };
+
+        let end = match self.needle.end(&view(buf), eof)? {
+            Some(end) => end,
+            None => return Ok(None),
+        };
+
+        // the shortest prefix which view covers the end
+        let end = partition_point(buf.len(), |n| view(&buf[..n]).len() >= end).min(buf.len());
+
+        Ok(Some(end))
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        let mut transforms = self.transforms.lock();
+        if transforms.is_empty() {
+            return self.needle.distance(buf, eof);
+        }
+
+        let view = transforms
+            .iter_mut()
+            .fold(buf.to_vec(), |bytes, transform| transform(&bytes));
+        self.needle.distance(&view, eof)
+    }
+
+    fn describe(&self) -> String {
+        self.needle.describe()
+    }
+}
+
+/// Returns the smallest `n` in `0..=max` for which a monotonic `pred` is true,
+/// or `max + 1` if there's none.
+fn partition_point(max: usize, mut pred: impl FnMut(usize) -> bool) -> usize {
+    let (mut lo, mut hi) = (0, max + 1);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        match pred(mid) {
+            true => hi = mid,
+            false => lo = mid + 1,
+        }
+    }
+
+    lo
+}
+
+/// NonEmpty matches all bytes in a buffer as long as there's at least one.
+#[derive(Debug)]
+pub(crate) struct NonEmpty;
+
+impl Needle for NonEmpty {
+    fn check(&self, buf: &[u8], _: bool) -> Result<Vec<Match>, Error> {
+        match buf.is_empty() {
+            true => Ok(Vec::new()),
+            false => Ok(vec![Match::new(0, buf.len())]),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Until looks up either an item or a stop needle,
+/// whichever is found first in a buffer.
+///
+/// It remembers whether the last match was made by the stop needle.
+#[derive(Debug)]
+pub(crate) struct Until<I, T> {
+    item: I,
+    stop: T,
+    stopped: Cell<bool>,
+}
+
+impl<I, T> Until<I, T> {
+    pub(crate) fn new(item: I, stop: T) -> Self {
+        Self {
+            item,
+            stop,
+            stopped: Cell::new(false),
+        }
+    }
+
+    /// Returns true if the last match was made by the stop needle.
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.get()
+    }
+}
+
+impl<I, T> Until<I, T>
+where
+    I: Needle,
+    T: Needle,
+{
+    /// Looks up both needles, returning the matches of the one found first
+    /// along with its inner index and whether it's the stop needle.
+    fn choose(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>, bool), Error> {
+        let left_most = |matches: &[Match]| matches.iter().map(Match::start).min();
+
+        let stop = self.stop.check_indexed(buf, eof)?;
+        let item = self.item.check_indexed(buf, eof)?;
+
+        let is_stop = match (left_most(&stop.0), left_most(&item.0)) {
+            (Some(stop), Some(item)) => stop <= item,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+
+        match is_stop {
+            true => Ok((stop.0, stop.1, true)),
+            false => Ok((item.0, item.1, false)),
+        }
+    }
+}
+
+impl<I, T> Needle for Until<I, T>
+where
+    I: Needle,
+    T: Needle,
+{
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        self.check_indexed(buf, eof).map(|(found, _)| found)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        let (found, needle_index, is_stop) = self.choose(buf, eof)?;
+        self.stopped.set(is_stop);
+
+        Ok((found, needle_index))
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        match self.choose(buf, eof)?.2 {
+            true => self.stop.groups(buf, eof),
+            false => self.item.groups(buf, eof),
+        }
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        match self.choose(buf, eof)?.2 {
+            true => self.stop.end(buf, eof),
+            false => self.item.end(buf, eof),
+        }
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        match self.choose(buf, eof)?.2 {
+            true => self.stop.distance(buf, eof),
+            false => self.item.distance(buf, eof),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("Until({}, {})", self.item.describe(), self.stop.describe())
+    }
+}
+
+/// Nth matches the nth occurrence of a needle, skipping the earlier ones.
+///
+/// Each next occurrence is looked up right after the end of the previous one,
+/// or a byte further if the previous one is empty.
+#[derive(Debug)]
+pub(crate) struct Nth<N> {
+    needle: N,
+    n: usize,
+}
+
+impl<N> Nth<N> {
+    /// Creates a needle for the nth occurrence, counting from 1; 0 is treated as 1.
+    pub(crate) fn new(needle: N, n: usize) -> Self {
+        Self {
+            needle,
+            n: n.max(1),
+        }
+    }
+}
+
+impl<N: Needle> Nth<N> {
+    /// Returns a position in a buffer the nth occurrence is looked up from,
+    /// or `None` if there are less than `n - 1` occurrences.
+    fn offset(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        let mut offset = 0;
+        for _ in 1..self.n {
+            let rest = &buf[offset..];
+            let found = self.needle.check(rest, eof)?;
+            if found.is_empty() {
+                return Ok(None);
+            }
+
+            // An empty match would be found again on the same spot,
+            // so the next occurrence is looked up at least a byte further.
+            let end = Captures::right_most_index(&found);
+            let end = self
+                .needle
+                .end(rest, eof)?
+                .map_or(end, |consumed| consumed.max(end));
+            offset += end.max(1);
+            if offset > buf.len() {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(offset))
+    }
+}
+
+impl<N: Needle> Needle for Nth<N> {
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        self.check_indexed(buf, eof).map(|(found, _)| found)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        let offset = match self.offset(buf, eof)? {
+            Some(offset) => offset,
+            None => return Ok((Vec::new(), None)),
+        };
+
+        let (found, needle_index) = self.needle.check_indexed(&buf[offset..], eof)?;
+        let found = found
+            .into_iter()
+            .map(|m| Match::new(m.start + offset, m.end + offset))
+            .collect();
+
+        Ok((found, needle_index))
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        match self.offset(buf, eof)? {
+            Some(offset) => self.needle.groups(&buf[offset..], eof),
+            None => Ok(None),
+        }
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        match self.offset(buf, eof)? {
+            Some(offset) => {
+                let end = self.needle.end(&buf[offset..], eof)?;
+                Ok(end.map(|end| end + offset))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        match self.offset(buf, eof)? {
+            Some(offset) => self.needle.distance(&buf[offset..], eof),
+            None => Ok(None),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("Nth({}, {})", self.needle.describe(), self.n)
+    }
+}
+
+impl Needle for &[&str] {
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        Any(*self).check(buf, eof)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        Any(*self).check_indexed(buf, eof)
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        Any(*self).groups(buf, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        Any(*self).end(buf, eof)
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        Any(*self).distance(buf, eof)
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl Needle for Vec<String> {
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        Any(self.as_slice()).check(buf, eof)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        Any(self.as_slice()).check_indexed(buf, eof)
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        Any(self.as_slice()).groups(buf, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        Any(self.as_slice()).end(buf, eof)
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        Any(self.as_slice()).distance(buf, eof)
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl<T: Needle> Needle for &T {
+    fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
+        T::check(self, buf, eof)
+    }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        T::check_indexed(self, buf, eof)
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        T::groups(self, buf, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        T::end(self, buf, eof)
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        T::distance(self, buf, eof)
+    }
+
+    fn describe(&self) -> String {
+        T::describe(self)
+    }
+}
+
+impl Needle for Box<dyn Needle + '_> {
     fn check(&self, buf: &[u8], eof: bool) -> Result<Vec<Match>, Error> {
         self.as_ref().check(buf, eof)
     }
+
+    fn check_indexed(&self, buf: &[u8], eof: bool) -> Result<(Vec<Match>, Option<usize>), Error> {
+        self.as_ref().check_indexed(buf, eof)
+    }
+
+    fn groups(&self, buf: &[u8], eof: bool) -> Result<Option<Groups>, Error> {
+        self.as_ref().groups(buf, eof)
+    }
+
+    fn end(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        self.as_ref().end(buf, eof)
+    }
+
+    fn distance(&self, buf: &[u8], eof: bool) -> Result<Option<usize>, Error> {
+        self.as_ref().distance(buf, eof)
+    }
+
+    fn describe(&self) -> String {
+        self.as_ref().describe()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "regex")]
     #[test]
     fn test_regex() {
         assert_eq!(
@@ -270,6 +1795,33 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_groups() {
+        let groups = Regex(r"(?P<key>\w+)=(x)?(?P<value>\w+)")
+            .groups(b"asd=123", false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(groups.group_of(0), Some(0));
+        assert_eq!(groups.group_of(1), Some(1));
+        assert_eq!(groups.group_of(2), Some(3));
+        assert_eq!(groups.group_of(3), None);
+        assert_eq!(groups.index_of("value"), Some(3));
+        assert_eq!(groups.index_of("x"), None);
+
+        // groups are reported through needles which wrap others
+        let found = find(
+            &Stripped(Any(["asd", r"(\w)=(\d)"].map(Regex))),
+            b"\x1b[1mq=1",
+            false,
+        )
+        .unwrap();
+        assert_eq!(found.needle_index(), Some(1));
+        assert_eq!(found.group(2), Some(b"1".as_ref()));
+
+        assert_eq!("asd".groups(b"asd", false).unwrap(), None);
+    }
+
     #[test]
     fn test_eof() {
         assert_eq!(Eof.check(b"qwe", true).unwrap(), vec![Match::new(0, 3)]);
@@ -299,6 +1851,237 @@ mod tests {
         assert_eq!("".check(b"qwerty", false).unwrap(), vec![Match::new(0, 0)]);
     }
 
+    #[test]
+    fn test_trimmed_match() {
+        assert_eq!(
+            TrimmedMatch("$ ").check(b"user$", false).unwrap(),
+            vec![Match::new(4, 5)]
+        );
+        assert_eq!(
+            TrimmedMatch("$").check(b"user$ \t", false).unwrap(),
+            vec![Match::new(4, 7)]
+        );
+        assert_eq!(
+            TrimmedMatch("$ ").check(b"user$\r\n", false).unwrap(),
+            vec![Match::new(4, 5)]
+        );
+        assert_eq!(TrimmedMatch("#").check(b"user$ ", false).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!("Hello".describe(), r#""Hello""#);
+        assert_eq!(b"Hello".as_slice().describe(), r#""Hello""#);
+        assert_eq!(NBytes(3).describe(), "NBytes(3)");
+        assert_eq!(Any(vec!["a", "b"]).describe(), r#"Any(["a", "b"])"#);
+        assert_eq!(Stripped(Eof).describe(), "Stripped(Eof)");
+        assert_eq!(Nth::new("a", 2).describe(), r#"Nth("a", 2)"#);
+
+        let needle: Box<dyn Needle> = Box::new(Line);
+        assert_eq!(needle.describe(), "Line");
+        assert_eq!(NonEmpty.describe(), "NonEmpty");
+    }
+
+    #[test]
+    fn test_line() {
+        assert_eq!(
+            Line.check(b"Hello\r\nWorld\r\n", false).unwrap(),
+            vec![Match::new(0, 5)]
+        );
+        assert_eq!(Line.end(b"Hello\r\nWorld\r\n", false).unwrap(), Some(7));
+        assert_eq!(
+            Line.check(b"Hello\nWorld", false).unwrap(),
+            vec![Match::new(0, 5)]
+        );
+        assert_eq!(Line.end(b"Hello\nWorld", false).unwrap(), Some(6));
+        assert_eq!(Line.check(b"\r\n", false).unwrap(), vec![Match::new(0, 0)]);
+        assert_eq!(Line.check(b"Hello\r", false).unwrap(), vec![]);
+        assert_eq!(
+            Line.check(b"Hello\r", true).unwrap(),
+            vec![Match::new(0, 6)]
+        );
+        assert_eq!(Line.end(b"Hello\r", true).unwrap(), None);
+        assert_eq!(Line.check(b"", true).unwrap(), vec![]);
+
+        let found = find(&Line, b"Hello\r\nWorld", false).unwrap();
+        assert_eq!(found.before(), b"");
+        assert_eq!(&found[0], b"Hello");
+        assert_eq!(found.matches().collect::<Vec<_>>(), vec![&b"Hello"[..]]);
+        assert_eq!(found.as_bytes(), b"Hello\r\n");
+
+        let found = find(&Line, b"World", true).unwrap();
+        assert_eq!(found.matches().collect::<Vec<_>>(), vec![&b"World"[..]]);
+        assert_eq!(found.as_bytes(), b"World");
+
+        let found = find(&Stripped(Line), b"\x1b[1mHello\x1b[0m\r\nWorld", false).unwrap();
+        assert_eq!(&found[0], b"Hello");
+        assert_eq!(found.as_bytes(), b"\x1b[1mHello\x1b[0m\r\n");
+
+        let found = find(
+            &Any::boxed(vec![Box::new("x"), Box::new(Line)]),
+            b"Hello\nWorld",
+            false,
+        )
+        .unwrap();
+        assert_eq!(found.as_bytes(), b"Hello\n");
+    }
+
+    #[test]
+    fn test_no_case() {
+        assert_eq!(
+            NoCase("WeR").check(b"qWErty", false).unwrap(),
+            vec![Match::new(1, 4)]
+        );
+        assert_eq!(
+            NoCase("ty").check(b"qwerTY", true).unwrap(),
+            vec![Match::new(4, 6)]
+        );
+        assert_eq!(NoCase("123").check(b"qwerty", false).unwrap(), vec![]);
+        assert_eq!(NoCase("qwertyu").check(b"qwerty", true).unwrap(), vec![]);
+        assert_eq!(
+            NoCase("").check(b"qwerty", false).unwrap(),
+            vec![Match::new(0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_nth() {
+        assert_eq!(
+            Nth::new("$ ", 2).check(b"$ ls\r\n$ ", false).unwrap(),
+            vec![Match::new(6, 8)]
+        );
+        assert_eq!(
+            Nth::new("$ ", 1).check(b"$ ls\r\n$ ", false).unwrap(),
+            vec![Match::new(0, 2)]
+        );
+        assert_eq!(
+            Nth::new("$ ", 0).check(b"$ ls\r\n$ ", false).unwrap(),
+            vec![Match::new(0, 2)]
+        );
+        assert_eq!(
+            Nth::new("$ ", 3).check(b"$ ls\r\n$ ", false).unwrap(),
+            vec![]
+        );
+        assert_eq!(
+            Nth::new(NBytes(0), 3).check(b"ab", false).unwrap(),
+            vec![Match::new(2, 2)]
+        );
+        assert_eq!(Nth::new(NBytes(0), 4).check(b"ab", false).unwrap(), vec![]);
+
+        let found = find(&Nth::new(&["$", "#"][..], 2), b"$ ls\r\n# ", false).unwrap();
+        assert_eq!(found.needle_index(), Some(1));
+        assert_eq!(found.before(), b"$ ls\r\n");
+
+        let found = find(&Nth::new(Line, 2), b"first\r\nsecond\r\nthird", false).unwrap();
+        assert_eq!(&found[0], b"second");
+        assert_eq!(found.as_bytes(), b"first\r\nsecond\r\n");
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_nth_groups() {
+        let needle = Any::boxed(vec![Box::new("A "), Box::new(Regex("port=(?P<port>\\d+)"))]);
+        let found = find(&Nth::new(needle, 2), b"A port=8080", false).unwrap();
+        assert_eq!(found.needle_index(), Some(1));
+        assert_eq!(found.name("port"), Some(b"8080".as_ref()));
+        assert_eq!(found.group(0), Some(b"port=8080".as_ref()));
+    }
+
+    #[test]
+    fn test_collapse_cr() {
+        let needle = CollapseCr::new("30%", true);
+        assert_eq!(
+            needle.check(b"10%\r20%\r30%\r", false).unwrap(),
+            vec![Match::new(8, 11)]
+        );
+
+        let needle = CollapseCr::new("10%", true);
+        assert_eq!(needle.check(b"10%\r20%\r\n", false).unwrap(), vec![]);
+
+        let needle = CollapseCr::new("50%%", true);
+        assert_eq!(
+            needle.check(b"100%\r50%", false).unwrap(),
+            vec![Match::new(3, 8)]
+        );
+
+        let needle = CollapseCr::new("1\r2", false);
+        assert_eq!(
+            needle.check(b"1\r2", false).unwrap(),
+            vec![Match::new(0, 3)]
+        );
+        let needle = CollapseCr::new("1\r2", true);
+        assert_eq!(needle.check(b"1\r2", false).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_transformed() {
+        let mut transforms = MatchTransforms::default();
+        assert_eq!(
+            Transformed::new("bc", &transforms)
+                .check(b"a**BC*d", false)
+                .unwrap(),
+            vec![]
+        );
+
+        transforms.push(Box::new(|buf| {
+            buf.iter().filter(|&&b| b != b'*').copied().collect()
+        }));
+        transforms.push(Box::new(|buf| buf.to_ascii_lowercase()));
+        assert_eq!(
+            Transformed::new("bc", &transforms)
+                .check(b"a**BC*d", false)
+                .unwrap(),
+            vec![Match::new(3, 5)]
+        );
+        assert_eq!(
+            Transformed::new("cd", &transforms)
+                .check(b"a**BC*d", false)
+                .unwrap(),
+            vec![Match::new(4, 7)]
+        );
+
+        transforms.clear();
+        assert_eq!(
+            Transformed::new("bc", &transforms)
+                .check(b"a**BC*d", false)
+                .unwrap(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_length_prefixed() {
+        let needle = LengthPrefixed {
+            len_bytes: 4,
+            endian: Endian::Big,
+        };
+        assert_eq!(
+            needle.check(b"\0\0\0\x03abcd", false).unwrap(),
+            vec![Match::new(4, 7)]
+        );
+        assert_eq!(needle.check(b"\0\0\0\x03ab", false).unwrap(), vec![]);
+        assert_eq!(needle.check(b"\0\0", false).unwrap(), vec![]);
+        assert_eq!(
+            needle.check(b"\0\0\0\0", false).unwrap(),
+            vec![Match::new(4, 4)]
+        );
+
+        let needle = LengthPrefixed {
+            len_bytes: 2,
+            endian: Endian::Little,
+        };
+        assert_eq!(
+            needle.check(b"\x02\0ab", false).unwrap(),
+            vec![Match::new(2, 4)]
+        );
+
+        let needle = LengthPrefixed {
+            len_bytes: 9,
+            endian: Endian::Little,
+        };
+        assert!(needle.check(b"\x02\0ab", false).is_err());
+    }
+
     #[test]
     fn test_bytes() {
         assert_eq!(
@@ -309,18 +2092,14 @@ mod tests {
         assert_eq!(b"".check(b"qwerty", false).unwrap(), vec![Match::new(0, 0)]);
     }
 
-    #[allow(clippy::needless_borrow)]
     #[test]
     #[allow(clippy::needless_borrow)]
     fn test_bytes_ref() {
         assert_eq!(
-            (&[b'q', b'w', b'e']).check(b"qwerty", false).unwrap(),
+            (b"qwe").check(b"qwerty", false).unwrap(),
             vec![Match::new(0, 3)]
         );
-        assert_eq!(
-            (&[b'1', b'2', b'3']).check(b"qwerty", false).unwrap(),
-            vec![]
-        );
+        assert_eq!((b"123").check(b"qwerty", false).unwrap(), vec![]);
         assert_eq!(
             (&[]).check(b"qwerty", false).unwrap(),
             vec![Match::new(0, 0)]
@@ -375,4 +2154,258 @@ mod tests {
             vec![Match::new(3, 6)]
         );
     }
+
+    #[test]
+    fn test_any_longest() {
+        let needles = ["Error", "Error: fatal", "fatal"];
+
+        assert_eq!(
+            Any::ordered(needles)
+                .check_indexed(b"> Error: fatal", false)
+                .unwrap(),
+            (vec![Match::new(2, 7)], Some(0))
+        );
+        assert_eq!(
+            Any::longest(needles)
+                .check_indexed(b"> Error: fatal", false)
+                .unwrap(),
+            (vec![Match::new(2, 14)], Some(1))
+        );
+        assert_eq!(
+            Any::longest(&needles[..])
+                .check_indexed(b"> Error: fat", false)
+                .unwrap(),
+            (vec![Match::new(2, 7)], Some(0))
+        );
+        assert_eq!(
+            Any::longest(vec!["fatal", "Error"])
+                .check_indexed(b"> Error: fatal", false)
+                .unwrap(),
+            (vec![Match::new(2, 7)], Some(1))
+        );
+        assert_eq!(
+            Any::longest(vec!["a", "a"])
+                .check_indexed(b"a", false)
+                .unwrap(),
+            (vec![Match::new(0, 1)], Some(0))
+        );
+        assert_eq!(
+            Any::longest(needles).check_indexed(b"ok", false).unwrap(),
+            (vec![], None)
+        );
+    }
+
+    #[test]
+    fn test_check_indexed() {
+        assert_eq!(
+            Any(["123", "234", "rty"])
+                .check_indexed(b"qwerty", false)
+                .unwrap(),
+            (vec![Match::new(3, 6)], Some(2))
+        );
+        assert_eq!(
+            Any(["123", "234"]).check_indexed(b"qwerty", false).unwrap(),
+            (vec![], None)
+        );
+        assert_eq!(
+            (&["123", "we"][..])
+                .check_indexed(b"qwerty", false)
+                .unwrap(),
+            (vec![Match::new(1, 3)], Some(1))
+        );
+        assert_eq!(
+            vec!["ty".to_string()]
+                .check_indexed(b"qwerty", false)
+                .unwrap(),
+            (vec![Match::new(4, 6)], Some(0))
+        );
+        assert_eq!(
+            Ignoring(Any(["x", "wer"]), b"\r")
+                .check_indexed(b"qw\rerty", false)
+                .unwrap(),
+            (vec![Match::new(1, 5)], Some(1))
+        );
+        assert_eq!(
+            "we".check_indexed(b"qwerty", false).unwrap(),
+            (vec![Match::new(1, 3)], None)
+        );
+    }
+
+    #[test]
+    fn test_ignoring() {
+        assert_eq!(
+            Ignoring("100%", b"\r").check(b"10%\r100%", false).unwrap(),
+            vec![Match::new(4, 8)]
+        );
+        assert_eq!(
+            Ignoring("abc", b"\r").check(b"xa\rb\r\rcy", false).unwrap(),
+            vec![Match::new(1, 7)]
+        );
+        assert_eq!(
+            Ignoring("abc", b"\r").check(b"ab\rd", false).unwrap(),
+            vec![]
+        );
+        assert_eq!(
+            Ignoring(Eof, "\r").check(b"a\rb", true).unwrap(),
+            vec![Match::new(0, 3)]
+        );
+        assert_eq!(
+            Ignoring("", "\r").check(b"\rab", false).unwrap(),
+            vec![Match::new(1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_stripped() {
+        assert_eq!(
+            Stripped("Hello World")
+                .check(b"\x1b[1m\x1b[31mHello\x1b[m World\x1b[m", false)
+                .unwrap(),
+            vec![Match::new(9, 23)]
+        );
+        assert_eq!(
+            Stripped("World")
+                .check(b"Hello \x1b]0;World\x07", false)
+                .unwrap(),
+            vec![]
+        );
+        assert_eq!(
+            Stripped(Eof).check(b"a\x1b[mb", true).unwrap(),
+            vec![Match::new(0, 5)]
+        );
+        assert_eq!(
+            Stripped("").check(b"\x1b[mab", false).unwrap(),
+            vec![Match::new(3, 3)]
+        );
+
+        let found = find(&Stripped("Hello"), b"\x1b[32m$\x1b[m Hello", false).unwrap();
+        assert_eq!(found.before(), b"\x1b[32m$\x1b[m ");
+        assert_eq!(&found[0], b"Hello");
+    }
+
+    #[test]
+    fn test_fuzzy() {
+        let fuzzy = |pattern: &str, max_distance| Fuzzy {
+            pattern: pattern.to_string(),
+            max_distance,
+            window: None,
+        };
+
+        assert_eq!(
+            fuzzy("login:", 0).check(b"xx login: ", false).unwrap(),
+            vec![Match::new(3, 9)]
+        );
+        assert_eq!(
+            fuzzy("login:", 0).check(b"xx lo\xffin: ", false).unwrap(),
+            vec![]
+        );
+        assert_eq!(
+            fuzzy("login:", 1).check(b"xx lo\xffin: ", false).unwrap(),
+            vec![Match::new(3, 9)]
+        );
+        assert_eq!(
+            fuzzy("login:", 1).check(b"xx logn: ", false).unwrap(),
+            vec![Match::new(3, 8)]
+        );
+        assert_eq!(
+            fuzzy("login:", 1).check(b"xx logiin: ", false).unwrap(),
+            vec![Match::new(3, 10)]
+        );
+        assert_eq!(
+            fuzzy("login:", 1).check(b"xx lxgxn: ", false).unwrap(),
+            vec![]
+        );
+        assert_eq!(
+            fuzzy("hello", 1).check(b"hello", false).unwrap(),
+            vec![Match::new(0, 5)]
+        );
+        // a part of the pattern may be not received yet
+        assert_eq!(fuzzy("hello", 1).check(b"hell", false).unwrap(), vec![]);
+        assert_eq!(
+            fuzzy("hello", 1).check(b"hell", true).unwrap(),
+            vec![Match::new(0, 4)]
+        );
+        assert_eq!(
+            fuzzy("hello", 1).check(b"hell\n", false).unwrap(),
+            vec![Match::new(0, 4)]
+        );
+        assert_eq!(
+            fuzzy("", 1).check(b"hello", false).unwrap(),
+            vec![Match::new(0, 0)]
+        );
+
+        assert_eq!(
+            fuzzy("login:", 1).distance(b"login:", false).unwrap(),
+            Some(0)
+        );
+        assert_eq!(
+            fuzzy("login:", 1).distance(b"lo\xffin:", false).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            fuzzy("login:", 1).distance(b"xx logiin: ", false).unwrap(),
+            Some(1)
+        );
+        assert_eq!(fuzzy("login:", 1).distance(b"lxgxn:", false).unwrap(), None);
+        assert_eq!(fuzzy("", 1).distance(b"ab", false).unwrap(), Some(0));
+
+        let found = find(&fuzzy("login:", 1), b"xx logn: ", false).unwrap();
+        assert_eq!(&found[0], b"logn:");
+        assert_eq!(found.distance(), Some(1));
+        let found = find(&Stripped(fuzzy("login:", 1)), b"\x1b[1mlogn: ", false).unwrap();
+        assert_eq!(found.distance(), Some(1));
+        assert_eq!(find(&"login:", b"login:", false).unwrap().distance(), None);
+
+        let windowed = Fuzzy {
+            window: Some(8),
+            ..fuzzy("login:", 1)
+        };
+        assert_eq!(
+            windowed.check(b"login: xxxx logn: ", false).unwrap(),
+            vec![Match::new(12, 17)]
+        );
+        assert_eq!(windowed.check(b"login: xxxxxxxx", false).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_find() {
+        let found = find(&Any(["Error", "World"]), b"Hello World", false).unwrap();
+        assert_eq!(found.before(), b"Hello ");
+        assert_eq!(found.get(0), Some(&b"World"[..]));
+        assert_eq!(found.needle_index(), Some(1));
+
+        let found = find(&"Bye", b"Hello World", false).unwrap();
+        assert!(found.is_empty());
+
+        let found = find(&Eof, b"Hello World", true).unwrap();
+        assert_eq!(found.get(0), Some(&b"Hello World"[..]));
+    }
+
+    #[test]
+    fn test_until() {
+        let until = Until::new('\n', "DONE");
+        assert_eq!(
+            until.check(b"1\nDONE", false).unwrap(),
+            vec![Match::new(1, 2)]
+        );
+        assert!(!until.is_stopped());
+        assert_eq!(
+            until.check(b"DONE\n1\n", false).unwrap(),
+            vec![Match::new(0, 4)]
+        );
+        assert!(until.is_stopped());
+        assert_eq!(until.check(b"1", false).unwrap(), vec![]);
+        assert!(!until.is_stopped());
+
+        let until = Until::new(Line, &["DONE", "FAIL"][..]);
+        assert_eq!(until.describe(), r#"Until(Line, ["DONE", "FAIL"])"#);
+
+        let found = find(&until, b"1\r\n2\r\n", false).unwrap();
+        assert_eq!(&found[0], b"1");
+        assert_eq!(found.as_bytes(), b"1\r\n");
+
+        let found = find(&until, b"FAIL\r\n", false).unwrap();
+        assert!(until.is_stopped());
+        assert_eq!(found.needle_index(), Some(1));
+    }
 }
@@ -2,12 +2,40 @@
 
 use std::io::Result;
 
+#[cfg(unix)]
+pub mod pipe;
 #[cfg(unix)]
 pub mod unix;
 #[cfg(windows)]
 pub mod windows;
 
 /// This trait represents a platform independent process which runs a program.
+///
+/// # Implementing a custom process
+///
+/// A program doesn't have to run locally,
+/// it may be run in a container or on a remote host (e.g. via a custom protocol).
+/// Nothing in a [`Session`](crate::Session) relies on a file descriptor,
+/// a session is built from a process and its stream via [`Session::new`](crate::Session::new).
+///
+/// - [`Process::Stream`] is the only way to talk to a program.
+///   It must implement [`std::io::Read`], [`std::io::Write`] and [`NonBlocking`],
+///   see the [`NonBlocking`] contract.
+///   With the `async` feature it must implement `IntoAsyncStream` instead,
+///   producing an `AsyncRead + AsyncWrite` stream.
+/// - [`Process::open_stream`] is called once per session,
+///   the stream is owned by the session from then on.
+/// - [`Healthcheck`] is optional, without it a session can expect and send,
+///   but it can't tell whether a program is alive, so the session doesn't implement [`Healthcheck`]
+///   and methods like [`Session::set_send_checks_alive`](crate::Session::set_send_checks_alive)
+///   and interact aren't available.
+///   It must not block.
+///   Interact also requires [`Healthcheck::Status`] to be [`WaitStatus`](unix::WaitStatus) on unix.
+/// - [`Termios`] is optional, it's used to turn echo on and off,
+///   e.g. by [`Session::send_line_no_echo`](crate::Session::send_line_no_echo).
+///   If a backend has no terminal it's better not to implement it.
+///
+/// See `examples/custom_process.rs` for a process which runs on a "remote" host.
 pub trait Process: Sized {
     /// A command which process can run.
     type Command;
@@ -67,8 +95,49 @@ where
     }
 }
 
+/// NoProcess is a [`Process`] placeholder for a session which doesn't own a spawned process,
+/// e.g. a session over an existing pty or a socket.
+///
+/// It's always considered to be alive.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NoProcess;
+
+impl Healthcheck for NoProcess {
+    #[cfg(unix)]
+    type Status = unix::WaitStatus;
+    #[cfg(windows)]
+    type Status = ();
+
+    fn get_status(&self) -> Result<Self::Status> {
+        #[cfg(unix)]
+        {
+            Ok(unix::WaitStatus::StillAlive)
+        }
+
+        #[cfg(windows)]
+        {
+            Ok(())
+        }
+    }
+
+    fn is_alive(&self) -> Result<bool> {
+        Ok(true)
+    }
+}
+
 /// NonBlocking interface represens a [std::io::Read]er which can be turned in a non blocking mode
 /// so its read operations will return imideately.
+///
+/// It's the only thing a custom stream needs besides [`std::io::Read`] and [`std::io::Write`]
+/// to be used in a [`Session`](crate::Session) via [`Session::new`](crate::Session::new),
+/// no file descriptor is required.
+///
+/// A contract is the following.
+/// Once `set_blocking(false)` is called a read must not block;
+/// it must return [`std::io::ErrorKind::WouldBlock`] if there's nothing to read yet,
+/// and `Ok(0)` only when the stream is over (EOF).
+///
+/// See `examples/custom_stream.rs` for an in-memory stream example.
 pub trait NonBlocking {
     /// Sets a [std::io::Read]er into a non/blocking mode.
     fn set_blocking(&mut self, on: bool) -> Result<()>;
@@ -83,12 +152,26 @@ where
     }
 }
 
+impl NonBlocking for std::net::TcpStream {
+    fn set_blocking(&mut self, on: bool) -> Result<()> {
+        self.set_nonblocking(!on)
+    }
+}
+
 /// Terminal configuration trait, used for IO configuration.
 pub trait Termios {
     /// Verifies whether a [`std::io::Write`] will be repeated in output stream and be read by [`std::io::Read`].
     fn is_echo(&self) -> Result<bool>;
     /// Configure a echo logic.
     fn set_echo(&mut self, on: bool) -> Result<bool>;
+
+    /// Waits for a terminal to process input written to it so far,
+    /// so a change of the configuration made afterwards doesn't affect it.
+    ///
+    /// By default it does nothing.
+    fn settle_input(&mut self) -> Result<()> {
+        Ok(())
+    }
 }
 
 impl<T> Termios for &mut T
@@ -102,6 +185,10 @@ where
     fn set_echo(&mut self, on: bool) -> Result<bool> {
         T::set_echo(self, on)
     }
+
+    fn settle_input(&mut self) -> Result<()> {
+        T::settle_input(self)
+    }
 }
 
 #[cfg(feature = "async")]
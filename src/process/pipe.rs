@@ -0,0 +1,238 @@
+//! This module contains a [Process] which talks to a program via plain pipes instead of a pty.
+//!
+//! There's no terminal in between, so the output is byte exact
+//! (e.g. `\n` isn't turned into `\r\n`),
+//! but programs which need a tty (e.g. to read a password or to draw a UI) won't work as usual.
+
+use std::{
+    io::{self, Read, Result, Write},
+    os::unix::prelude::{AsRawFd, ExitStatusExt},
+    process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio},
+    sync::{Mutex, PoisonError},
+};
+
+use nix::unistd::Pid;
+
+use crate::{
+    error::to_io_error,
+    process::{
+        unix::{make_non_blocking, parse_command, Signal, WaitStatus},
+        Healthcheck, NonBlocking, Process,
+    },
+};
+
+#[cfg(feature = "async")]
+use super::IntoAsyncStream;
+#[cfg(feature = "async")]
+use futures_lite::{AsyncRead, AsyncWrite};
+#[cfg(feature = "async")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A process which stdin and stdout are connected to pipes.
+///
+/// The stderr is left as it's set in a [Command], it's inherited by default.
+#[derive(Debug)]
+pub struct PipeProcess {
+    // A mutex is used as checking a status requires a mutable access,
+    // while [Healthcheck] provides only a shared one.
+    child: Mutex<Child>,
+}
+
+impl Process for PipeProcess {
+    type Command = Command;
+    type Stream = PipeStream;
+
+    fn spawn<S>(cmd: S) -> Result<Self>
+    where
+        S: AsRef<str>,
+    {
+        let command = parse_command(cmd.as_ref())?;
+        Self::spawn_command(command)
+    }
+
+    fn spawn_command(mut command: Self::Command) -> Result<Self> {
+        let child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(to_io_error("Failed to spawn a command"))?;
+
+        Ok(Self {
+            child: Mutex::new(child),
+        })
+    }
+
+    fn open_stream(&mut self) -> Result<Self::Stream> {
+        let child = self.child();
+        match (child.stdin.take(), child.stdout.take()) {
+            (Some(stdin), Some(stdout)) => Ok(PipeStream::new(stdin, stdout)),
+            _ => Err(io::Error::other("the stream was already opened")),
+        }
+    }
+}
+
+impl PipeProcess {
+    /// Returns the underlying child process,
+    /// e.g. to get its id or to kill it.
+    pub fn child(&mut self) -> &mut Child {
+        self.child.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn try_wait(&self) -> Result<Option<ExitStatus>> {
+        self.child
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .try_wait()
+    }
+
+    fn pid(&self) -> Pid {
+        let id = self
+            .child
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .id();
+        Pid::from_raw(id as i32)
+    }
+}
+
+impl Drop for PipeProcess {
+    fn drop(&mut self) {
+        // The process is killed as a pty one is, so it's not left running unattended.
+        let child = self.child();
+        if let Ok(None) = child.try_wait() {
+            let _ = child.kill();
+        }
+
+        let _ = child.wait();
+    }
+}
+
+impl Healthcheck for PipeProcess {
+    type Status = WaitStatus;
+
+    fn get_status(&self) -> Result<Self::Status> {
+        let status = match self.try_wait()? {
+            Some(status) => status,
+            None => return Ok(WaitStatus::StillAlive),
+        };
+
+        let pid = self.pid();
+        if let Some(code) = status.code() {
+            return Ok(WaitStatus::Exited(pid, code));
+        }
+
+        match status.signal() {
+            Some(signal) => {
+                let signal = Signal::try_from(signal).map_err(io::Error::other)?;
+                Ok(WaitStatus::Signaled(pid, signal, status.core_dumped()))
+            }
+            None => Err(io::Error::other(format!(
+                "unexpected exit status {}",
+                status
+            ))),
+        }
+    }
+
+    fn is_alive(&self) -> Result<bool> {
+        self.try_wait().map(|status| status.is_none())
+    }
+}
+
+/// An IO stream of [PipeProcess].
+#[derive(Debug)]
+pub struct PipeStream {
+    input: ChildStdin,
+    output: ChildStdout,
+}
+
+impl PipeStream {
+    fn new(input: ChildStdin, output: ChildStdout) -> Self {
+        Self { input, output }
+    }
+}
+
+impl Write for PipeStream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.input.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.input.flush()
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> Result<usize> {
+        self.input.write_vectored(bufs)
+    }
+}
+
+impl Read for PipeStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.output.read(buf)
+    }
+}
+
+impl NonBlocking for PipeStream {
+    fn set_blocking(&mut self, on: bool) -> Result<()> {
+        make_non_blocking(self.output.as_raw_fd(), !on)
+    }
+}
+
+#[cfg(feature = "async")]
+impl IntoAsyncStream for PipeStream {
+    type AsyncStream = AsyncPipeStream;
+
+    fn into_async_stream(self) -> Result<Self::AsyncStream> {
+        AsyncPipeStream::new(self)
+    }
+}
+
+/// An async version of IO stream of [PipeProcess].
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct AsyncPipeStream {
+    input: crate::runtime::Async<ChildStdin>,
+    output: crate::runtime::Async<ChildStdout>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncPipeStream {
+    fn new(stream: PipeStream) -> Result<Self> {
+        Ok(Self {
+            input: crate::runtime::Async::new(stream.input)?,
+            output: crate::runtime::Async::new(stream.output)?,
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncWrite for AsyncPipeStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.input).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.input).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.input).poll_close(cx)
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncRead for AsyncPipeStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.output).poll_read(cx, buf)
+    }
+}
@@ -1,9 +1,10 @@
 //! This module contains a Unix implementation of [crate::process::Process].
 
 use std::{
+    fs::File,
     io::{self, ErrorKind, Read, Result, Write},
     ops::{Deref, DerefMut},
-    os::unix::prelude::{AsRawFd, RawFd},
+    os::unix::prelude::{AsRawFd, FromRawFd, RawFd},
     process::Command,
 };
 
@@ -12,6 +13,10 @@ use crate::{
     process::{Healthcheck, NonBlocking, Process, Termios},
 };
 
+use nix::{
+    sys::termios::{self, InputFlags, LocalFlags, OutputFlags, SetArg, SpecialCharacterIndices},
+    unistd::Gid,
+};
 use ptyprocess::{errno::Errno, stream::Stream, PtyProcess};
 
 #[cfg(feature = "async")]
@@ -40,21 +45,12 @@ impl Process for UnixProcess {
     where
         S: AsRef<str>,
     {
-        let args = tokenize_command(cmd.as_ref());
-        if args.is_empty() {
-            return Err(io_error("failed to parse a command"));
-        }
-
-        let mut command = std::process::Command::new(&args[0]);
-        let _ = command.args(args.iter().skip(1));
-
+        let command = parse_command(cmd.as_ref())?;
         Self::spawn_command(command)
     }
 
     fn spawn_command(command: Self::Command) -> Result<Self> {
-        let proc = PtyProcess::spawn(command).map_err(to_io_error("Failed to spawn a command"))?;
-
-        Ok(Self { proc })
+        Self::spawn_with_options(command, UnixProcessOptions::default())
     }
 
     fn open_stream(&mut self) -> Result<Self::Stream> {
@@ -67,6 +63,258 @@ impl Process for UnixProcess {
     }
 }
 
+impl UnixProcess {
+    /// Spawns a command applying a given [UnixProcessOptions].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::process::Command;
+    /// use expectrl::{process::{Process, unix::{UnixProcess, UnixProcessOptions}}, Session};
+    ///
+    /// let opts = UnixProcessOptions::new().uid(1000).gid(1000);
+    /// let mut proc = UnixProcess::spawn_with_options(Command::new("id"), opts).unwrap();
+    /// let stream = proc.open_stream().unwrap();
+    /// let session = Session::new(proc, stream).unwrap();
+    /// ```
+    pub fn spawn_with_options(mut command: Command, options: UnixProcessOptions) -> Result<Self> {
+        let window_size = options.window_size;
+        options.apply(&mut command);
+
+        let mut proc =
+            PtyProcess::spawn(command).map_err(to_io_error("Failed to spawn a command"))?;
+
+        // PtyProcess resets the size to a default one right after the command is executed,
+        // so it's set once again; a program gets the size set before the exec at startup.
+        if let Some((cols, rows)) = window_size {
+            proc.set_window_size(cols, rows)?;
+        }
+
+        Ok(Self { proc })
+    }
+}
+
+/// UnixProcessOptions represents a set of settings which are applied to a child
+/// before it executes a command.
+///
+/// By default nothing is changed and a child gets the pty as its controlling terminal.
+#[derive(Debug, Clone)]
+pub struct UnixProcessOptions {
+    uid: Option<u32>,
+    gid: Option<u32>,
+    groups: Option<Vec<u32>>,
+    controlling_terminal: bool,
+    clear_env: bool,
+    echo: Option<bool>,
+    window_size: Option<(u16, u16)>,
+}
+
+impl UnixProcessOptions {
+    /// Creates a default options.
+    pub fn new() -> Self {
+        Self {
+            uid: None,
+            gid: None,
+            groups: None,
+            controlling_terminal: true,
+            clear_env: false,
+            echo: None,
+            window_size: None,
+        }
+    }
+
+    /// Sets a user id the child will be run with.
+    ///
+    /// If supplementary groups are not set they will be dropped.
+    pub fn uid(mut self, uid: u32) -> Self {
+        self.uid = Some(uid);
+        self
+    }
+
+    /// Sets a group id the child will be run with.
+    pub fn gid(mut self, gid: u32) -> Self {
+        self.gid = Some(gid);
+        self
+    }
+
+    /// Sets supplementary groups of the child.
+    ///
+    /// It's not supported on macOS, so the spawn will fail there.
+    pub fn groups(mut self, groups: impl Into<Vec<u32>>) -> Self {
+        self.groups = Some(groups.into());
+        self
+    }
+
+    /// Sets whether the pty will be a controlling terminal of the child.
+    ///
+    /// If it's turned off the child is detached from the pty (it still uses it as STDIN/STDOUT/STDERR),
+    /// so for example `/dev/tty` will not be available for it.
+    pub fn controlling_terminal(mut self, on: bool) -> Self {
+        self.controlling_terminal = on;
+        self
+    }
+
+    /// Sets whether the child will not inherit environment variables of the current process.
+    ///
+    /// Variables set on the command itself are kept.
+    pub fn clear_env(mut self, on: bool) -> Self {
+        self.clear_env = on;
+        self
+    }
+
+    /// Sets whether the terminal echoes input.
+    ///
+    /// It's set before the command is executed, so the program sees it at startup.
+    pub fn echo(mut self, on: bool) -> Self {
+        self.echo = Some(on);
+        self
+    }
+
+    /// Sets a size of the terminal in columns and rows.
+    ///
+    /// It's set before the command is executed, so the program sees it at startup.
+    pub fn window_size(mut self, cols: u16, rows: u16) -> Self {
+        self.window_size = Some((cols, rows));
+        self
+    }
+
+    fn apply(self, command: &mut Command) {
+        use std::os::unix::process::CommandExt;
+
+        if self.clear_env {
+            let envs = command
+                .get_envs()
+                .filter_map(|(key, value)| Some((key.to_owned(), value?.to_owned())))
+                .collect::<Vec<_>>();
+            let _ = command.env_clear().envs(envs);
+        }
+
+        if self.uid.is_none()
+            && self.gid.is_none()
+            && self.groups.is_none()
+            && self.controlling_terminal
+            && self.echo.is_none()
+            && self.window_size.is_none()
+        {
+            return;
+        }
+
+        // We don't use CommandExt::{uid, gid} because they are applied before `pre_exec` callbacks,
+        // so we would not be able to set groups after privileges were dropped.
+        //
+        // Allocation is not async-signal-safe, so the groups are prepared beforehand.
+        let groups = self
+            .groups
+            .as_ref()
+            .map(|groups| groups.iter().map(|&g| Gid::from_raw(g)).collect::<Vec<_>>());
+
+        // SAFETY: the closure makes only async-signal-safe calls.
+        unsafe {
+            let _ = command.pre_exec(move || self.apply_in_child(groups.as_deref()));
+        }
+    }
+
+    #[allow(trivial_numeric_casts)]
+    fn apply_in_child(&self, groups: Option<&[Gid]>) -> Result<()> {
+        use nix::{
+            libc,
+            sys::signal::{signal, SigHandler, Signal},
+            unistd::{setgid, setuid, Uid},
+        };
+
+        // The pty is already set as STDIN when the closure is called.
+        if let Some(on) = self.echo {
+            // SAFETY: the struct is initialized by tcgetattr before it's read.
+            unsafe {
+                let mut attrs = std::mem::MaybeUninit::<libc::termios>::uninit();
+                if libc::tcgetattr(libc::STDIN_FILENO, attrs.as_mut_ptr()) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+
+                let mut attrs = attrs.assume_init();
+                if on {
+                    attrs.c_lflag |= libc::ECHO;
+                } else {
+                    attrs.c_lflag &= !libc::ECHO;
+                }
+
+                if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &attrs) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+        }
+
+        if let Some((cols, rows)) = self.window_size {
+            let size = libc::winsize {
+                ws_row: rows,
+                ws_col: cols,
+                ws_xpixel: 0,
+                ws_ypixel: 0,
+            };
+
+            // SAFETY: the ioctl reads a winsize struct which outlives the call.
+            if unsafe { libc::ioctl(libc::STDIN_FILENO, libc::TIOCSWINSZ, &size) } == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        if !self.controlling_terminal {
+            // A session leader which gives up its controlling terminal
+            // causes SIGHUP to be sent to the foreground group (which is the child itself).
+            unsafe {
+                let _ = signal(Signal::SIGHUP, SigHandler::SigIgn).map_err(nix_error_to_io)?;
+                if libc::ioctl(libc::STDIN_FILENO, libc::TIOCNOTTY as _) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                let _ = signal(Signal::SIGHUP, SigHandler::SigDfl).map_err(nix_error_to_io)?;
+            }
+        }
+
+        match groups {
+            Some(groups) => set_groups(groups)?,
+            None if self.uid.is_some() => {
+                // Drop extraneous groups as std::process::Command does;
+                // ignoring the case of not having CAP_SETGID.
+                match set_groups(&[]) {
+                    Ok(()) => {}
+                    Err(err) if err.raw_os_error() == Some(libc::EPERM) => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            None => {}
+        }
+
+        if let Some(gid) = self.gid {
+            setgid(Gid::from_raw(gid)).map_err(nix_error_to_io)?;
+        }
+
+        if let Some(uid) = self.uid {
+            setuid(Uid::from_raw(uid)).map_err(nix_error_to_io)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for UnixProcessOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn set_groups(groups: &[Gid]) -> Result<()> {
+    nix::unistd::setgroups(groups).map_err(nix_error_to_io)
+}
+
+#[cfg(target_os = "macos")]
+fn set_groups(_: &[Gid]) -> Result<()> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        "setting supplementary groups is not supported",
+    ))
+}
+
 impl Healthcheck for UnixProcess {
     type Status = WaitStatus;
 
@@ -93,6 +341,142 @@ impl Termios for UnixProcess {
 
         Ok(value)
     }
+
+    fn settle_input(&mut self) -> Result<()> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        use nix::poll::{poll, PollFd, PollFlags};
+
+        // Linux passes input to a terminal asynchronously,
+        // while a poll of the terminal side makes it process pending input right away.
+        let tty = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(nix::libc::O_NOCTTY | nix::libc::O_NONBLOCK)
+            .open(self.tty_name()?)?;
+        let mut fds = [PollFd::new(tty.as_raw_fd(), PollFlags::POLLIN)];
+        let _ = poll(&mut fds, 0)?;
+
+        Ok(())
+    }
+}
+
+/// A terminal flag of a PTY which can be set individually.
+///
+/// See [`UnixProcess::set_termios_flag`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TermiosFlag {
+    /// `ECHO` - echo input characters back.
+    Echo,
+    /// `ICANON` - canonical (line buffered) input mode.
+    Canonical,
+    /// `ISIG` - generate signals on INTR, QUIT, SUSP characters.
+    Signals,
+    /// `IEXTEN` - extended input processing (e.g. LNEXT character).
+    ExtendedInput,
+    /// `ICRNL` - translate a carriage return to a newline on input.
+    InputCrToNl,
+    /// `OPOST` - output post processing.
+    ///
+    /// Turning it off disables all output translations,
+    /// e.g. translation of `\n` into `\r\n` which you see in the output of `cat`.
+    OutputProcessing,
+    /// `ONLCR` - translate a newline to a carriage return and newline on output.
+    OutputNlToCrNl,
+}
+
+impl UnixProcess {
+    /// Verifies whether a terminal flag is set.
+    pub fn get_termios_flag(&self, flag: TermiosFlag) -> Result<bool> {
+        let fd = self.proc.get_raw_handle()?;
+        let attrs = termios::tcgetattr(fd.as_raw_fd())?;
+
+        let is_set = match flag {
+            TermiosFlag::Echo => attrs.local_flags.contains(LocalFlags::ECHO),
+            TermiosFlag::Canonical => attrs.local_flags.contains(LocalFlags::ICANON),
+            TermiosFlag::Signals => attrs.local_flags.contains(LocalFlags::ISIG),
+            TermiosFlag::ExtendedInput => attrs.local_flags.contains(LocalFlags::IEXTEN),
+            TermiosFlag::InputCrToNl => attrs.input_flags.contains(InputFlags::ICRNL),
+            TermiosFlag::OutputProcessing => attrs.output_flags.contains(OutputFlags::OPOST),
+            TermiosFlag::OutputNlToCrNl => attrs.output_flags.contains(OutputFlags::ONLCR),
+        };
+
+        Ok(is_set)
+    }
+
+    /// Sets or unsets a terminal flag.
+    ///
+    /// The change is applied immediately,
+    /// but bytes which were already processed by the terminal are not affected.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use expectrl::{spawn, process::unix::TermiosFlag};
+    ///
+    /// let mut p = spawn("cat").unwrap();
+    /// p.get_process_mut().set_termios_flag(TermiosFlag::OutputProcessing, false).unwrap();
+    /// ```
+    pub fn set_termios_flag(&mut self, flag: TermiosFlag, on: bool) -> Result<()> {
+        let fd = self.proc.get_raw_handle()?;
+        let mut attrs = termios::tcgetattr(fd.as_raw_fd())?;
+
+        match flag {
+            TermiosFlag::Echo => attrs.local_flags.set(LocalFlags::ECHO, on),
+            TermiosFlag::Canonical => attrs.local_flags.set(LocalFlags::ICANON, on),
+            TermiosFlag::Signals => attrs.local_flags.set(LocalFlags::ISIG, on),
+            TermiosFlag::ExtendedInput => attrs.local_flags.set(LocalFlags::IEXTEN, on),
+            TermiosFlag::InputCrToNl => attrs.input_flags.set(InputFlags::ICRNL, on),
+            TermiosFlag::OutputProcessing => attrs.output_flags.set(OutputFlags::OPOST, on),
+            TermiosFlag::OutputNlToCrNl => attrs.output_flags.set(OutputFlags::ONLCR, on),
+        }
+
+        termios::tcsetattr(fd.as_raw_fd(), SetArg::TCSANOW, &attrs)?;
+
+        Ok(())
+    }
+
+    /// Returns an EOF character (`VEOF`) of the terminal, usually `^D`.
+    pub fn eof_char(&self) -> Result<u8> {
+        self.get_control_char(SpecialCharacterIndices::VEOF)
+    }
+
+    /// Returns an interrupt character (`VINTR`) of the terminal, usually `^C`.
+    pub fn intr_char(&self) -> Result<u8> {
+        self.get_control_char(SpecialCharacterIndices::VINTR)
+    }
+
+    /// Returns a path of the terminal device the process uses, e.g. `/dev/pts/3`.
+    ///
+    /// It's the controlling terminal of the process,
+    /// so other tools can be pointed at the same terminal.
+    pub fn tty_name(&self) -> Result<String> {
+        let fd = self.proc.get_raw_handle()?;
+        ptsname(fd.as_raw_fd())
+    }
+
+    /// Returns a process group id of the foreground job of the terminal.
+    ///
+    /// When the process is a shell which runs other programs
+    /// it's the group of the running program, rather than the shell itself.
+    ///
+    /// There's no setter for it.
+    /// `tcsetpgrp` is allowed only for a process which has the terminal as its controlling one
+    /// and belongs to its session, which the spawning process never does,
+    /// so on the master side of a pty it always fails with `ENOTTY`.
+    /// The foreground job can be changed only from within the terminal, e.g. by `fg` in a shell.
+    pub fn foreground_pgrp(&self) -> Result<i32> {
+        let fd = self.proc.get_raw_handle()?;
+        let pgrp = nix::unistd::tcgetpgrp(fd.as_raw_fd())?;
+        Ok(pgrp.as_raw())
+    }
+
+    fn get_control_char(&self, index: SpecialCharacterIndices) -> Result<u8> {
+        let fd = self.proc.get_raw_handle()?;
+        let attrs = termios::tcgetattr(fd.as_raw_fd())?;
+
+        Ok(attrs.control_chars[index as usize])
+    }
 }
 
 impl Deref for UnixProcess {
@@ -110,6 +494,8 @@ impl DerefMut for UnixProcess {
 }
 
 /// A IO stream (write/read) of [UnixProcess].
+///
+/// It can also be built from an existing file descriptor via [FromRawFd].
 #[derive(Debug)]
 pub struct PtyStream {
     handle: Stream,
@@ -151,12 +537,24 @@ impl NonBlocking for PtyStream {
     }
 }
 
+impl NonBlocking for std::os::unix::net::UnixStream {
+    fn set_blocking(&mut self, on: bool) -> Result<()> {
+        self.set_nonblocking(!on)
+    }
+}
+
 impl AsRawFd for PtyStream {
     fn as_raw_fd(&self) -> RawFd {
         self.handle.as_raw_fd()
     }
 }
 
+impl FromRawFd for PtyStream {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        Self::new(Stream::new(File::from_raw_fd(fd)))
+    }
+}
+
 #[cfg(feature = "async")]
 impl IntoAsyncStream for PtyStream {
     type AsyncStream = AsyncPtyStream;
@@ -170,13 +568,13 @@ impl IntoAsyncStream for PtyStream {
 #[cfg(feature = "async")]
 #[derive(Debug)]
 pub struct AsyncPtyStream {
-    stream: async_io::Async<PtyStream>,
+    stream: crate::runtime::Async<PtyStream>,
 }
 
 #[cfg(feature = "async")]
 impl AsyncPtyStream {
     fn new(stream: PtyStream) -> Result<Self> {
-        let stream = async_io::Async::new(stream)?;
+        let stream = crate::runtime::Async::new(stream)?;
         Ok(Self { stream })
     }
 }
@@ -218,6 +616,38 @@ impl polling::Source for PtyStream {
     }
 }
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn ptsname(fd: RawFd) -> Result<String> {
+    use nix::libc;
+
+    let mut buf = [0; 64];
+    // SAFETY: the buffer length is passed along so it won't be overrun.
+    let code = unsafe { libc::ptsname_r(fd, buf.as_mut_ptr(), buf.len()) };
+    if code != 0 {
+        return Err(io::Error::from_raw_os_error(code));
+    }
+
+    // SAFETY: ptsname_r puts a NUL terminated string into the buffer on success.
+    let name = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) };
+    Ok(name.to_string_lossy().into_owned())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn ptsname(fd: RawFd) -> Result<String> {
+    use nix::libc;
+
+    // SAFETY: ptsname returns a pointer to a static buffer,
+    // which is copied right away; it's not thread safe but there's no reentrant version here.
+    let name = unsafe { libc::ptsname(fd) };
+    if name.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: the pointer is not null and points to a NUL terminated string.
+    let name = unsafe { std::ffi::CStr::from_ptr(name) };
+    Ok(name.to_string_lossy().into_owned())
+}
+
 pub(crate) fn make_non_blocking(fd: RawFd, blocking: bool) -> Result<()> {
     use nix::fcntl::{fcntl, FcntlArg, OFlag};
 
@@ -229,7 +659,7 @@ pub(crate) fn make_non_blocking(fd: RawFd, blocking: bool) -> Result<()> {
 }
 
 fn nix_error_to_io(err: nix::Error) -> io::Error {
-    io::Error::new(io::ErrorKind::Other, err)
+    io::Error::other(err)
 }
 
 /// Turn e.g. "prog arg1 arg2" into ["prog", "arg1", "arg2"]
@@ -237,12 +667,28 @@ fn nix_error_to_io(err: nix::Error) -> io::Error {
 ///
 /// It doesn't cover all edge cases.
 /// So it may not be compatible with real shell arguments parsing.
-fn tokenize_command(program: &str) -> Vec<String> {
-    let re = regex::Regex::new(r#""[^"]+"|'[^']+'|[^'" ]+"#).unwrap();
+pub(crate) fn tokenize_command(program: &str) -> Vec<String> {
     let mut res = vec![];
-    for cap in re.captures_iter(program) {
-        res.push(cap[0].to_string());
+    let mut rest = program;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '"' | '\'' => match rest[1..].find(c) {
+                // an empty quoted string isn't a token
+                Some(end) if end > 0 => {
+                    res.push(rest[..end + 2].to_string());
+                    rest = &rest[end + 2..];
+                }
+                _ => rest = &rest[1..],
+            },
+            ' ' => rest = &rest[1..],
+            _ => {
+                let end = rest.find(['"', '\'', ' ']).unwrap_or(rest.len());
+                res.push(rest[..end].to_string());
+                rest = &rest[end..];
+            }
+        }
     }
+
     res
 }
 
@@ -251,13 +697,26 @@ fn get_status(proc: &PtyProcess) -> std::prelude::v1::Result<WaitStatus, io::Err
         Ok(status) => Ok(status),
         Err(err) => match err {
             Errno::ECHILD | Errno::ESRCH => Err(io::Error::new(ErrorKind::WouldBlock, err)),
-            err => Err(io::Error::new(ErrorKind::Other, err)),
+            err => Err(io::Error::other(err)),
         },
     }
 }
 
+/// Builds a command from a commandline, e.g. "prog arg1 arg2".
+pub(crate) fn parse_command(cmd: &str) -> Result<Command> {
+    let args = tokenize_command(cmd);
+    if args.is_empty() {
+        return Err(io_error("failed to parse a command"));
+    }
+
+    let mut command = Command::new(&args[0]);
+    let _ = command.args(args.iter().skip(1));
+
+    Ok(command)
+}
+
 fn io_error(msg: &str) -> io::Error {
-    io::Error::new(io::ErrorKind::Other, msg)
+    io::Error::other(msg)
 }
 
 #[cfg(test)]
@@ -278,5 +737,8 @@ mod tests {
 
         let res = tokenize_command(r#"prog "my text""#);
         assert_eq!(vec!["prog", r#""my text""#], res);
+
+        let res = tokenize_command(r#"prog  a"b c"d "" 'e"#);
+        assert_eq!(vec!["prog", "a", r#""b c""#, "d", "e"], res);
     }
 }
@@ -0,0 +1,469 @@
+//! This module contains a [`Screen`], a minimal terminal emulator
+//! which renders an output of a process the way a terminal would display it.
+//!
+//! It's useful for full-screen programs (e.g. curses based ones),
+//! which move a cursor around so the raw output doesn't reflect what's displayed.
+
+/// A default number of rows of a [`Screen`].
+const DEFAULT_ROWS: usize = 24;
+/// A default number of columns of a [`Screen`].
+const DEFAULT_COLS: usize = 80;
+/// A limit of CSI parameters bytes, the rest is ignored.
+const MAX_PARAMS_LEN: usize = 64;
+
+/// Screen is a grid of characters which an output is rendered onto.
+///
+/// It handles cursor movements, erasing, inserting and deleting, scrolling and line wrapping.
+/// Colors and other attributes are ignored, as well as scrolling regions.
+///
+/// # Example
+///
+/// ```
+/// use expectrl::render::Screen;
+///
+/// let mut screen = Screen::new(2, 10);
+/// screen.feed(b"Hello\x1b[2;3HWorld");
+/// assert_eq!(screen.contents(), "Hello\n  World");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screen {
+    grid: Vec<Vec<char>>,
+    cols: usize,
+    row: usize,
+    col: usize,
+    saved_cursor: (usize, usize),
+    pending_wrap: bool,
+    state: State,
+    params: Vec<u8>,
+    utf8: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    EscapeIntermediate,
+    Csi,
+    Osc,
+    OscEscape,
+}
+
+impl Screen {
+    /// Creates an empty screen of a given size.
+    ///
+    /// A size of 0 is treated as 1.
+    pub fn new(rows: usize, cols: usize) -> Self {
+        let rows = rows.max(1);
+        let cols = cols.max(1);
+
+        Self {
+            grid: vec![vec![' '; cols]; rows],
+            cols,
+            row: 0,
+            col: 0,
+            saved_cursor: (0, 0),
+            pending_wrap: false,
+            state: State::Ground,
+            params: Vec::new(),
+            utf8: Vec::new(),
+        }
+    }
+
+    /// Renders bytes onto the screen.
+    ///
+    /// An escape sequence or a UTF-8 character may be split between calls.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.feed_byte(b);
+        }
+    }
+
+    /// Returns the text displayed on the screen.
+    ///
+    /// Rows are separated by `\n` and trailing spaces of each row are trimmed.
+    pub fn contents(&self) -> String {
+        self.rows().join("\n")
+    }
+
+    /// Returns rows of the screen as strings.
+    ///
+    /// Trailing spaces of each row are trimmed.
+    pub fn rows(&self) -> Vec<String> {
+        self.grid.iter().map(|row| render_row(row)).collect()
+    }
+
+    /// Returns a row of the screen as a string, if the index is in bounds.
+    ///
+    /// Trailing spaces are trimmed.
+    pub fn row(&self, index: usize) -> Option<String> {
+        self.grid.get(index).map(|row| render_row(row))
+    }
+
+    /// Returns a character displayed in a cell, if the position is in bounds.
+    ///
+    /// An empty cell is a space.
+    pub fn cell(&self, row: usize, col: usize) -> Option<char> {
+        self.grid.get(row).and_then(|r| r.get(col)).copied()
+    }
+
+    /// Returns a cursor position as a `(row, column)` pair, both starting from 0.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    /// Returns a size of the screen as a `(rows, columns)` pair.
+    pub fn size(&self) -> (usize, usize) {
+        (self.height(), self.cols)
+    }
+
+    fn height(&self) -> usize {
+        self.grid.len()
+    }
+
+    fn feed_byte(&mut self, b: u8) {
+        match self.state {
+            State::Ground => self.ground(b),
+            State::Escape => self.escape(b),
+            State::EscapeIntermediate => {
+                if (0x30..=0x7e).contains(&b) {
+                    self.state = State::Ground;
+                }
+            }
+            State::Csi => match b {
+                0x1b => self.state = State::Escape,
+                0x40..=0x7e => {
+                    self.state = State::Ground;
+                    self.csi(b);
+                }
+                _ => {
+                    if self.params.len() < MAX_PARAMS_LEN {
+                        self.params.push(b);
+                    }
+                }
+            },
+            State::Osc => match b {
+                0x07 => self.state = State::Ground,
+                0x1b => self.state = State::OscEscape,
+                _ => {}
+            },
+            State::OscEscape => self.state = State::Ground,
+        }
+    }
+
+    fn ground(&mut self, b: u8) {
+        match b {
+            0x1b => {
+                self.utf8.clear();
+                self.state = State::Escape;
+            }
+            b'\r' => self.move_to(self.row, 0),
+            b'\n' | 0x0b | 0x0c => self.line_feed(),
+            0x08 => self.move_to(self.row, self.col.saturating_sub(1)),
+            b'\t' => self.move_to(self.row, (self.col / 8 + 1) * 8),
+            0x00..=0x1f | 0x7f => {}
+            _ => self.print_byte(b),
+        }
+    }
+
+    fn escape(&mut self, b: u8) {
+        self.state = State::Ground;
+        match b {
+            b'[' => {
+                self.params.clear();
+                self.state = State::Csi;
+            }
+            b']' => self.state = State::Osc,
+            b'7' => self.saved_cursor = (self.row, self.col),
+            b'8' => self.move_to(self.saved_cursor.0, self.saved_cursor.1),
+            b'D' => self.line_feed(),
+            b'E' => {
+                self.move_to(self.row, 0);
+                self.line_feed();
+            }
+            b'M' => self.reverse_line_feed(),
+            b'c' => *self = Self::new(self.height(), self.cols),
+            0x20..=0x2f => self.state = State::EscapeIntermediate,
+            _ => {}
+        }
+    }
+
+    fn print_byte(&mut self, b: u8) {
+        self.utf8.push(b);
+        match std::str::from_utf8(&self.utf8) {
+            Ok(s) => {
+                let chars = s.chars().collect::<Vec<_>>();
+                self.utf8.clear();
+                for c in chars {
+                    self.print(c);
+                }
+            }
+            Err(err) if err.error_len().is_some() || self.utf8.len() >= 4 => {
+                self.utf8.clear();
+                self.print(char::REPLACEMENT_CHARACTER);
+            }
+            Err(_) => {}
+        }
+    }
+
+    fn print(&mut self, c: char) {
+        if self.pending_wrap {
+            self.move_to(self.row, 0);
+            self.line_feed();
+        }
+
+        self.grid[self.row][self.col] = c;
+
+        match self.col + 1 == self.cols {
+            true => self.pending_wrap = true,
+            false => self.col += 1,
+        }
+    }
+
+    fn csi(&mut self, action: u8) {
+        let params = std::mem::take(&mut self.params);
+        let is_private = matches!(params.first(), Some(b) if !b.is_ascii_digit() && *b != b';');
+        let args = params
+            .split(|&b| b == b';')
+            .map(|arg| {
+                arg.iter()
+                    .filter(|b| b.is_ascii_digit())
+                    .fold(0usize, |n, b| {
+                        n.saturating_mul(10).saturating_add((b - b'0') as usize)
+                    })
+            })
+            .collect::<Vec<_>>();
+        let arg = |i: usize, default: usize| match args.get(i) {
+            Some(&n) if n > 0 => n,
+            _ => default,
+        };
+
+        if is_private {
+            // an alternate screen buffer is approximated by clearing the screen
+            if matches!(action, b'h' | b'l') && args.iter().any(|&n| matches!(n, 47 | 1047 | 1049))
+            {
+                self.erase_display(2);
+            }
+
+            return;
+        }
+
+        let (row, col) = (self.row, self.col);
+        match action {
+            b'A' => self.move_to(row.saturating_sub(arg(0, 1)), col),
+            b'B' | b'e' => self.move_to(row.saturating_add(arg(0, 1)), col),
+            b'C' | b'a' => self.move_to(row, col.saturating_add(arg(0, 1))),
+            b'D' => self.move_to(row, col.saturating_sub(arg(0, 1))),
+            b'E' => self.move_to(row.saturating_add(arg(0, 1)), 0),
+            b'F' => self.move_to(row.saturating_sub(arg(0, 1)), 0),
+            b'G' | b'`' => self.move_to(row, arg(0, 1) - 1),
+            b'd' => self.move_to(arg(0, 1) - 1, col),
+            b'H' | b'f' => self.move_to(arg(0, 1) - 1, arg(1, 1) - 1),
+            b'J' => self.erase_display(arg(0, 0)),
+            b'K' => self.erase_line(arg(0, 0)),
+            b'X' => {
+                let end = col.saturating_add(arg(0, 1)).min(self.cols);
+                self.grid[row][col..end].fill(' ');
+            }
+            b'P' => {
+                let n = arg(0, 1).min(self.cols - col);
+                let line = &mut self.grid[row];
+                let _ = line.drain(col..col + n);
+                line.resize(self.cols, ' ');
+            }
+            b'@' => {
+                let n = arg(0, 1).min(self.cols - col);
+                let line = &mut self.grid[row];
+                let _ = line.splice(col..col, std::iter::repeat_n(' ', n));
+                line.truncate(self.cols);
+            }
+            b'L' => {
+                let n = arg(0, 1).min(self.height() - row);
+                for _ in 0..n {
+                    let _ = self.grid.pop();
+                    self.grid.insert(row, vec![' '; self.cols]);
+                }
+            }
+            b'M' => {
+                let n = arg(0, 1).min(self.height() - row);
+                for _ in 0..n {
+                    let _ = self.grid.remove(row);
+                    self.grid.push(vec![' '; self.cols]);
+                }
+            }
+            b'S' => self.scroll_up(arg(0, 1)),
+            b'T' => self.scroll_down(arg(0, 1)),
+            b's' => self.saved_cursor = (row, col),
+            b'u' => self.move_to(self.saved_cursor.0, self.saved_cursor.1),
+            _ => {}
+        }
+    }
+
+    fn move_to(&mut self, row: usize, col: usize) {
+        self.row = row.min(self.height() - 1);
+        self.col = col.min(self.cols - 1);
+        self.pending_wrap = false;
+    }
+
+    fn line_feed(&mut self) {
+        self.pending_wrap = false;
+        match self.row + 1 == self.height() {
+            true => self.scroll_up(1),
+            false => self.row += 1,
+        }
+    }
+
+    fn reverse_line_feed(&mut self) {
+        self.pending_wrap = false;
+        match self.row {
+            0 => self.scroll_down(1),
+            _ => self.row -= 1,
+        }
+    }
+
+    fn scroll_up(&mut self, n: usize) {
+        for _ in 0..n.min(self.height()) {
+            let _ = self.grid.remove(0);
+            self.grid.push(vec![' '; self.cols]);
+        }
+    }
+
+    fn scroll_down(&mut self, n: usize) {
+        for _ in 0..n.min(self.height()) {
+            let _ = self.grid.pop();
+            self.grid.insert(0, vec![' '; self.cols]);
+        }
+    }
+
+    fn erase_display(&mut self, mode: usize) {
+        let (row, col) = (self.row, self.col);
+        match mode {
+            0 => {
+                self.grid[row][col..].fill(' ');
+                self.grid[row + 1..].iter_mut().for_each(|l| l.fill(' '));
+            }
+            1 => {
+                self.grid[row][..=col].fill(' ');
+                self.grid[..row].iter_mut().for_each(|l| l.fill(' '));
+            }
+            _ => self.grid.iter_mut().for_each(|l| l.fill(' ')),
+        }
+    }
+
+    fn erase_line(&mut self, mode: usize) {
+        let (row, col) = (self.row, self.col);
+        match mode {
+            0 => self.grid[row][col..].fill(' '),
+            1 => self.grid[row][..=col].fill(' '),
+            _ => self.grid[row].fill(' '),
+        }
+    }
+}
+
+fn render_row(row: &[char]) -> String {
+    row.iter().collect::<String>().trim_end().to_owned()
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Self::new(DEFAULT_ROWS, DEFAULT_COLS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text() {
+        let mut screen = Screen::new(3, 5);
+        screen.feed(b"ab\r\ncd");
+        assert_eq!(screen.contents(), "ab\ncd\n");
+
+        screen.feed("\r\nżółw".as_bytes());
+        assert_eq!(screen.contents(), "ab\ncd\nżółw");
+    }
+
+    #[test]
+    fn test_wrap_and_scroll() {
+        let mut screen = Screen::new(2, 3);
+        screen.feed(b"abcdef");
+        assert_eq!(screen.contents(), "abc\ndef");
+
+        screen.feed(b"g");
+        assert_eq!(screen.contents(), "def\ng");
+
+        screen.feed(b"\r\n\r\nh");
+        assert_eq!(screen.contents(), "\nh");
+    }
+
+    #[test]
+    fn test_cursor_movements() {
+        let mut screen = Screen::new(3, 10);
+        screen.feed(b"\x1b[3;5Hx\x1b[2Ay\x1b[1;1Hz\x1b[2Cw\x1b[99;99Hq");
+        assert_eq!(screen.contents(), "z  w y\n\n    x    q");
+
+        let mut screen = Screen::new(1, 10);
+        screen.feed(b"abc\x1b7\rd\x1b8e");
+        assert_eq!(screen.contents(), "dbce");
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut screen = Screen::new(2, 4);
+        screen.feed(b"ab\r\ncd ");
+        assert_eq!(screen.rows(), vec!["ab", "cd"]);
+        assert_eq!(screen.row(1), Some(String::from("cd")));
+        assert_eq!(screen.row(2), None);
+        assert_eq!(screen.cell(1, 0), Some('c'));
+        assert_eq!(screen.cell(1, 3), Some(' '));
+        assert_eq!(screen.cell(2, 0), None);
+        assert_eq!(screen.cursor(), (1, 3));
+        assert_eq!(screen.size(), (2, 4));
+    }
+
+    #[test]
+    fn test_erase() {
+        let mut screen = Screen::new(2, 5);
+        screen.feed(b"abcde\r\nfghij\x1b[1;3H\x1b[K");
+        assert_eq!(screen.contents(), "ab\nfghij");
+
+        screen.feed(b"\x1b[J");
+        assert_eq!(screen.contents(), "ab\n");
+
+        screen.feed(b"xyz\x1b[2J");
+        assert_eq!(screen.contents(), "\n");
+    }
+
+    #[test]
+    fn test_ignored_sequences() {
+        let mut screen = Screen::new(1, 20);
+        screen.feed(b"\x1b[1;31mred\x1b[0m \x1b]0;title\x07\x1b(Bok\x1b[?25l");
+        assert_eq!(screen.contents(), "red ok");
+    }
+
+    #[test]
+    fn test_split_sequences() {
+        let mut screen = Screen::new(1, 10);
+        screen.feed(b"abc\x1b[");
+        screen.feed(b"1G");
+        screen.feed(&"ż".as_bytes()[..1]);
+        screen.feed(&"ż".as_bytes()[1..]);
+        assert_eq!(screen.contents(), "żbc");
+    }
+
+    #[test]
+    fn test_insert_delete() {
+        let mut screen = Screen::new(3, 5);
+        screen.feed(b"abcde\x1b[1;2H\x1b[2P");
+        assert_eq!(screen.contents(), "ade\n\n");
+
+        screen.feed(b"\x1b[@");
+        assert_eq!(screen.contents(), "a de\n\n");
+
+        screen.feed(b"\x1b[2;1Hx\x1b[1;1H\x1b[L");
+        assert_eq!(screen.contents(), "\na de\nx");
+
+        screen.feed(b"\x1b[M");
+        assert_eq!(screen.contents(), "a de\nx\n");
+    }
+}
@@ -1,14 +1,16 @@
 //! This module contains a list of special Sessions that can be spawned.
 
-use std::io::{self, BufRead, Read, Write};
+use std::{
+    io::{self, BufRead, Read, Write},
+    time::Duration,
+};
 
-#[cfg(unix)]
 use std::process::Command;
 
 use crate::{
     error::Error,
     process::{Healthcheck, Termios},
-    session::OsSession,
+    session::{ExpectTimeout, OsSession, TimeoutGuard},
     spawn, Captures, Expect, Needle,
 };
 
@@ -24,44 +26,83 @@ use futures_lite::{io::AsyncBufRead, AsyncRead, AsyncWrite};
 #[cfg(feature = "async")]
 use crate::AsyncExpect;
 
-/// Spawn a bash session.
+/// Spawns a repl session of an arbitrary program, e.g. `node`, `sqlite3` or `gdb`.
 ///
-/// It uses a custom prompt to be able to controll shell better.
+/// It sets the prompt, the quit command and the echo setting of the [`ReplSession`],
+/// and waits for the first prompt, so the session is ready to execute commands.
 ///
-/// If you wan't to use [`Session::interact`] method it is better to use just Session.
-/// Because we don't handle echoes here (currently). Ideally we need to.
+/// # Example
 ///
-/// [`Session::interact`]: crate::Session::interact
-#[cfg(unix)]
+/// ```no_run
+/// use std::process::Command;
+/// use expectrl::repl::spawn_repl;
+///
+/// let mut sqlite = spawn_repl(Command::new("sqlite3"), "sqlite> ", Some(".quit".into()), false).unwrap();
+/// let out = sqlite.execute("SELECT 1 + 1;").unwrap();
+/// ```
 #[cfg(not(feature = "async"))]
-pub fn spawn_bash() -> Result<ReplSession<OsSession>, Error> {
-    const DEFAULT_PROMPT: &str = "EXPECT_PROMPT";
-
-    let mut cmd = Command::new("bash");
-    let _ = cmd.env("PS1", DEFAULT_PROMPT);
-    // bind 'set enable-bracketed-paste off' turns off paste mode,
-    // without it each command in bash starts and ends with an invisible sequence.
-    //
-    // We might need to turn it off optionally?
-    let _ = cmd.env(
-        "PROMPT_COMMAND",
-        "PS1=EXPECT_PROMPT; unset PROMPT_COMMAND; bind 'set enable-bracketed-paste off'",
-    );
-
+pub fn spawn_repl(
+    cmd: Command,
+    prompt: impl Into<String>,
+    quit: Option<String>,
+    echo: bool,
+) -> Result<ReplSession<OsSession>, Error> {
     let session = crate::session::Session::spawn(cmd)?;
 
-    let mut bash = ReplSession::new(session, DEFAULT_PROMPT);
-    bash.set_quit_command("quit");
+    let mut repl = ReplSession::new(session, prompt);
+    repl.quit_command = quit;
+    repl.set_echo(echo);
 
     // read a prompt to make it not available on next read.
     //
     // fix: somehow this line causes a different behaviour in iteract method.
     //      the issue most likely that with this line in interact mode ENTER produces CTRL-M
     //      when without the line it produces \r\n
+    repl.expect_prompt()?;
+
+    Ok(repl)
+}
+
+/// Spawns a repl session of an arbitrary program, e.g. `node`, `sqlite3` or `gdb`.
+///
+/// It sets the prompt, the quit command and the echo setting of the [`ReplSession`],
+/// and waits for the first prompt, so the session is ready to execute commands.
+#[cfg(feature = "async")]
+pub async fn spawn_repl(
+    cmd: Command,
+    prompt: impl Into<String>,
+    quit: Option<String>,
+    echo: bool,
+) -> Result<ReplSession<OsSession>, Error> {
+    let session = crate::session::Session::spawn(cmd)?;
 
-    bash.expect_prompt()?;
+    let mut repl = ReplSession::new(session, prompt);
+    repl.quit_command = quit;
+    repl.set_echo(echo);
 
-    Ok(bash)
+    // read a prompt to make it not available on next read.
+    repl.expect_prompt().await?;
+
+    Ok(repl)
+}
+
+/// Spawn a bash session.
+///
+/// It uses a custom prompt to be able to controll shell better.
+///
+/// If you wan't to use [`Session::interact`] method it is better to use just Session.
+/// Because we don't handle echoes here (currently). Ideally we need to.
+///
+/// [`Session::interact`]: crate::Session::interact
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+pub fn spawn_bash() -> Result<ReplSession<OsSession>, Error> {
+    spawn_repl(
+        bash_command(),
+        DEFAULT_PROMPT,
+        Some(String::from("quit")),
+        false,
+    )
 }
 
 /// Spawn a bash session.
@@ -70,7 +111,83 @@ pub fn spawn_bash() -> Result<ReplSession<OsSession>, Error> {
 #[cfg(unix)]
 #[cfg(feature = "async")]
 pub async fn spawn_bash() -> Result<ReplSession<OsSession>, Error> {
-    const DEFAULT_PROMPT: &str = "EXPECT_PROMPT";
+    spawn_repl(
+        bash_command(),
+        DEFAULT_PROMPT,
+        Some(String::from("quit")),
+        false,
+    )
+    .await
+}
+
+/// Spawn a POSIX shell (`sh`) session, e.g. `dash` or `ash`.
+///
+/// It uses a custom prompt to be able to controll the shell,
+/// and it's handy where `bash` is not available.
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+pub fn spawn_sh() -> Result<ReplSession<OsSession>, Error> {
+    spawn_repl(
+        sh_command(),
+        DEFAULT_PROMPT,
+        Some(String::from("exit")),
+        false,
+    )
+}
+
+/// Spawn a POSIX shell (`sh`) session, e.g. `dash` or `ash`.
+///
+/// It uses a custom prompt to be able to controll the shell.
+#[cfg(unix)]
+#[cfg(feature = "async")]
+pub async fn spawn_sh() -> Result<ReplSession<OsSession>, Error> {
+    spawn_repl(
+        sh_command(),
+        DEFAULT_PROMPT,
+        Some(String::from("exit")),
+        false,
+    )
+    .await
+}
+
+/// Spawn a zsh session.
+///
+/// It uses a custom prompt to be able to controll the shell.
+/// Startup files are not read, so `precmd` hooks and themes don't change the prompt,
+/// and the line editor is turned off, so there's no bracketed paste mode.
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+pub fn spawn_zsh() -> Result<ReplSession<OsSession>, Error> {
+    spawn_repl(
+        zsh_command(),
+        DEFAULT_PROMPT,
+        Some(String::from("exit")),
+        false,
+    )
+}
+
+/// Spawn a zsh session.
+///
+/// It uses a custom prompt to be able to controll the shell.
+/// Startup files are not read, so `precmd` hooks and themes don't change the prompt,
+/// and the line editor is turned off, so there's no bracketed paste mode.
+#[cfg(unix)]
+#[cfg(feature = "async")]
+pub async fn spawn_zsh() -> Result<ReplSession<OsSession>, Error> {
+    spawn_repl(
+        zsh_command(),
+        DEFAULT_PROMPT,
+        Some(String::from("exit")),
+        false,
+    )
+    .await
+}
+
+#[cfg(unix)]
+const DEFAULT_PROMPT: &str = "EXPECT_PROMPT";
+
+#[cfg(unix)]
+fn bash_command() -> Command {
     let mut cmd = Command::new("bash");
     let _ = cmd.env("PS1", DEFAULT_PROMPT);
     // bind 'set enable-bracketed-paste off' turns off paste mode,
@@ -82,16 +199,30 @@ pub async fn spawn_bash() -> Result<ReplSession<OsSession>, Error> {
         "PS1=EXPECT_PROMPT; unset PROMPT_COMMAND; bind 'set enable-bracketed-paste off'",
     );
 
-    let session = crate::session::Session::spawn(cmd)?;
+    cmd
+}
 
-    let mut bash = ReplSession::new(session, DEFAULT_PROMPT);
-    bash.set_quit_command("quit");
-    bash.set_echo(false);
+#[cfg(unix)]
+fn sh_command() -> Command {
+    let mut cmd = Command::new("sh");
+    let _ = cmd.env("PS1", DEFAULT_PROMPT);
+    // an interactive sh runs a file set in ENV, which may change the prompt.
+    let _ = cmd.env_remove("ENV");
 
-    // read a prompt to make it not available on next read.
-    bash.expect_prompt().await?;
+    cmd
+}
+
+#[cfg(unix)]
+fn zsh_command() -> Command {
+    let mut cmd = Command::new("zsh");
+    // -f skips startup files, which usually set a prompt.
+    //
+    // Without the line editor zsh doesn't use bracketed paste,
+    // and prompt_sp/prompt_cr stop it from printing a partial line marker before a prompt.
+    let _ = cmd.args(["-f", "+o", "zle", "+o", "prompt_sp", "+o", "prompt_cr"]);
+    let _ = cmd.env("PS1", DEFAULT_PROMPT);
 
-    Ok(bash)
+    cmd
 }
 
 /// Spawn default python's IDLE.
@@ -375,6 +506,80 @@ where
     }
 }
 
+#[cfg(not(feature = "async"))]
+impl<P, S> ReplSession<crate::Session<P, S>>
+where
+    crate::Session<P, S>: Expect,
+{
+    /// Sends a command to a repl and waits for the prompt within a given timeout,
+    /// returning its output.
+    ///
+    /// The timeout is used only for this command, the session timeout is left intact.
+    /// A timeout error is annotated with the command, see [`Error::context`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use expectrl::repl::spawn_bash;
+    ///
+    /// let mut p = spawn_bash().unwrap();
+    /// let out = p.execute_with_timeout("make", Duration::from_secs(600)).unwrap();
+    /// ```
+    pub fn execute_with_timeout<C>(&mut self, cmd: C, timeout: Duration) -> Result<Vec<u8>, Error>
+    where
+        C: AsRef<str>,
+    {
+        let cmd = cmd.as_ref();
+        TimeoutGuard::new(self, Some(timeout))
+            .execute(cmd)
+            .map_err(|err| execute_timeout_error(cmd, err))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, S> ReplSession<crate::Session<P, S>>
+where
+    crate::Session<P, S>: AsyncExpect + Unpin,
+{
+    /// Sends a command to a repl and waits for the prompt within a given timeout,
+    /// returning its output.
+    ///
+    /// The timeout is used only for this command, the session timeout is left intact.
+    /// A timeout error is annotated with the command, see [`Error::context`].
+    pub async fn execute_with_timeout<C>(
+        &mut self,
+        cmd: C,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, Error>
+    where
+        C: AsRef<str>,
+    {
+        let cmd = cmd.as_ref();
+        TimeoutGuard::new(self, Some(timeout))
+            .execute(cmd)
+            .await
+            .map_err(|err| execute_timeout_error(cmd, err))
+    }
+}
+
+impl<P, S> ExpectTimeout for ReplSession<crate::Session<P, S>> {
+    fn expect_timeout(&self) -> Option<Duration> {
+        self.session.get_expect_timeout()
+    }
+
+    fn set_expect_timeout(&mut self, timeout: Option<Duration>) {
+        self.session.set_expect_timeout(timeout)
+    }
+}
+
+fn execute_timeout_error(cmd: &str, err: Error) -> Error {
+    match err {
+        Error::ExpectTimeout => err.context(format!("executing {:?}", cmd)),
+        err => err,
+    }
+}
+
 impl<S> Healthcheck for ReplSession<S>
 where
     S: Healthcheck,
@@ -401,6 +606,10 @@ where
     fn set_echo(&mut self, on: bool) -> io::Result<bool> {
         self.get_session_mut().set_echo(on)
     }
+
+    fn settle_input(&mut self) -> io::Result<()> {
+        self.get_session_mut().settle_input()
+    }
 }
 
 impl<S> Expect for ReplSession<S>
@@ -0,0 +1,169 @@
+//! Internal helpers which abstract an async runtime away.
+//!
+//! By default IO is driven by [`async-io`] and timers by [`futures-timer`],
+//! which work with any executor.
+//! When `tokio` feature is on and a tokio runtime is running,
+//! a tokio reactor and timers are used instead,
+//! so the runtime must have IO and time drivers enabled.
+//!
+//! The choice is made when an IO handle or a timer is created,
+//! so a session created within a tokio runtime must be driven by it.
+//!
+//! [`async-io`]: https://docs.rs/async-io
+//! [`futures-timer`]: https://docs.rs/futures-timer
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+#[cfg(all(unix, not(feature = "tokio")))]
+pub(crate) use async_io::Async;
+
+#[cfg(all(unix, feature = "tokio"))]
+pub(crate) use tokio_fd::Async;
+
+/// Returns a future which completes after a given duration.
+///
+/// The timer starts when the function is called, not when the future is polled.
+pub(crate) fn sleep(duration: Duration) -> Sleep {
+    #[cfg(feature = "tokio")]
+    if is_tokio_running() {
+        return Sleep::Tokio(Box::pin(tokio::time::sleep(duration)));
+    }
+
+    Sleep::Timer(futures_timer::Delay::new(duration))
+}
+
+/// A timer future returned by [`sleep`].
+#[derive(Debug)]
+pub(crate) enum Sleep {
+    Timer(futures_timer::Delay),
+    #[cfg(feature = "tokio")]
+    Tokio(Pin<Box<tokio::time::Sleep>>),
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &mut *self {
+            Sleep::Timer(delay) => Pin::new(delay).poll(cx),
+            #[cfg(feature = "tokio")]
+            Sleep::Tokio(sleep) => sleep.as_mut().poll(cx),
+        }
+    }
+}
+
+/// Verifies whether the current thread runs within a tokio runtime.
+#[cfg(feature = "tokio")]
+fn is_tokio_running() -> bool {
+    tokio::runtime::Handle::try_current().is_ok()
+}
+
+#[cfg(all(unix, feature = "tokio"))]
+mod tokio_fd {
+    use std::{
+        io::{self, Read, Write},
+        os::unix::io::{AsRawFd, RawFd},
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use futures_lite::{ready, AsyncRead, AsyncWrite};
+    use tokio::io::unix::AsyncFd;
+
+    /// An IO handle registered either in a tokio reactor or in the `async-io` one,
+    /// depending on whether it's created within a tokio runtime.
+    ///
+    /// It mirrors [`async_io::Async`] so the streams don't depend on which runtime is used.
+    #[derive(Debug)]
+    pub(crate) enum Async<T: AsRawFd> {
+        AsyncIo(async_io::Async<T>),
+        Tokio(AsyncFd<T>),
+    }
+
+    impl<T: AsRawFd> Async<T> {
+        /// Puts the handle into a non-blocking mode and registers it in a reactor.
+        pub(crate) fn new(io: T) -> io::Result<Self> {
+            if !super::is_tokio_running() {
+                return async_io::Async::new(io).map(Self::AsyncIo);
+            }
+
+            crate::process::unix::make_non_blocking(io.as_raw_fd(), true)?;
+            AsyncFd::new(io).map(Self::Tokio)
+        }
+    }
+
+    impl<T: AsRawFd> AsRawFd for Async<T> {
+        fn as_raw_fd(&self) -> RawFd {
+            match self {
+                Async::AsyncIo(io) => io.as_raw_fd(),
+                Async::Tokio(fd) => fd.as_raw_fd(),
+            }
+        }
+    }
+
+    impl<T: AsRawFd + Read + Unpin> AsyncRead for Async<T> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            let fd = match &mut *self {
+                Async::AsyncIo(io) => return Pin::new(io).poll_read(cx, buf),
+                Async::Tokio(fd) => fd,
+            };
+
+            loop {
+                let mut guard = ready!(fd.poll_read_ready_mut(cx))?;
+                if let Ok(result) = guard.try_io(|fd| fd.get_mut().read(buf)) {
+                    return Poll::Ready(result);
+                }
+            }
+        }
+    }
+
+    impl<T: AsRawFd + Write + Unpin> AsyncWrite for Async<T> {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let fd = match &mut *self {
+                Async::AsyncIo(io) => return Pin::new(io).poll_write(cx, buf),
+                Async::Tokio(fd) => fd,
+            };
+
+            loop {
+                let mut guard = ready!(fd.poll_write_ready_mut(cx))?;
+                if let Ok(result) = guard.try_io(|fd| fd.get_mut().write(buf)) {
+                    return Poll::Ready(result);
+                }
+            }
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            let fd = match &mut *self {
+                Async::AsyncIo(io) => return Pin::new(io).poll_flush(cx),
+                Async::Tokio(fd) => fd,
+            };
+
+            loop {
+                let mut guard = ready!(fd.poll_write_ready_mut(cx))?;
+                if let Ok(result) = guard.try_io(|fd| fd.get_mut().flush()) {
+                    return Poll::Ready(result);
+                }
+            }
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            match &mut *self {
+                Async::AsyncIo(io) => Pin::new(io).poll_close(cx),
+                Async::Tokio(_) => self.poll_flush(cx),
+            }
+        }
+    }
+}
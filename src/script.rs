@@ -0,0 +1,187 @@
+//! This module contains a [`Script`] which describes a sequence of expect/send steps.
+//!
+//! A script can be built in code or,
+//! with the `serde` feature turned on, be loaded from a file (e.g. JSON or TOML).
+//!
+//! # Example
+//!
+//! ```no_run,ignore
+//! use expectrl::{spawn, script::{Script, Step}};
+//!
+//! let script = Script::new(vec![
+//!     Step::SendLine("Hello World".to_string()),
+//!     Step::Expect("Hello World".to_string()),
+//! ]);
+//!
+//! let mut p = spawn("cat").unwrap();
+//! let report = p.run_script(&script).unwrap();
+//! assert_eq!(report.len(), 1);
+//! ```
+//!
+//! The same script in JSON.
+//!
+//! ```json
+//! {
+//!     "steps": [
+//!         { "send_line": "Hello World" },
+//!         { "expect": "Hello World" }
+//!     ]
+//! }
+//! ```
+
+use std::time::Duration;
+
+use crate::{Captures, Eof, Error, Session};
+
+#[cfg(feature = "regex")]
+use crate::Regex;
+
+#[cfg(not(feature = "async"))]
+use crate::Expect;
+
+#[cfg(feature = "async")]
+use crate::AsyncExpect;
+
+/// Script is a list of steps to run against a [`Session`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Script {
+    /// A list of steps to be run in order.
+    pub steps: Vec<Step>,
+}
+
+impl Script {
+    /// Creates a new script.
+    pub fn new(steps: Vec<Step>) -> Self {
+        Self { steps }
+    }
+}
+
+/// Step is a single action of a [`Script`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Step {
+    /// Expects a string.
+    Expect(String),
+    /// Expects a regex.
+    #[cfg(feature = "regex")]
+    ExpectRegex(String),
+    /// Expects an EOF.
+    ExpectEof,
+    /// Sends a string.
+    Send(String),
+    /// Sends a string with a line ending.
+    SendLine(String),
+    /// Sets an expect timeout in milliseconds.
+    ///
+    /// `None` means no timeout.
+    SetTimeout(Option<u64>),
+}
+
+/// ScriptReport contains results of expect steps of a [`Script`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ScriptReport {
+    matches: Vec<(usize, Captures)>,
+}
+
+impl ScriptReport {
+    /// Returns captures of expect steps along with an index of a step.
+    pub fn matches(&self) -> &[(usize, Captures)] {
+        &self.matches
+    }
+
+    /// Returns captures of a step by its index.
+    pub fn get(&self, step: usize) -> Option<&Captures> {
+        self.matches
+            .iter()
+            .find(|(i, _)| *i == step)
+            .map(|(_, captures)| captures)
+    }
+
+    /// Returns a number of expect steps which were run.
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Verifies whether there were no expect steps.
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<P, S> Session<P, S>
+where
+    Self: Expect,
+{
+    /// Runs a [`Script`] step by step.
+    ///
+    /// It stops on a first failed step,
+    /// the returned error is wrapped in a context with the step which failed.
+    pub fn run_script(&mut self, script: &Script) -> Result<ScriptReport, Error> {
+        let mut report = ScriptReport::default();
+        for (i, step) in script.steps.iter().enumerate() {
+            let result = match step {
+                Step::Expect(s) => self.expect(s).map(Some),
+                #[cfg(feature = "regex")]
+                Step::ExpectRegex(re) => self.expect(Regex(re)).map(Some),
+                Step::ExpectEof => self.expect(Eof).map(Some),
+                Step::Send(s) => self.send(s).map(|_| None),
+                Step::SendLine(s) => self.send_line(s).map(|_| None),
+                Step::SetTimeout(timeout) => {
+                    self.set_expect_timeout(timeout.map(Duration::from_millis));
+                    Ok(None)
+                }
+            };
+
+            match result {
+                Ok(Some(captures)) => report.matches.push((i, captures)),
+                Ok(None) => {}
+                Err(err) => return Err(step_error(i, step, err)),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, S> Session<P, S>
+where
+    Self: AsyncExpect,
+{
+    /// Runs a [`Script`] step by step.
+    ///
+    /// It stops on a first failed step,
+    /// the returned error is wrapped in a context with the step which failed.
+    pub async fn run_script(&mut self, script: &Script) -> Result<ScriptReport, Error> {
+        let mut report = ScriptReport::default();
+        for (i, step) in script.steps.iter().enumerate() {
+            let result = match step {
+                Step::Expect(s) => self.expect(s).await.map(Some),
+                #[cfg(feature = "regex")]
+                Step::ExpectRegex(re) => self.expect(Regex(re)).await.map(Some),
+                Step::ExpectEof => self.expect(Eof).await.map(Some),
+                Step::Send(s) => self.send(s).await.map(|_| None),
+                Step::SendLine(s) => self.send_line(s).await.map(|_| None),
+                Step::SetTimeout(timeout) => {
+                    self.set_expect_timeout(timeout.map(Duration::from_millis));
+                    Ok(None)
+                }
+            };
+
+            match result {
+                Ok(Some(captures)) => report.matches.push((i, captures)),
+                Ok(None) => {}
+                Err(err) => return Err(step_error(i, step, err)),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+fn step_error(i: usize, step: &Step, err: Error) -> Error {
+    err.context(format!("script failed on step {} {:?}", i, step))
+}
@@ -12,16 +12,59 @@ use futures_lite::{
 };
 
 use crate::{
+    error::send_error,
+    needle::{capture, CollapseCr, MatchTransforms, Transformed, Until},
     process::{Healthcheck, Termios},
-    AsyncExpect, Captures, Error, Expect, Needle,
+    AsyncExpect, Captures, Eof, Error, Key, LineEnding, Needle,
 };
 
+use super::{eof_hook::EofHook, history::OutputHistory, lifetime::Lifetime};
+
 /// Session represents a spawned process and its streams.
 /// It controlls process and communication with it.
 #[derive(Debug)]
 pub struct Session<P, S> {
     process: P,
     stream: Stream<S>,
+    settings: Settings<P>,
+}
+
+/// Settings of a session.
+///
+/// They're kept together so a session moves all of them over when its stream is swapped.
+#[derive(Debug)]
+struct Settings<P> {
+    expect_timeout: Option<Duration>,
+    expect_lazy: bool,
+    max_read_iterations: Option<usize>,
+    collapse_cr: bool,
+    match_transforms: MatchTransforms,
+    output_trim: Vec<u8>,
+    timeout_discards_buffer: bool,
+    confirm_answers: (String, String),
+    application_cursor_keys: bool,
+    line_ending: LineEnding,
+    send_alive_check: Option<fn(&P) -> io::Result<bool>>,
+    lifetime: Lifetime<P>,
+}
+
+impl<P> Default for Settings<P> {
+    fn default() -> Self {
+        Self {
+            expect_timeout: Some(Duration::from_millis(10000)),
+            expect_lazy: false,
+            max_read_iterations: None,
+            collapse_cr: false,
+            match_transforms: MatchTransforms::default(),
+            output_trim: Vec::new(),
+            timeout_discards_buffer: false,
+            confirm_answers: (String::from("yes"), String::from("no")),
+            application_cursor_keys: false,
+            line_ending: LineEnding::default(),
+            send_alive_check: None,
+            lifetime: Lifetime::new(),
+        }
+    }
 }
 
 // GEt back to the solution where Logger is just dyn Write instead of all these magic with type system.....
@@ -32,6 +75,7 @@ impl<P, S> Session<P, S> {
         Ok(Self {
             process,
             stream: Stream::new(stream),
+            settings: Settings::default(),
         })
     }
 
@@ -57,7 +101,7 @@ impl<P, S> Session<P, S> {
 
     /// Set the pty session's expect timeout.
     pub fn set_expect_timeout(&mut self, expect_timeout: Option<Duration>) {
-        self.stream.set_expect_timeout(expect_timeout);
+        self.settings.expect_timeout = expect_timeout;
     }
 
     /// Set a expect algorithm to be either gready or lazy.
@@ -66,7 +110,272 @@ impl<P, S> Session<P, S> {
     ///
     /// See [Session::expect].
     pub fn set_expect_lazy(&mut self, is_lazy: bool) {
-        self.stream.expect_lazy = is_lazy;
+        self.settings.expect_lazy = is_lazy;
+    }
+
+    /// Sets a maximum number of reads an expect call can do without a match.
+    ///
+    /// Only reads which bring new data are counted.
+    /// Once the limit is reached [`Error::MaxIterations`] is returned.
+    /// It's a safety valve against needles which never match,
+    /// unlike a timeout it doesn't depend on a wall clock.
+    ///
+    /// Default is `None` which means no limit.
+    pub fn set_max_read_iterations(&mut self, max: Option<usize>) {
+        self.settings.max_read_iterations = max;
+    }
+
+    /// Sets a number of consecutive empty reads required to declare an EOF.
+    ///
+    /// Some streams may return a transient empty read while a process is still alive,
+    /// which would be treated as an EOF otherwise.
+    /// Requiring a few consecutive empty reads makes an EOF detection more robust.
+    ///
+    /// Default is 1; 0 is treated as 1.
+    pub fn set_eof_confirm_reads(&mut self, n: usize) {
+        self.stream.eof_confirm_reads = n.max(1);
+    }
+
+    /// Sets whether lines overwritten via carriage returns are collapsed for matching.
+    ///
+    /// When it's on, needles are matched against the output as a terminal would display it,
+    /// e.g. `10%\r20%\r30%` is seen as `30%`.
+    /// It's a transformation of a view used for matching, the buffer itself is left intact,
+    /// so [`Captures`] contain the original bytes.
+    ///
+    /// Default is `false`.
+    pub fn set_collapse_cr(&mut self, on: bool) {
+        self.settings.collapse_cr = on;
+    }
+
+    /// Adds a transformation of a view used for matching.
+    ///
+    /// Transformations are chained in the order they were added,
+    /// e.g. to strip ANSI sequences, normalize newlines or lowercase the output.
+    /// Only a view the needles are matched against is transformed,
+    /// the buffer and what gets consumed stay in original bytes, so [`Captures`] contain them.
+    ///
+    /// A transformation may be called several times per match and on prefixes of the buffer
+    /// in order to map a match back onto the original bytes,
+    /// so it must be a pure function of its input where a prefix is transformed into a prefix.
+    ///
+    /// The transformations are applied before [`Session::set_collapse_cr`].
+    pub fn add_match_transform<F>(&mut self, transform: F)
+    where
+        F: FnMut(&[u8]) -> Vec<u8> + Send + 'static,
+    {
+        self.settings.match_transforms.push(Box::new(transform));
+    }
+
+    /// Removes all transformations added by [`Session::add_match_transform`].
+    pub fn clear_match_transforms(&mut self) {
+        self.settings.match_transforms.clear();
+    }
+
+    /// Sets a pattern which is trimmed from the end of [`Captures::before`]
+    /// in captures returned by expect calls.
+    ///
+    /// It's handy for REPLs, where the output before a prompt ends with a part of it
+    /// or with whitespace, so the output of a command comes out clean.
+    /// It's only a view of the returned captures, the stream and the buffer are left intact
+    /// and [`Captures::as_bytes`] still contains the pattern.
+    ///
+    /// An empty pattern, which is the default, turns it off.
+    pub fn set_output_trim(&mut self, pattern: impl Into<Vec<u8>>) {
+        self.settings.output_trim = pattern.into();
+    }
+
+    /// Sets whether an expect timeout discards the output which was read but not matched.
+    ///
+    /// By default on [`Error::ExpectTimeout`] the buffer is left intact,
+    /// so the next expect call can match the partial output.
+    /// When it's on the buffer is cleared instead, so the next call starts fresh
+    /// and the partial output can't cause a spurious match.
+    ///
+    /// Default is `false`.
+    pub fn set_timeout_discards_buffer(&mut self, on: bool) {
+        self.settings.timeout_discards_buffer = on;
+    }
+
+    /// Sets answers which are sent by [`Session::confirm`].
+    ///
+    /// Default answers are `yes` and `no`.
+    pub fn set_confirm_answers(&mut self, yes: &str, no: &str) {
+        self.settings.confirm_answers = (yes.to_owned(), no.to_owned());
+    }
+
+    /// Sets whether keys sent by [`Session::send_keys`] use an application cursor mode (`DECCKM`).
+    ///
+    /// Full screen programs (e.g. `vim` or `less`) usually switch a terminal into the mode,
+    /// so they expect arrow keys as `\x1bOA` rather than `\x1b[A`.
+    ///
+    /// Default is `false`.
+    pub fn set_application_cursor_keys(&mut self, on: bool) {
+        self.settings.application_cursor_keys = on;
+    }
+
+    /// Sets a line ending which `send_line` puts after a line.
+    ///
+    /// Default is `\r\n` on windows and `\n` on other platforms,
+    /// see [`LineEnding`].
+    pub fn set_line_ending(&mut self, ending: LineEnding) {
+        self.settings.line_ending = ending;
+    }
+
+    /// Sets a callback which is called the first time an EOF is confirmed,
+    /// see [`Session::set_eof_confirm_reads`].
+    ///
+    /// It's called once, from whichever call waiting for the output confirms the EOF first,
+    /// so it's a precise notification of the process output being over
+    /// without polling the process liveness.
+    ///
+    /// Setting a new callback replaces the previous one if it wasn't called yet.
+    pub fn set_on_eof<F>(&mut self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.stream.stream.on_eof.set(f);
+    }
+
+    /// Sets whether the process liveness is verified before anything is sent to it.
+    ///
+    /// Writing to a process which has exited may succeed silently or even block,
+    /// as the bytes are buffered by a terminal.
+    /// When it's on, [`AsyncExpect::send`] and [`AsyncExpect::send_line`]
+    /// return [`Error::ProcessExited`] for a process which is not alive.
+    ///
+    /// Regardless of the setting a write error caused by a gone process
+    /// (e.g. a broken pipe) is reported as [`Error::ProcessExited`].
+    ///
+    /// Default is `false`.
+    pub fn set_send_checks_alive(&mut self, on: bool)
+    where
+        P: Healthcheck,
+    {
+        let check: fn(&P) -> io::Result<bool> = P::is_alive;
+        self.settings.send_alive_check = on.then_some(check);
+    }
+
+    /// Sets a limit of the total lifetime of the session, counting from its creation.
+    ///
+    /// Once it's exceeded expect, read and send calls fail with [`Error::LifetimeExceeded`].
+    /// It's a watchdog for tests and CI, unlike the expect timeout it limits the session as a whole,
+    /// so a loop of successful expect calls can't run forever.
+    ///
+    /// An expect call waits no longer than the rest of the lifetime,
+    /// even if its own timeout is longer.
+    /// Other calls, like a send or a plain read, check it only at the beginning,
+    /// so one which is already waiting is not interrupted.
+    ///
+    /// Default is `None`, no limit.
+    pub fn set_lifetime(&mut self, lifetime: Option<Duration>) {
+        self.settings.lifetime.set_limit(lifetime);
+    }
+
+    /// Sets a function which is called to kill the process once the lifetime is exceeded.
+    pub(crate) fn set_lifetime_kill(&mut self, kill: Option<fn(&mut P) -> io::Result<()>>) {
+        self.settings.lifetime.set_kill(kill);
+    }
+
+    fn check_lifetime(&mut self) -> Result<(), Error> {
+        self.settings.lifetime.check(&mut self.process)
+    }
+
+    fn check_alive_before_send(&self) -> Result<(), Error> {
+        if let Some(is_alive) = self.settings.send_alive_check {
+            if !is_alive(&self.process)? {
+                return Err(Error::ProcessExited);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns bytes which were read but not consumed by an expect call yet.
+    pub(crate) fn get_available(&mut self) -> &[u8] {
+        self.stream.get_available()
+    }
+
+    /// Returns a copy of bytes which were read from the process but not consumed yet.
+    ///
+    /// Together with [`Session::import_buffer`] it allows to hand the state over to another session,
+    /// e.g. when reattaching to an existing pty.
+    /// Use [`Session::drain_buffer`] to take the bytes out instead.
+    pub fn export_buffer(&self) -> Vec<u8> {
+        self.stream.stream.buffer().to_vec()
+    }
+
+    pub(crate) fn get_expect_timeout(&self) -> Option<Duration> {
+        self.settings.expect_timeout
+    }
+
+    pub(crate) fn get_confirm_answer(&self, answer: bool) -> &str {
+        match answer {
+            true => &self.settings.confirm_answers.0,
+            false => &self.settings.confirm_answers.1,
+        }
+    }
+
+    pub(crate) fn is_timeout_discards_buffer(&self) -> bool {
+        self.settings.timeout_discards_buffer
+    }
+
+    pub(crate) fn is_expect_lazy(&self) -> bool {
+        self.settings.expect_lazy
+    }
+
+    /// Starts keeping the last `capacity` bytes read from the process.
+    ///
+    /// All reads are recorded, either they're done by expect calls or by [`AsyncRead`] methods.
+    /// Calling it again changes the capacity keeping the most recent bytes.
+    ///
+    /// It may be useful for diagnostics, e.g. to dump the recent output when a process crashes.
+    pub fn enable_output_history(&mut self, capacity: usize) {
+        match &mut self.stream.stream.history {
+            Some(history) => history.set_capacity(capacity),
+            history => *history = Some(OutputHistory::new(capacity)),
+        }
+    }
+
+    /// Returns the last bytes read from the process.
+    ///
+    /// It's empty unless [`Session::enable_output_history`] was called.
+    pub fn output_history(&self) -> Vec<u8> {
+        self.stream
+            .stream
+            .history
+            .as_ref()
+            .map(OutputHistory::to_vec)
+            .unwrap_or_default()
+    }
+
+    /// Puts bytes to the end of the inner buffer,
+    /// so they'll be examined by the next expect call before anything read from the process.
+    ///
+    /// It's a counterpart of [`Session::export_buffer`].
+    pub fn import_buffer(&mut self, buf: &[u8]) {
+        self.stream.keep(buf);
+    }
+
+    /// Takes bytes which were read from the process but not consumed yet out of the buffer.
+    ///
+    /// It's handy to find out what was read before an [`Error::ExpectTimeout`].
+    /// The bytes are consumed, so a next expect call won't see them;
+    /// use [`Session::export_buffer`] to get a copy instead.
+    pub fn drain_buffer(&mut self) -> Vec<u8> {
+        let stream = &mut self.stream.stream;
+        let buf = stream.buffer.drain(..stream.length).collect();
+        stream.length = 0;
+        buf
+    }
+
+    /// Drops bytes which were read from the process but not consumed yet.
+    ///
+    /// It discards unread output, so a next expect call examines only what comes afterwards;
+    /// it's handy to resync before expecting a fresh prompt.
+    /// Use [`Session::drain_buffer`] to get the discarded bytes.
+    pub fn clear_buffer(&mut self) {
+        let _ = self.drain_buffer();
     }
 
     pub(crate) fn swap_stream<F: FnOnce(S) -> R, R>(
@@ -74,11 +383,24 @@ impl<P, S> Session<P, S> {
         new_stream: F,
     ) -> Result<Session<P, R>, Error> {
         let buf = self.stream.get_available().to_owned();
+        let history = self.stream.stream.history.take();
+        let on_eof = std::mem::take(&mut self.stream.stream.on_eof);
+        #[cfg(feature = "render")]
+        let screen = std::mem::take(&mut self.stream.stream.screen);
+        let eof_confirm_reads = self.stream.eof_confirm_reads;
 
         let stream = self.stream.into_inner();
         let stream = new_stream(stream);
         let mut session = Session::new(self.process, stream)?;
         session.stream.keep(&buf);
+        session.stream.stream.history = history;
+        session.stream.stream.on_eof = on_eof;
+        #[cfg(feature = "render")]
+        {
+            session.stream.stream.screen = screen;
+        }
+        session.stream.eof_confirm_reads = eof_confirm_reads;
+        session.settings = self.settings;
         Ok(session)
     }
 
@@ -89,6 +411,75 @@ impl<P, S> Session<P, S> {
     {
         self.stream.is_empty().await
     }
+
+    /// Reads until `max` bytes are read, an EOF is reached or the timeout is elapsed.
+    ///
+    /// It returns whatever was read, which may be less than `max` bytes or even nothing,
+    /// so unlike [`Read::read_to_end`](std::io::Read::read_to_end)
+    /// the memory used is bounded, which is safer for untrusted processes.
+    ///
+    /// Bytes which were read by previous calls but not consumed are returned first.
+    pub async fn read_up_to(&mut self, max: usize, timeout: Duration) -> io::Result<Vec<u8>>
+    where
+        S: AsyncRead + Unpin,
+    {
+        let mut buf = vec![0; max];
+        let mut n = 0;
+
+        let read = async {
+            while n < max {
+                let read = self.stream.read(&mut buf[n..]).await?;
+                if self.stream.check_eof(read) {
+                    break;
+                }
+
+                n += read;
+            }
+
+            io::Result::Ok(())
+        };
+        let timeout = async {
+            crate::runtime::sleep(timeout).await;
+            Ok(())
+        };
+        futures_lite::future::or(read, timeout).await?;
+
+        buf.truncate(n);
+
+        Ok(buf)
+    }
+
+    /// Reads everything which is available right now without waiting.
+    ///
+    /// Bytes which were read by previous calls but not consumed are returned first.
+    /// Unlike a read call it drains all pending output rather than a single chunk,
+    /// and it returns an empty buffer if nothing is ready.
+    pub async fn read_available(&mut self) -> io::Result<Vec<u8>>
+    where
+        S: AsyncRead + Unpin,
+    {
+        self.stream.fill_available().await?;
+        Ok(self.drain_buffer())
+    }
+
+    /// Reads everything which is available right now without waiting as a string,
+    /// see [`Session::read_available`].
+    ///
+    /// An incomplete UTF-8 character at the end is left in the buffer until the rest of it comes.
+    /// Invalid UTF-8 results in [`io::ErrorKind::InvalidData`] error,
+    /// in which case nothing is consumed.
+    pub async fn read_available_string(&mut self) -> io::Result<String>
+    where
+        S: AsyncRead + Unpin,
+    {
+        self.stream.fill_available().await?;
+        let buf = self.stream.get_available();
+        let n = super::utf8_prefix_len(buf)?;
+        let text = String::from_utf8_lossy(&buf[..n]).into_owned();
+        self.stream.stream.consume(n);
+
+        Ok(text)
+    }
 }
 
 impl<P, S> AsyncExpect for Session<P, S>
@@ -99,23 +490,64 @@ where
     where
         N: Needle,
     {
-        match self.stream.expect_lazy {
-            true => self.stream.expect_lazy(needle).await,
-            false => self.stream.expect_gready(needle).await,
+        self.check_lifetime()?;
+
+        let needle = Transformed::new(
+            CollapseCr::new(needle, self.settings.collapse_cr),
+            &self.settings.match_transforms,
+        );
+        // The expect timeout is capped by the lifetime, so a call doesn't outlive the session.
+        let remaining = self.settings.lifetime.remaining();
+        let result = crate::trace::expect_async(&needle, async {
+            let expect_future = async {
+                let timeout = self.settings.expect_timeout;
+                let max = self.settings.max_read_iterations;
+                match self.settings.expect_lazy {
+                    true => self.stream.expect_lazy(&needle, timeout, max).await,
+                    false => self.stream.expect_gready(&needle, timeout, max).await,
+                }
+            };
+
+            within_lifetime(remaining, expect_future).await
+        })
+        .await;
+
+        if matches!(result, Err(Error::LifetimeExceeded)) {
+            return Err(self.settings.lifetime.exceed(&mut self.process));
         }
+
+        if self.settings.timeout_discards_buffer && matches!(result, Err(Error::ExpectTimeout)) {
+            let n = self.stream.stream.length;
+            self.stream.stream.consume(n);
+        }
+
+        result.map(|found| found.with_before_trim(&self.settings.output_trim))
     }
 
     async fn check<N>(&mut self, needle: N) -> Result<Captures, Error>
     where
         N: Needle,
     {
-        self.stream.check(needle).await
+        self.check_lifetime()?;
+
+        let needle = Transformed::new(
+            CollapseCr::new(needle, self.settings.collapse_cr),
+            &self.settings.match_transforms,
+        );
+        let found = self.stream.check(needle, true).await?;
+        Ok(found.with_before_trim(&self.settings.output_trim))
     }
 
     async fn is_matched<N>(&mut self, needle: N) -> Result<bool, Error>
     where
         N: Needle,
     {
+        self.check_lifetime()?;
+
+        let needle = Transformed::new(
+            CollapseCr::new(needle, self.settings.collapse_cr),
+            &self.settings.match_transforms,
+        );
         self.stream.is_matched(needle).await
     }
 
@@ -123,20 +555,281 @@ where
     where
         B: AsRef<[u8]>,
     {
-        self.stream.write_all(buf.as_ref()).await.map_err(Error::IO)
+        self.check_lifetime()?;
+        self.check_alive_before_send()?;
+
+        crate::trace::send(buf.as_ref());
+        self.stream
+            .write_all(buf.as_ref())
+            .await
+            .map_err(send_error)
     }
 
     async fn send_line<B>(&mut self, buf: B) -> Result<(), Error>
     where
         B: AsRef<[u8]>,
     {
-        #[cfg(windows)]
-        const LINE_ENDING: &[u8] = b"\r\n";
-        #[cfg(not(windows))]
-        const LINE_ENDING: &[u8] = b"\n";
+        self.write_line(buf.as_ref(), crate::trace::send).await
+    }
+}
+
+impl<P, S> Session<P, S>
+where
+    S: AsyncWrite + AsyncRead + Unpin,
+{
+    /// Sends a line, the line is recorded in a trace by a given function.
+    pub(crate) async fn write_line(&mut self, buf: &[u8], trace: fn(&[u8])) -> Result<(), Error> {
+        let line_ending = self.settings.line_ending.as_bytes();
+
+        self.check_lifetime()?;
+        self.check_alive_before_send()?;
+
+        trace(buf);
+        crate::trace::send(line_ending);
+        self.stream.write_all(buf).await.map_err(send_error)?;
+        self.stream
+            .write_all(line_ending)
+            .await
+            .map_err(send_error)?;
+
+        Ok(())
+    }
+
+    /// Sends special keys, like arrows or function keys,
+    /// as the VT100/xterm escape sequences a terminal would send.
+    ///
+    /// See [`Session::set_application_cursor_keys`] for a mode the arrows are sent in.
+    pub async fn send_keys(&mut self, keys: &[Key]) -> Result<(), Error> {
+        let bytes = keys
+            .iter()
+            .flat_map(|key| key.as_bytes(self.settings.application_cursor_keys))
+            .copied()
+            .collect::<Vec<_>>();
+        self.send(bytes).await
+    }
+
+    /// Expects a needle using a given timeout instead of the session one.
+    ///
+    /// The timeout is used only for this call,
+    /// the session timeout is restored afterwards regardless of the result.
+    /// `None` waits for the needle without a limit.
+    pub async fn expect_timeout<N>(
+        &mut self,
+        needle: N,
+        timeout: Option<Duration>,
+    ) -> Result<Captures, Error>
+    where
+        N: Needle,
+    {
+        super::TimeoutGuard::new(self, timeout).expect(needle).await
+    }
 
-        self.stream.write_all(buf.as_ref()).await?;
-        self.stream.write_all(LINE_ENDING).await?;
+    /// Reads until an EOF and returns all the output,
+    /// using a given timeout instead of the session one like [`Session::expect_timeout`].
+    ///
+    /// On [`Error::ExpectTimeout`] the bytes read so far are left in the buffer,
+    /// so they can be taken via [`Session::drain_buffer`]
+    /// (unless [`Session::set_timeout_discards_buffer`] is on).
+    pub async fn expect_eof(&mut self, timeout: Option<Duration>) -> Result<Vec<u8>, Error> {
+        let found = self.expect_timeout(Eof, timeout).await?;
+        Ok(found.as_bytes().to_vec())
+    }
+
+    /// Checks whether a needle is matched in available bytes like [`AsyncExpect::check`] does,
+    /// but leaves all bytes in the buffer,
+    /// so a following call sees them again.
+    ///
+    /// Unlike [`AsyncExpect::is_matched`] it returns the captures,
+    /// which are empty if nothing was found.
+    pub async fn peek<N>(&mut self, needle: N) -> Result<Captures, Error>
+    where
+        N: Needle,
+    {
+        self.check_lifetime()?;
+
+        let needle = Transformed::new(
+            CollapseCr::new(needle, self.settings.collapse_cr),
+            &self.settings.match_transforms,
+        );
+        let found = self.stream.check(needle, false).await?;
+        Ok(found.with_before_trim(&self.settings.output_trim))
+    }
+
+    /// Returns a stream which repeatedly expects an `item`
+    /// until a `stop` needle is matched.
+    ///
+    /// Whichever needle is found first in the buffer is taken.
+    /// The stream ends when `stop` is matched (its bytes are consumed),
+    /// or right after an error (e.g. timeout or EOF) is yielded.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use expectrl::spawn;
+    /// use futures_lite::StreamExt;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let mut p = spawn("tail -f /var/log/syslog").unwrap();
+    /// let mut lines = Box::pin(p.expect_until('\n', "DONE"));
+    /// while let Some(line) = lines.next().await {
+    ///     let line = line.unwrap();
+    ///     println!("{}", String::from_utf8_lossy(line.before()));
+    /// }
+    /// # });
+    /// ```
+    pub fn expect_until<'a, I, T>(
+        &'a mut self,
+        item: I,
+        stop: T,
+    ) -> impl futures_lite::Stream<Item = Result<Captures, Error>> + 'a
+    where
+        I: Needle + 'a,
+        T: Needle + 'a,
+        P: 'a,
+    {
+        let needle = Until::new(item, stop);
+        futures_lite::stream::unfold(Some((self, needle)), |state| async move {
+            let (session, needle) = state?;
+            match session.expect(&needle).await {
+                Ok(_) if needle.is_stopped() => None,
+                Ok(found) => Some((Ok(found), Some((session, needle)))),
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+
+    /// Returns a stream of fixed-width records, `width` bytes each.
+    ///
+    /// The stream ends on an EOF, bytes of an incomplete record are left in the buffer.
+    /// Other errors (e.g. timeout) are yielded and end the stream as well.
+    pub fn records(
+        &mut self,
+        width: usize,
+    ) -> impl futures_lite::Stream<Item = Result<Vec<u8>, Error>> + '_ {
+        futures_lite::stream::unfold(Some(self), move |state| async move {
+            let session = state?;
+            match session.read_record(width).await {
+                Ok(record) => Some((Ok(record), Some(session))),
+                Err(Error::Eof) => None,
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+
+    /// Expects a needle until a `cancel` future is resolved.
+    ///
+    /// It returns `None` if the expectation was cancelled.
+    /// Bytes which were read but not matched are kept in the buffer,
+    /// so they can be matched by next calls.
+    ///
+    /// The expect timeout is still applied.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let mut p = expectrl::spawn("cat").unwrap();
+    /// let shutdown = futures_timer::Delay::new(Duration::from_secs(1));
+    /// let found = p.expect_until_signal("Hello", shutdown).await.unwrap();
+    /// assert!(found.is_none());
+    /// # });
+    /// ```
+    pub async fn expect_until_signal<N, F>(
+        &mut self,
+        needle: N,
+        cancel: F,
+    ) -> Result<Option<Captures>, Error>
+    where
+        N: Needle,
+        F: std::future::Future<Output = ()>,
+    {
+        let expect = async { self.expect(needle).await.map(Some) };
+        let cancel = async {
+            cancel.await;
+            Ok(None)
+        };
+
+        futures_lite::future::or(expect, cancel).await
+    }
+
+    /// Expects a needle to be displayed on a screen.
+    ///
+    /// The output is rendered by a minimal terminal emulator, see [`Screen`],
+    /// and the needle is checked against the rendered text rather than the raw output.
+    /// Rows of the screen are separated by `\n`.
+    ///
+    /// All output read so far is rendered, including one consumed by other calls,
+    /// and on a match all buffered output is consumed.
+    ///
+    /// [`Screen`]: crate::render::Screen
+    #[cfg(feature = "render")]
+    pub async fn expect_screen<N>(&mut self, needle: N) -> Result<(), Error>
+    where
+        N: Needle,
+    {
+        self.check_lifetime()?;
+
+        let remaining = self.settings.lifetime.remaining();
+        let expect = self.stream.expect_screen(
+            needle,
+            self.settings.expect_timeout,
+            self.settings.max_read_iterations,
+        );
+        let result = within_lifetime(remaining, expect).await;
+        if matches!(result, Err(Error::LifetimeExceeded)) {
+            return Err(self.settings.lifetime.exceed(&mut self.process));
+        }
+
+        result
+    }
+
+    /// Returns a screen with all output read so far rendered onto it.
+    ///
+    /// The output which is available at the moment is read first,
+    /// but it's not consumed.
+    #[cfg(feature = "render")]
+    pub async fn screen(&mut self) -> Result<crate::render::Screen, Error> {
+        loop {
+            let length = self.stream.stream.length;
+            let eof = self.stream.try_fill().await?;
+            if eof || self.stream.stream.length == length {
+                break;
+            }
+        }
+
+        Ok(self.stream.stream.screen.clone())
+    }
+}
+
+impl<P, S> Session<P, S>
+where
+    S: AsyncWrite + Unpin,
+{
+    /// Sends a text byte by byte with a delay between them.
+    ///
+    /// Each byte is flushed right away.
+    /// It mimics a human typing, which may be necessary for programs
+    /// which drop input sent too fast (e.g. devices connected over serial).
+    ///
+    /// Like [`Session::send`] it fails if the process has exited or the session lifetime is exceeded,
+    /// the lifetime is checked before each byte as the delays may add up.
+    pub async fn send_slow(&mut self, text: &str, per_char: Duration) -> Result<(), Error> {
+        self.check_lifetime()?;
+        self.check_alive_before_send()?;
+
+        crate::trace::send(text.as_bytes());
+
+        for (i, b) in text.bytes().enumerate() {
+            if i > 0 {
+                crate::runtime::sleep(per_char).await;
+                self.check_lifetime()?;
+            }
+
+            self.stream.write_all(&[b]).await.map_err(send_error)?;
+            self.stream.flush().await.map_err(send_error)?;
+        }
 
         Ok(())
     }
@@ -198,6 +891,10 @@ where
         cx: &mut Context<'_>,
         buf: &mut [u8],
     ) -> Poll<io::Result<usize>> {
+        if let Err(err) = self.check_lifetime() {
+            return Poll::Ready(Err(err.into()));
+        }
+
         Pin::new(&mut self.stream).poll_read(cx, buf)
     }
 }
@@ -208,7 +905,12 @@ where
     S: AsyncRead + Unpin,
 {
     fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
-        Pin::new(&mut self.get_mut().stream).poll_fill_buf(cx)
+        let session = self.get_mut();
+        if let Err(err) = session.check_lifetime() {
+            return Poll::Ready(Err(err.into()));
+        }
+
+        Pin::new(&mut session.stream).poll_fill_buf(cx)
     }
 
     fn consume(mut self: Pin<&mut Self>, amt: usize) {
@@ -227,6 +929,36 @@ where
     fn set_echo(&mut self, on: bool) -> io::Result<bool> {
         P::set_echo(self.get_process_mut(), on)
     }
+
+    fn settle_input(&mut self) -> io::Result<()> {
+        P::settle_input(self.get_process_mut())
+    }
+}
+
+/// Runs a future until it's done or a remaining lifetime of a session is over,
+/// in which case [`Error::LifetimeExceeded`] is returned.
+async fn within_lifetime<T, F>(remaining: Option<Duration>, future: F) -> Result<T, Error>
+where
+    F: std::future::Future<Output = Result<T, Error>>,
+{
+    match remaining {
+        Some(remaining) => {
+            let lifetime_future = crate::runtime::sleep(remaining);
+            futures_lite::future::or(future, async {
+                lifetime_future.await;
+                Err(Error::LifetimeExceeded)
+            })
+            .await
+        }
+        None => future.await,
+    }
+}
+
+fn check_read_iterations(iterations: usize, max: Option<usize>) -> Result<(), Error> {
+    match max {
+        Some(max) if iterations >= max => Err(Error::MaxIterations),
+        _ => Ok(()),
+    }
 }
 
 /// Session represents a spawned process and its streams.
@@ -234,8 +966,8 @@ where
 #[derive(Debug)]
 struct Stream<S> {
     stream: BufferedStream<S>,
-    expect_timeout: Option<Duration>,
-    expect_lazy: bool,
+    eof_confirm_reads: usize,
+    zero_reads: usize,
 }
 
 impl<S> Stream<S> {
@@ -243,8 +975,8 @@ impl<S> Stream<S> {
     fn new(stream: S) -> Self {
         Self {
             stream: BufferedStream::new(stream),
-            expect_timeout: Some(Duration::from_millis(10000)),
-            expect_lazy: false,
+            eof_confirm_reads: 1,
+            zero_reads: 0,
         }
     }
 
@@ -258,9 +990,22 @@ impl<S> Stream<S> {
         &mut self.stream.stream
     }
 
-    /// Set the pty session's expect timeout.
-    fn set_expect_timeout(&mut self, expect_timeout: Option<Duration>) {
-        self.expect_timeout = expect_timeout;
+    /// Registers a read result and verifies whether an EOF is confirmed.
+    ///
+    /// An EOF is confirmed only after `eof_confirm_reads` consecutive zero reads.
+    fn check_eof(&mut self, n: usize) -> bool {
+        if n > 0 {
+            self.zero_reads = 0;
+            return false;
+        }
+
+        self.zero_reads = self.zero_reads.saturating_add(1);
+        if self.zero_reads < self.eof_confirm_reads {
+            return false;
+        }
+
+        self.stream.on_eof.call();
+        true
     }
 
     /// Save a bytes in inner buffer.
@@ -284,34 +1029,85 @@ impl<S> Stream<S>
 where
     S: AsyncRead + Unpin,
 {
-    async fn expect_gready<N: Needle>(&mut self, needle: N) -> Result<Captures, Error> {
-        let expect_timeout = self.expect_timeout;
-
+    async fn expect_gready<N: Needle>(
+        &mut self,
+        needle: N,
+        expect_timeout: Option<Duration>,
+        max_read_iterations: Option<usize>,
+    ) -> Result<Captures, Error> {
         let expect_future = async {
             let mut eof = false;
+            let mut iterations = 0;
             loop {
+                // The buffer is checked before a read,
+                // so bytes left by previous calls are matched without any IO.
                 let data = self.stream.buffer();
 
-                let found = Needle::check(&needle, data, eof)?;
+                if let Some(found) = capture(&needle, data, eof)? {
+                    self.stream.consume(found.as_bytes().len());
+                    return Ok(found);
+                }
 
-                if !found.is_empty() {
-                    let end_index = Captures::right_most_index(&found);
-                    let involved_bytes = data[..end_index].to_vec();
-                    self.stream.consume(end_index);
+                if eof {
+                    return Err(Error::Eof);
+                }
+
+                check_read_iterations(iterations, max_read_iterations)?;
+
+                let n = self.stream.fill().await?;
+                eof = self.check_eof(n);
+                if n > 0 {
+                    iterations += 1;
+                }
+            }
+        };
 
-                    return Ok(Captures::new(involved_bytes, found));
+        if let Some(timeout) = expect_timeout {
+            let timeout_future = crate::runtime::sleep(timeout);
+            futures_lite::future::or(expect_future, async {
+                timeout_future.await;
+                Err(Error::ExpectTimeout)
+            })
+            .await
+        } else {
+            expect_future.await
+        }
+    }
+
+    #[cfg(feature = "render")]
+    async fn expect_screen<N: Needle>(
+        &mut self,
+        needle: N,
+        expect_timeout: Option<Duration>,
+        max_read_iterations: Option<usize>,
+    ) -> Result<(), Error> {
+        let expect_future = async {
+            let mut eof = false;
+            let mut iterations = 0;
+            loop {
+                let contents = self.stream.screen.contents();
+                let found = needle.check(contents.as_bytes(), eof)?;
+                if !found.is_empty() {
+                    self.stream.consume(self.stream.length);
+                    return Ok(());
                 }
 
                 if eof {
                     return Err(Error::Eof);
                 }
 
-                eof = self.stream.fill().await? == 0;
+                check_read_iterations(iterations, max_read_iterations)?;
+
+                let n = self.stream.fill().await?;
+                eof = self.check_eof(n);
+                if n > 0 {
+                    iterations += 1;
+                }
             }
         };
 
         if let Some(timeout) = expect_timeout {
-            let timeout_future = futures_timer::Delay::new(timeout);
+            let timeout_future = crate::runtime::sleep(timeout);
             futures_lite::future::or(expect_future, async {
                 timeout_future.await;
                 Err(Error::ExpectTimeout)
@@ -322,11 +1118,15 @@ where
         }
     }
 
-    async fn expect_lazy<N>(&mut self, needle: N) -> Result<Captures, Error>
+    async fn expect_lazy<N>(
+        &mut self,
+        needle: N,
+        expect_timeout: Option<Duration>,
+        max_read_iterations: Option<usize>,
+    ) -> Result<Captures, Error>
     where
         N: Needle,
     {
-        let expect_timeout = self.expect_timeout;
         let expect_future = async {
             // We read by byte to make things as lazy as possible.
             //
@@ -344,12 +1144,16 @@ where
 
             let mut checked_length = 0;
             let mut eof = false;
+            let mut iterations = 0;
             loop {
                 let available = self.stream.buffer();
                 let is_buffer_checked = checked_length == available.len();
                 if is_buffer_checked {
                     let n = self.stream.fill().await?;
-                    eof = n == 0;
+                    eof = self.check_eof(n);
+                    if n > 0 {
+                        iterations += 1;
+                    }
                 }
 
                 // We intentinally not increase the counter
@@ -361,22 +1165,21 @@ where
                 }
 
                 let data = &available[..checked_length];
-                let found = Needle::check(&needle, data, eof)?;
-                if !found.is_empty() {
-                    let end_index = Captures::right_most_index(&found);
-                    let involved_bytes = data[..end_index].to_vec();
-                    self.stream.consume(end_index);
-                    return Ok(Captures::new(involved_bytes, found));
+                if let Some(found) = capture(&needle, data, eof)? {
+                    self.stream.consume(found.as_bytes().len());
+                    return Ok(found);
                 }
 
                 if eof {
                     return Err(Error::Eof);
                 }
+
+                check_read_iterations(iterations, max_read_iterations)?;
             }
         };
 
         if let Some(timeout) = expect_timeout {
-            let timeout_future = futures_timer::Delay::new(timeout);
+            let timeout_future = crate::runtime::sleep(timeout);
             futures_lite::future::or(expect_future, async {
                 timeout_future.await;
                 Err(Error::ExpectTimeout)
@@ -407,19 +1210,21 @@ where
 
     /// Check checks if a pattern is matched.
     /// Returns empty found structure if nothing found.
-    async fn check<E>(&mut self, needle: E) -> Result<Captures, Error>
+    ///
+    /// The matched bytes are consumed only if `consume` is set.
+    async fn check<E>(&mut self, needle: E, consume: bool) -> Result<Captures, Error>
     where
         E: Needle,
     {
         let eof = self.try_fill().await?;
 
         let buf = self.stream.buffer();
-        let found = needle.check(buf, eof)?;
-        if !found.is_empty() {
-            let end_index = Captures::right_most_index(&found);
-            let involved_bytes = buf[..end_index].to_vec();
-            self.stream.consume(end_index);
-            return Ok(Captures::new(involved_bytes, found));
+        if let Some(found) = capture(&needle, buf, eof)? {
+            if consume {
+                self.stream.consume(found.as_bytes().len());
+            }
+
+            return Ok(found);
         }
 
         if eof {
@@ -439,9 +1244,21 @@ where
         }
     }
 
+    /// Reads everything which is ready to be read without waiting.
+    async fn fill_available(&mut self) -> io::Result<()> {
+        while let Some(n) = futures_lite::future::poll_once(self.stream.fill()).await {
+            let n = n?;
+            if self.check_eof(n) || n == 0 {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
     async fn try_fill(&mut self) -> Result<bool, Error> {
         match futures_lite::future::poll_once(self.stream.fill()).await {
-            Some(Ok(n)) => Ok(n == 0),
+            Some(Ok(n)) => Ok(self.check_eof(n)),
             Some(Err(err)) => Err(err.into()),
             None => Ok(false),
         }
@@ -524,6 +1341,10 @@ struct BufferedStream<S> {
     stream: S,
     buffer: Vec<u8>,
     length: usize,
+    history: Option<OutputHistory>,
+    on_eof: EofHook,
+    #[cfg(feature = "render")]
+    screen: crate::render::Screen,
 }
 
 impl<S> BufferedStream<S> {
@@ -532,9 +1353,25 @@ impl<S> BufferedStream<S> {
             stream,
             buffer: Vec::new(),
             length: 0,
+            history: None,
+            on_eof: EofHook::default(),
+            #[cfg(feature = "render")]
+            screen: crate::render::Screen::default(),
         }
     }
 
+    /// Save a bytes which were read from the stream.
+    fn keep_read(&mut self, buf: &[u8]) {
+        if let Some(history) = &mut self.history {
+            history.push(buf);
+        }
+
+        #[cfg(feature = "render")]
+        self.screen.feed(buf);
+
+        self.keep(buf);
+    }
+
     fn keep(&mut self, buf: &[u8]) {
         self.buffer.extend(buf);
         self.length += buf.len();
@@ -551,9 +1388,11 @@ impl<S> BufferedStream<S> {
 
 impl<S: AsyncRead + Unpin> BufferedStream<S> {
     async fn fill(&mut self) -> io::Result<usize> {
+        // Bytes are put into the buffer in the same poll they're read,
+        // so dropping a pending call doesn't lose any of them.
         let mut buf = [0; 128];
         let n = self.stream.read(&mut buf).await?;
-        self.keep(&buf[..n]);
+        self.keep_read(&buf[..n]);
         Ok(n)
     }
 }
@@ -565,7 +1404,7 @@ impl<S: AsyncRead + Unpin> AsyncRead for BufferedStream<S> {
         buf: &mut [u8],
     ) -> Poll<io::Result<usize>> {
         let mut rem = ready!(self.as_mut().poll_fill_buf(cx))?;
-        let nread = std::io::Read::read(&mut rem, buf)?;
+        let nread = io::Read::read(&mut rem, buf)?;
         self.consume(nread);
         Poll::Ready(Ok(nread))
     }
@@ -576,7 +1415,7 @@ impl<S: AsyncRead + Unpin> AsyncRead for BufferedStream<S> {
         bufs: &mut [IoSliceMut<'_>],
     ) -> Poll<io::Result<usize>> {
         let mut rem = ready!(self.as_mut().poll_fill_buf(cx))?;
-        let nread = std::io::Read::read_vectored(&mut rem, bufs)?;
+        let nread = io::Read::read_vectored(&mut rem, bufs)?;
         self.consume(nread);
         Poll::Ready(Ok(nread))
     }
@@ -587,7 +1426,7 @@ impl<S: AsyncRead + Unpin> AsyncBufRead for BufferedStream<S> {
         if self.buffer.is_empty() {
             let mut buf = [0; 128];
             let n = ready!(Pin::new(&mut self.stream).poll_read(cx, &mut buf))?;
-            self.keep(&buf[..n]);
+            self.keep_read(&buf[..n]);
         }
 
         let buf = self.get_mut().buffer();
@@ -608,6 +1447,8 @@ mod tests {
 
     use super::*;
 
+    const TIMEOUT: Option<Duration> = Some(Duration::from_millis(10000));
+
     #[test]
     fn test_expect_lazy() {
         let buf = b"Hello World".to_vec();
@@ -615,7 +1456,7 @@ mod tests {
         let mut stream = Stream::new(cursor);
 
         futures_lite::future::block_on(async {
-            let found = stream.expect_lazy("World").await.unwrap();
+            let found = stream.expect_lazy("World", TIMEOUT, None).await.unwrap();
             assert_eq!(b"Hello ", found.before());
             assert_eq!(vec![b"World"], found.matches().collect::<Vec<_>>());
         });
@@ -628,7 +1469,7 @@ mod tests {
         let mut stream = Stream::new(cursor);
 
         futures_lite::future::block_on(async {
-            let found = stream.expect_lazy(Eof).await.unwrap();
+            let found = stream.expect_lazy(Eof, TIMEOUT, None).await.unwrap();
             assert_eq!(b"", found.before());
             assert_eq!(vec![b"Hello World"], found.matches().collect::<Vec<_>>());
         });
@@ -637,7 +1478,7 @@ mod tests {
         let mut stream = Stream::new(cursor);
 
         futures_lite::future::block_on(async {
-            let err = stream.expect_lazy("").await.unwrap_err();
+            let err = stream.expect_lazy("", TIMEOUT, None).await.unwrap_err();
             assert!(matches!(err, Error::Eof));
         });
     }
@@ -646,15 +1487,18 @@ mod tests {
     fn test_expect_lazy_timeout() {
         futures_lite::future::block_on(async {
             let mut stream = Stream::new(NoEofReader::default());
-            stream.set_expect_timeout(Some(Duration::from_millis(100)));
+            let timeout = Some(Duration::from_millis(100));
 
             stream.write_all(b"Hello").await.unwrap();
 
-            let err = stream.expect_lazy("Hello World").await.unwrap_err();
+            let err = stream
+                .expect_lazy("Hello World", timeout, None)
+                .await
+                .unwrap_err();
             assert!(matches!(err, Error::ExpectTimeout));
 
             stream.write_all(b" World").await.unwrap();
-            let found = stream.expect_lazy("World").await.unwrap();
+            let found = stream.expect_lazy("World", timeout, None).await.unwrap();
             assert_eq!(b"Hello ", found.before());
             assert_eq!(vec![b"World"], found.matches().collect::<Vec<_>>());
         });
@@ -667,7 +1511,7 @@ mod tests {
         let mut stream = Stream::new(cursor);
 
         futures_lite::future::block_on(async {
-            let found = stream.expect_gready("World").await.unwrap();
+            let found = stream.expect_gready("World", TIMEOUT, None).await.unwrap();
             assert_eq!(b"Hello ", found.before());
             assert_eq!(vec![b"World"], found.matches().collect::<Vec<_>>());
         });
@@ -680,7 +1524,7 @@ mod tests {
         let mut stream = Stream::new(cursor);
 
         futures_lite::future::block_on(async {
-            let found = stream.expect_gready(Eof).await.unwrap();
+            let found = stream.expect_gready(Eof, TIMEOUT, None).await.unwrap();
             assert_eq!(b"", found.before());
             assert_eq!(vec![b"Hello World"], found.matches().collect::<Vec<_>>());
         });
@@ -689,7 +1533,7 @@ mod tests {
         let mut stream = Stream::new(cursor);
 
         futures_lite::future::block_on(async {
-            let err = stream.expect_gready("").await.unwrap_err();
+            let err = stream.expect_gready("", TIMEOUT, None).await.unwrap_err();
             assert!(matches!(err, Error::Eof));
         });
     }
@@ -698,15 +1542,18 @@ mod tests {
     fn test_expect_gready_timeout() {
         futures_lite::future::block_on(async {
             let mut stream = Stream::new(NoEofReader::default());
-            stream.set_expect_timeout(Some(Duration::from_millis(100)));
+            let timeout = Some(Duration::from_millis(100));
 
             stream.write_all(b"Hello").await.unwrap();
 
-            let err = stream.expect_gready("Hello World").await.unwrap_err();
+            let err = stream
+                .expect_gready("Hello World", timeout, None)
+                .await
+                .unwrap_err();
             assert!(matches!(err, Error::ExpectTimeout));
 
             stream.write_all(b" World").await.unwrap();
-            let found = stream.expect_gready("World").await.unwrap();
+            let found = stream.expect_gready("World", timeout, None).await.unwrap();
             assert_eq!(b"Hello ", found.before());
             assert_eq!(vec![b"World"], found.matches().collect::<Vec<_>>());
         });
@@ -719,7 +1566,7 @@ mod tests {
         let mut stream = Stream::new(cursor);
 
         futures_lite::future::block_on(async {
-            let found = stream.check("World").await.unwrap();
+            let found = stream.check("World", true).await.unwrap();
             assert_eq!(b"Hello ", found.before());
             assert_eq!(vec![b"World"], found.matches().collect::<Vec<_>>());
         });
@@ -733,7 +1580,7 @@ mod tests {
             assert!(stream.is_matched("World").await.unwrap());
             assert!(!stream.is_matched("*****").await.unwrap());
 
-            let found = stream.check("World").await.unwrap();
+            let found = stream.check("World", true).await.unwrap();
             assert_eq!(b"Hello ", found.before());
             assert_eq!(vec![b"World"], found.matches().collect::<Vec<_>>());
         });
@@ -0,0 +1,45 @@
+//! Module contains a hook which is called once an EOF is read from a process.
+
+use std::{fmt, sync::Mutex};
+
+/// EofHook keeps a callback which is called the first time an EOF is confirmed.
+///
+/// The callback is kept behind a [`Mutex`] so it's not required to be `Sync`,
+/// it's never locked as it's accessed only via a mutable reference.
+#[derive(Default)]
+pub(crate) struct EofHook {
+    callback: Mutex<Option<Box<dyn FnOnce() + Send>>>,
+}
+
+impl EofHook {
+    pub(crate) fn set<F>(&mut self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        *self.callback_mut() = Some(Box::new(f));
+    }
+
+    /// Calls the callback unless it was already called.
+    pub(crate) fn call(&mut self) {
+        if let Some(f) = self.callback_mut().take() {
+            f();
+        }
+    }
+
+    fn callback_mut(&mut self) -> &mut Option<Box<dyn FnOnce() + Send>> {
+        self.callback
+            .get_mut()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl fmt::Debug for EofHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EofHook")
+            .field(
+                "is_set",
+                &matches!(self.callback.lock().as_deref(), Ok(Some(_))),
+            )
+            .finish()
+    }
+}
@@ -0,0 +1,61 @@
+//! Module contains an output history which keeps the last bytes read from a process.
+
+use std::collections::VecDeque;
+
+/// OutputHistory is a bounded buffer which keeps the most recent bytes.
+#[derive(Debug, Clone)]
+pub(crate) struct OutputHistory {
+    buf: VecDeque<u8>,
+    capacity: usize,
+}
+
+impl OutputHistory {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            buf: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Changes a capacity keeping the most recent bytes.
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.truncate();
+    }
+
+    pub(crate) fn push(&mut self, bytes: &[u8]) {
+        let bytes = &bytes[bytes.len().saturating_sub(self.capacity)..];
+        self.buf.extend(bytes);
+        self.truncate();
+    }
+
+    pub(crate) fn to_vec(&self) -> Vec<u8> {
+        self.buf.iter().copied().collect()
+    }
+
+    fn truncate(&mut self) {
+        let excess = self.buf.len().saturating_sub(self.capacity);
+        let _ = self.buf.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_history() {
+        let mut history = OutputHistory::new(5);
+        history.push(b"123");
+        assert_eq!(history.to_vec(), b"123");
+        history.push(b"456");
+        assert_eq!(history.to_vec(), b"23456");
+        history.push(b"Hello World");
+        assert_eq!(history.to_vec(), b"World");
+        history.set_capacity(2);
+        assert_eq!(history.to_vec(), b"ld");
+        history.set_capacity(0);
+        history.push(b"123");
+        assert_eq!(history.to_vec(), b"");
+    }
+}
@@ -0,0 +1,77 @@
+//! Module contains a watchdog which limits the total lifetime of a session.
+
+use std::{
+    fmt, io,
+    time::{Duration, Instant},
+};
+
+use crate::Error;
+
+/// Lifetime tracks how long a session exists and whether it's allowed to keep going.
+pub(crate) struct Lifetime<P> {
+    start: Instant,
+    limit: Option<Duration>,
+    kill: Option<fn(&mut P) -> io::Result<()>>,
+}
+
+impl<P> Lifetime<P> {
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            limit: None,
+            kill: None,
+        }
+    }
+
+    pub(crate) fn set_limit(&mut self, limit: Option<Duration>) {
+        self.limit = limit;
+    }
+
+    pub(crate) fn set_kill(&mut self, kill: Option<fn(&mut P) -> io::Result<()>>) {
+        self.kill = kill;
+    }
+
+    /// Returns [`Error::LifetimeExceeded`] once the limit is exceeded.
+    ///
+    /// The process is killed the first time it happens, if it was asked for.
+    pub(crate) fn check(&mut self, process: &mut P) -> Result<(), Error> {
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        if self.start.elapsed() <= limit {
+            return Ok(());
+        }
+
+        Err(self.exceed(process))
+    }
+
+    /// Returns a time left until the limit is exceeded,
+    /// it's `None` if there's no limit.
+    #[cfg(feature = "async")]
+    pub(crate) fn remaining(&self) -> Option<Duration> {
+        self.limit
+            .map(|limit| limit.saturating_sub(self.start.elapsed()))
+    }
+
+    /// Marks the lifetime as exceeded, killing the process if it was asked for.
+    pub(crate) fn exceed(&mut self, process: &mut P) -> Error {
+        // The process is killed only once, as its pid may be reused after it's gone.
+        if let Some(kill) = self.kill.take() {
+            let _ = kill(process);
+        }
+
+        Error::LifetimeExceeded
+    }
+}
+
+impl<P> fmt::Debug for Lifetime<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lifetime")
+            .field("start", &self.start)
+            .field("limit", &self.limit)
+            .field("kill", &self.kill.is_some())
+            .finish()
+    }
+}
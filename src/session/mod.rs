@@ -20,78 +20,1828 @@ mod async_session;
 #[cfg(not(feature = "async"))]
 mod sync_session;
 
-use std::{io::Write, process::Command};
+mod eof_hook;
+mod history;
+mod lifetime;
+mod timeout_guard;
 
-use crate::{interact::InteractSession, process::Process, stream::log::LogStream, Error};
+use std::{
+    io::Write,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    interact::InteractSession,
+    process::Process,
+    stream::log::{LogFormat, LogStream},
+    Error,
+};
+
+pub(crate) use timeout_guard::{ExpectTimeout, TimeoutGuard};
+
+#[cfg(not(feature = "async"))]
+use std::io::Read;
+
+#[cfg(feature = "async")]
+use crate::process::IntoAsyncStream;
+
+#[cfg(unix)]
+type OsProc = crate::process::unix::UnixProcess;
+#[cfg(windows)]
+type OsProc = crate::process::windows::WinProcess;
+
+#[cfg(all(unix, not(feature = "async")))]
+type OsProcStream = crate::process::unix::PtyStream;
+#[cfg(all(unix, feature = "async"))]
+type OsProcStream = crate::process::unix::AsyncPtyStream;
+#[cfg(all(windows, not(feature = "async")))]
+type OsProcStream = crate::process::windows::ProcessStream;
+#[cfg(all(windows, feature = "async"))]
+type OsProcStream = crate::process::windows::AsyncProcessStream;
+
+#[cfg(all(unix, not(feature = "async")))]
+type PipeProcStream = crate::process::pipe::PipeStream;
+#[cfg(all(unix, feature = "async"))]
+type PipeProcStream = crate::process::pipe::AsyncPipeStream;
+
+/// A type alias for OS process which can run a [`Session`] and a default one.
+pub type OsProcess = OsProc;
+/// A type alias for OS process stream which is a default one for [`Session`].
+pub type OsStream = OsProcStream;
+/// A type alias for OS session.
+pub type OsSession = Session<OsProc, OsStream>;
+
+#[cfg(feature = "async")]
+pub use async_session::Session;
+
+#[cfg(not(feature = "async"))]
+pub use sync_session::Session;
+
+impl Session<OsProc, OsProcStream> {
+    /// Spawns a session on a platform process.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::process::Command;
+    /// use expectrl::Session;
+    ///
+    /// let p = Session::spawn(Command::new("cat"));
+    /// ```
+    pub fn spawn(command: Command) -> Result<Self, Error> {
+        crate::trace::spawn(&command.get_program().to_owned(), || {
+            let process = OsProcess::spawn_command(command)?;
+            Self::from_process(process)
+        })
+    }
+
+    /// Spawns a session on a platform process,
+    /// letting a closure configure a [`Command`] built from a `program` before it's spawned.
+    ///
+    /// It can be used to set arguments, environment variables, a working directory
+    /// or any other [`Command`] setting.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use expectrl::Session;
+    ///
+    /// let p = Session::spawn_with("ls", |cmd| {
+    ///     cmd.arg("-l").env("LC_ALL", "C").current_dir("/tmp");
+    /// });
+    /// ```
+    pub fn spawn_with<F>(program: &str, configure: F) -> Result<Self, Error>
+    where
+        F: FnOnce(&mut Command),
+    {
+        let mut command = Command::new(program);
+        configure(&mut command);
+
+        Self::spawn(command)
+    }
+
+    /// Spawns a session on a platform process.
+    /// Using a string commandline.
+    pub(crate) fn spawn_cmd(cmd: &str) -> Result<Self, Error> {
+        crate::trace::spawn(&cmd, || {
+            let process = OsProcess::spawn(cmd)?;
+            Self::from_process(process)
+        })
+    }
+
+    /// Creates a session over a stream of a spawned process.
+    fn from_process(mut process: OsProc) -> Result<Self, Error> {
+        let stream = process.open_stream()?;
+
+        #[cfg(feature = "async")]
+        let stream = stream.into_async_stream()?;
+
+        let session = Self::new(process, stream)?;
+
+        Ok(session)
+    }
+}
+
+/// A builder of an [`OsSession`] which configures a session before it's returned.
+///
+/// Terminal settings (echo and a window size) are applied before the program is executed,
+/// so the program sees a configured terminal at startup.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use expectrl::session::SessionBuilder;
+///
+/// let p = SessionBuilder::new("htop")
+///     .timeout(Some(Duration::from_secs(5)))
+///     .lazy(true)
+///     .echo(false)
+///     .window_size(120, 40)
+///     .spawn()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SessionBuilder {
+    cmd: String,
+    timeout: Option<Option<Duration>>,
+    lazy: bool,
+    #[cfg(unix)]
+    echo: Option<bool>,
+    #[cfg(unix)]
+    window_size: Option<(u16, u16)>,
+}
+
+impl SessionBuilder {
+    /// Creates a builder of a commandline, which is parsed the same way as by [`crate::spawn`].
+    pub fn new<S: AsRef<str>>(cmd: S) -> Self {
+        Self {
+            cmd: cmd.as_ref().to_owned(),
+            timeout: None,
+            lazy: false,
+            #[cfg(unix)]
+            echo: None,
+            #[cfg(unix)]
+            window_size: None,
+        }
+    }
+
+    /// Sets an expect timeout, see [`Session::set_expect_timeout`].
+    ///
+    /// Default is 10 seconds.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets whether a lazy expect algorithm is used, see [`Session::set_expect_lazy`].
+    ///
+    /// Default is `false`.
+    pub fn lazy(mut self, on: bool) -> Self {
+        self.lazy = on;
+        self
+    }
+
+    /// Sets whether the terminal echoes input.
+    ///
+    /// By default the terminal setting is left as it is, which is usually on.
+    #[cfg(unix)]
+    pub fn echo(mut self, on: bool) -> Self {
+        self.echo = Some(on);
+        self
+    }
+
+    /// Sets a size of the terminal in columns and rows, see [`Session::set_window_size`].
+    #[cfg(unix)]
+    pub fn window_size(mut self, cols: u16, rows: u16) -> Self {
+        self.window_size = Some((cols, rows));
+        self
+    }
+
+    /// Spawns a session.
+    pub fn spawn(self) -> Result<OsSession, Error> {
+        #[cfg(unix)]
+        let mut session = crate::trace::spawn(&self.cmd, || {
+            use crate::process::unix::{parse_command, UnixProcess, UnixProcessOptions};
+
+            let mut options = UnixProcessOptions::new();
+            if let Some(on) = self.echo {
+                options = options.echo(on);
+            }
+
+            if let Some((cols, rows)) = self.window_size {
+                options = options.window_size(cols, rows);
+            }
+
+            let process = UnixProcess::spawn_with_options(parse_command(&self.cmd)?, options)?;
+            Session::from_process(process)
+        })?;
+
+        #[cfg(windows)]
+        let mut session = Session::spawn_cmd(&self.cmd)?;
+
+        if let Some(timeout) = self.timeout {
+            session.set_expect_timeout(timeout);
+        }
+
+        session.set_expect_lazy(self.lazy);
+
+        Ok(session)
+    }
+}
+
+#[cfg(unix)]
+impl Session<crate::process::pipe::PipeProcess, PipeProcStream> {
+    /// Spawns a session on a process which stdin and stdout are connected to pipes instead of a pty.
+    ///
+    /// The IO is byte exact as there's no terminal to transform it (e.g. `\n` to `\r\n`),
+    /// and it's lighter than a pty.
+    /// But features which rely on a tty, like echo settings or control characters, don't apply,
+    /// and programs may behave differently (e.g. buffer their output) when not connected to a terminal.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(feature = "async", doc = "```ignore")]
+    #[cfg_attr(not(feature = "async"), doc = "```no_run")]
+    /// use std::process::Command;
+    /// use expectrl::{Expect, Session};
+    ///
+    /// let mut p = Session::spawn_piped(Command::new("cat")).unwrap();
+    /// p.send_line("Hello World").unwrap();
+    /// p.expect("Hello World\n").unwrap();
+    /// ```
+    pub fn spawn_piped(command: Command) -> Result<Self, Error> {
+        crate::trace::spawn(&command.get_program().to_owned(), || {
+            let mut process = crate::process::pipe::PipeProcess::spawn_command(command)?;
+            let stream = process.open_stream()?;
+
+            #[cfg(feature = "async")]
+            let stream = stream.into_async_stream()?;
+
+            let session = Self::new(process, stream)?;
+
+            Ok(session)
+        })
+    }
+}
+
+impl<S> Session<crate::process::NoProcess, S> {
+    /// Creates a session over a generic IO stream, e.g. a TCP connection or a serial port.
+    ///
+    /// There's no process behind such a session,
+    /// so it's always considered to be alive.
+    ///
+    /// To use [`Expect`] methods the stream must implement [`NonBlocking`],
+    /// it's implemented for [`TcpStream`] and for [`UnixStream`] on unix.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::net::TcpStream;
+    /// use expectrl::{Expect, Session};
+    ///
+    /// let stream = TcpStream::connect("127.0.0.1:25").unwrap();
+    /// let mut p = Session::over_stream(stream).unwrap();
+    /// p.expect("220").unwrap();
+    /// p.send_line("QUIT").unwrap();
+    /// ```
+    ///
+    /// [`Expect`]: crate::Expect
+    /// [`NonBlocking`]: crate::process::NonBlocking
+    /// [`TcpStream`]: std::net::TcpStream
+    /// [`UnixStream`]: std::os::unix::net::UnixStream
+    #[cfg(not(feature = "async"))]
+    pub fn over_stream(stream: S) -> std::io::Result<Self>
+    where
+        S: Read + Write,
+    {
+        Self::new(crate::process::NoProcess, stream)
+    }
+
+    /// Creates a session over a generic IO stream, e.g. a TCP connection or a serial port.
+    ///
+    /// There's no process behind such a session,
+    /// so it's always considered to be alive.
+    ///
+    /// The stream must implement [`AsyncRead`] and [`AsyncWrite`],
+    /// e.g. `async_io::Async<TcpStream>`.
+    /// With `tokio` feature tokio streams can be used via `stream::tokio::TokioStream`.
+    ///
+    /// [`AsyncRead`]: futures_lite::AsyncRead
+    /// [`AsyncWrite`]: futures_lite::AsyncWrite
+    #[cfg(feature = "async")]
+    pub fn over_stream(stream: S) -> std::io::Result<Self>
+    where
+        S: futures_lite::AsyncRead + futures_lite::AsyncWrite + Unpin,
+    {
+        Self::new(crate::process::NoProcess, stream)
+    }
+}
+
+#[cfg(unix)]
+impl Session<crate::process::NoProcess, OsProcStream> {
+    /// Creates a session over an existing file descriptor without spawning a process,
+    /// e.g. a pty master from a login session or a socket.
+    ///
+    /// There's no process to wait for,
+    /// so the session is always considered to be alive.
+    ///
+    /// # Safety
+    ///
+    /// The descriptor must be open and must not be owned by anything else,
+    /// it's closed once the session is dropped.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(feature = "async", doc = "```ignore")]
+    #[cfg_attr(not(feature = "async"), doc = "```no_run")]
+    /// use std::{os::unix::{io::IntoRawFd, net::UnixStream}};
+    /// use expectrl::{Expect, Session};
+    ///
+    /// let (stream, _) = UnixStream::pair().unwrap();
+    /// let mut p = unsafe { Session::from_raw_fd(stream.into_raw_fd()) }.unwrap();
+    /// p.send_line("Hello World").unwrap();
+    /// ```
+    pub unsafe fn from_raw_fd(fd: std::os::unix::io::RawFd) -> std::io::Result<Self> {
+        use std::os::unix::io::FromRawFd;
+
+        let stream = crate::process::unix::PtyStream::from_raw_fd(fd);
+
+        #[cfg(feature = "async")]
+        let stream = stream.into_async_stream()?;
+
+        Self::new(crate::process::NoProcess, stream)
+    }
+}
+
+#[cfg(unix)]
+impl<S> Session<crate::process::unix::UnixProcess, S> {
+    /// Verifies whether a terminal flag of the process is set.
+    ///
+    /// See [`UnixProcess::get_termios_flag`].
+    ///
+    /// [`UnixProcess::get_termios_flag`]: crate::process::unix::UnixProcess::get_termios_flag
+    pub fn get_termios_flag(&self, flag: crate::process::unix::TermiosFlag) -> Result<bool, Error> {
+        let is_set = self.get_process().get_termios_flag(flag)?;
+        Ok(is_set)
+    }
+
+    /// Sets or unsets a terminal flag of the process.
+    ///
+    /// Supported flags are listed in [`TermiosFlag`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use expectrl::{spawn, process::unix::TermiosFlag};
+    ///
+    /// let mut p = spawn("cat").unwrap();
+    /// // `cat` will print `\n` instead of `\r\n`.
+    /// p.set_termios_flag(TermiosFlag::OutputProcessing, false).unwrap();
+    /// ```
+    ///
+    /// [`TermiosFlag`]: crate::process::unix::TermiosFlag
+    pub fn set_termios_flag(
+        &mut self,
+        flag: crate::process::unix::TermiosFlag,
+        on: bool,
+    ) -> Result<(), Error> {
+        self.get_process_mut().set_termios_flag(flag, on)?;
+        Ok(())
+    }
+
+    /// Returns an EOF character the terminal of the process uses, usually `^D`.
+    ///
+    /// It's the byte which is expected to be sent to signal an EOF in canonical mode.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let p = expectrl::spawn("cat").unwrap();
+    /// assert_eq!(p.eof_char().unwrap(), 0x04);
+    /// ```
+    pub fn eof_char(&self) -> Result<u8, Error> {
+        let c = self.get_process().eof_char()?;
+        Ok(c)
+    }
+
+    /// Returns an interrupt character the terminal of the process uses, usually `^C`.
+    ///
+    /// It's the byte which makes the terminal send `SIGINT` to the process.
+    pub fn intr_char(&self) -> Result<u8, Error> {
+        let c = self.get_process().intr_char()?;
+        Ok(c)
+    }
+
+    /// Returns a path of the terminal device the process uses, e.g. `/dev/pts/3`.
+    ///
+    /// It's the child's controlling terminal,
+    /// so an external tool (e.g. a logger) can be attached to the same terminal.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let p = expectrl::spawn("cat").unwrap();
+    /// println!("{}", p.tty_name().unwrap());
+    /// ```
+    pub fn tty_name(&self) -> std::io::Result<String> {
+        self.get_process().tty_name()
+    }
+
+    /// Sets a size of the terminal in columns and rows.
+    ///
+    /// The process is notified with `SIGWINCH`,
+    /// so programs which render according to the terminal size (e.g. `vim`, `htop`) redraw.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut p = expectrl::spawn("htop").unwrap();
+    /// p.set_window_size(120, 40).unwrap();
+    /// ```
+    pub fn set_window_size(&mut self, cols: u16, rows: u16) -> Result<(), Error> {
+        self.get_process_mut()
+            .set_window_size(cols, rows)
+            .map_err(std::io::Error::from)?;
+        Ok(())
+    }
+
+    /// Returns a size of the terminal as a pair of columns and rows.
+    pub fn get_window_size(&self) -> Result<(u16, u16), Error> {
+        let size = self
+            .get_process()
+            .get_window_size()
+            .map_err(std::io::Error::from)?;
+        Ok(size)
+    }
+
+    /// Sends a signal to the process.
+    ///
+    /// Unlike sending a control code (e.g. `^C`) it doesn't depend on the terminal settings,
+    /// and any signal can be sent, e.g. `SIGTERM`, `SIGHUP` or `SIGUSR1`.
+    /// Only the process itself gets the signal, not its process group.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use expectrl::process::unix::Signal;
+    ///
+    /// let p = expectrl::spawn("my-daemon").unwrap();
+    /// p.send_signal(Signal::SIGHUP).unwrap();
+    /// ```
+    pub fn send_signal(&self, signal: crate::process::unix::Signal) -> Result<(), Error> {
+        nix::sys::signal::kill(self.get_process().pid(), signal).map_err(std::io::Error::from)?;
+        Ok(())
+    }
+
+    /// Sets whether the process is killed with `SIGKILL` once the session lifetime is exceeded.
+    ///
+    /// See [`Session::set_lifetime`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut p = expectrl::spawn("my-test-server").unwrap();
+    /// p.set_lifetime(Some(Duration::from_secs(60)));
+    /// p.set_lifetime_kills_process(true);
+    /// ```
+    pub fn set_lifetime_kills_process(&mut self, on: bool) {
+        fn kill(process: &mut crate::process::unix::UnixProcess) -> std::io::Result<()> {
+            process.kill(crate::process::unix::Signal::SIGKILL)?;
+            Ok(())
+        }
+
+        let kill: fn(&mut crate::process::unix::UnixProcess) -> std::io::Result<()> = kill;
+        self.set_lifetime_kill(on.then_some(kill));
+    }
+
+    /// Returns a process group id of the foreground job of the terminal.
+    ///
+    /// When a shell is automated and it runs another program,
+    /// it's the group of that program, so a signal sent to the group reaches it
+    /// rather than the shell.
+    ///
+    /// It can't be changed from the session, as the system allows it only from within the terminal,
+    /// see [`UnixProcess::foreground_pgrp`].
+    ///
+    /// [`UnixProcess::foreground_pgrp`]: crate::process::unix::UnixProcess::foreground_pgrp
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(feature = "async", doc = "```ignore")]
+    #[cfg_attr(not(feature = "async"), doc = "```no_run")]
+    /// use expectrl::{process::unix::Signal, Expect};
+    /// use nix::{sys::signal::killpg, unistd::Pid};
+    ///
+    /// let mut p = expectrl::spawn("sh").unwrap();
+    /// p.send_line("sleep 100").unwrap();
+    ///
+    /// let pgrp = p.foreground_pgrp().unwrap();
+    /// killpg(Pid::from_raw(pgrp), Signal::SIGINT).unwrap();
+    /// ```
+    pub fn foreground_pgrp(&self) -> std::io::Result<i32> {
+        self.get_process().foreground_pgrp()
+    }
+
+    /// Expects an EOF and then waits for the process to exit.
+    ///
+    /// It returns the remaining output along with a [`WaitStatus`],
+    /// so it's possible to tell a clean exit from a process killed by a signal.
+    /// The child is reaped, so the status is not available afterwards.
+    ///
+    /// Be aware that it blocks until the process exits,
+    /// even if it closes its output earlier.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use expectrl::{spawn, process::unix::WaitStatus};
+    ///
+    /// let mut p = spawn("echo Hello World").unwrap();
+    /// let (found, status) = p.expect_eof_status().unwrap();
+    /// assert_eq!(found.as_bytes(), b"Hello World\r\n");
+    /// assert!(matches!(status, WaitStatus::Exited(_, 0)));
+    /// ```
+    ///
+    /// [`WaitStatus`]: crate::process::unix::WaitStatus
+    #[cfg(not(feature = "async"))]
+    pub fn expect_eof_status(
+        &mut self,
+    ) -> Result<(crate::Captures, crate::process::unix::WaitStatus), Error>
+    where
+        Self: crate::Expect,
+    {
+        let found = crate::Expect::expect(self, crate::Eof)?;
+        let status = self.get_process().wait().map_err(std::io::Error::from)?;
+
+        Ok((found, status))
+    }
+
+    /// Expects an EOF and then waits for the process to exit.
+    ///
+    /// It returns the remaining output along with a [`WaitStatus`],
+    /// so it's possible to tell a clean exit from a process killed by a signal.
+    /// The child is reaped, so the status is not available afterwards.
+    ///
+    /// Be aware that it waits until the process exits,
+    /// even if it closes its output earlier.
+    /// The status is polled, so an executor is not blocked meanwhile.
+    ///
+    /// [`WaitStatus`]: crate::process::unix::WaitStatus
+    #[cfg(feature = "async")]
+    pub async fn expect_eof_status(
+        &mut self,
+    ) -> Result<(crate::Captures, crate::process::unix::WaitStatus), Error>
+    where
+        Self: crate::AsyncExpect,
+    {
+        use crate::process::unix::WaitStatus;
+
+        let found = crate::AsyncExpect::expect(self, crate::Eof).await?;
+        loop {
+            let status = self.get_process().status().map_err(std::io::Error::from)?;
+            if status != WaitStatus::StillAlive {
+                return Ok((found, status));
+            }
+
+            crate::runtime::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Waits for the process to exit and verifies its exit code.
+    ///
+    /// It returns [`Error::ExpectTimeout`] if the process didn't exit in time,
+    /// and an error with the actual status if the code differs or the process was killed by a signal.
+    ///
+    /// The output of the process is not read while waiting,
+    /// so a process which writes a lot may block until it's read.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut p = expectrl::spawn("false").unwrap();
+    /// p.wait_for_exit_code(1, Duration::from_secs(1)).unwrap();
+    /// ```
+    pub fn wait_for_exit_code(&mut self, expected: i32, timeout: Duration) -> Result<(), Error> {
+        use crate::process::unix::WaitStatus;
+
+        let start = Instant::now();
+        loop {
+            let status = self.get_process().status().map_err(std::io::Error::from)?;
+            match status {
+                WaitStatus::Exited(_, code) if code == expected => return Ok(()),
+                WaitStatus::Exited(_, code) => return Err(exit_code_error(expected, code)),
+                WaitStatus::Signaled(..) => {
+                    return Err(Error::Other {
+                        message: format!("expected process to exit with code {}", expected),
+                        err: format!("it was terminated; {:?}", status),
+                    })
+                }
+                _ => {}
+            }
+
+            if start.elapsed() > timeout {
+                return Err(Error::ExpectTimeout);
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+#[cfg(windows)]
+impl<S> Session<crate::process::windows::WinProcess, S> {
+    /// Waits for the process to exit and verifies its exit code.
+    ///
+    /// It returns [`Error::ExpectTimeout`] if the process didn't exit in time,
+    /// and an error with the actual code if it differs.
+    ///
+    /// The output of the process is not read while waiting,
+    /// so a process which writes a lot may block until it's read.
+    pub fn wait_for_exit_code(&mut self, expected: i32, timeout: Duration) -> Result<(), Error> {
+        let start = Instant::now();
+        while self.get_process().is_alive() {
+            if start.elapsed() > timeout {
+                return Err(Error::ExpectTimeout);
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let code = self.get_process().wait(None).map_err(|err| Error::Other {
+            message: "failed to get an exit code".to_string(),
+            err: err.to_string(),
+        })?;
+
+        // Windows exit codes are unsigned, we compare them bit by bit.
+        let code = code as i32;
+        if code != expected {
+            return Err(exit_code_error(expected, code));
+        }
+
+        Ok(())
+    }
+
+    /// Sets whether the process is terminated once the session lifetime is exceeded.
+    ///
+    /// See [`Session::set_lifetime`].
+    pub fn set_lifetime_kills_process(&mut self, on: bool) {
+        fn kill(process: &mut crate::process::windows::WinProcess) -> std::io::Result<()> {
+            process
+                .exit(1)
+                .map_err(crate::error::to_io_error("failed to terminate a process"))
+        }
+
+        let kill: fn(&mut crate::process::windows::WinProcess) -> std::io::Result<()> = kill;
+        self.set_lifetime_kill(on.then_some(kill));
+    }
+}
+
+impl<P, S> Session<P, S>
+where
+    P: crate::process::Healthcheck + crate::process::Termios,
+{
+    /// Returns a human readable snapshot of the session state.
+    ///
+    /// It includes the liveness of the process, its echo setting,
+    /// expect settings and bytes which were read but not consumed yet (as a string and as hex).
+    ///
+    /// The format is meant for people, not for parsing,
+    /// but the listed fields are kept across versions.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let p = expectrl::spawn("cat").unwrap();
+    /// println!("{}", p.dump());
+    /// ```
+    pub fn dump(&self) -> String {
+        use std::fmt::Write;
+
+        fn or_error<T: std::fmt::Display>(result: std::io::Result<T>) -> String {
+            match result {
+                Ok(value) => value.to_string(),
+                Err(err) => format!("unknown ({})", err),
+            }
+        }
+
+        let buffer = self.export_buffer();
+        let hex = buffer
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let timeout = match self.get_expect_timeout() {
+            Some(timeout) => format!("{:?}", timeout),
+            None => String::from("none"),
+        };
+        let mode = match self.is_expect_lazy() {
+            true => "lazy",
+            false => "greedy",
+        };
+
+        let mut dump = String::new();
+        let _ = writeln!(dump, "alive: {}", or_error(self.get_process().is_alive()));
+        let _ = writeln!(dump, "echo: {}", or_error(self.get_process().is_echo()));
+        let _ = writeln!(dump, "expect timeout: {}", timeout);
+        let _ = writeln!(dump, "expect mode: {}", mode);
+        let _ = writeln!(
+            dump,
+            "buffer ({} bytes): {:?}",
+            buffer.len(),
+            String::from_utf8_lossy(&buffer)
+        );
+        let _ = writeln!(dump, "buffer hex: {}", hex);
+
+        dump
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<P, S> Session<P, S>
+where
+    Self: crate::Expect,
+{
+    /// Waits until any output arrives and returns it.
+    ///
+    /// It returns all bytes which were available at the moment,
+    /// or an error in case of a timeout or an EOF.
+    /// The timeout is used only for this call, the expect timeout is left intact.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut p = expectrl::spawn("echo Hello World").unwrap();
+    /// let output = p.expect_any_output(Duration::from_secs(1)).unwrap();
+    /// assert!(!output.is_empty());
+    /// ```
+    pub fn expect_any_output(&mut self, timeout: Duration) -> Result<Vec<u8>, Error> {
+        let mut session = TimeoutGuard::new(self, Some(timeout));
+        let found = crate::Expect::expect(&mut *session, crate::needle::NonEmpty)?;
+
+        Ok(found.as_bytes().to_vec())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, S> Session<P, S>
+where
+    Self: crate::AsyncExpect,
+{
+    /// Waits until any output arrives and returns it.
+    ///
+    /// It returns all bytes which were available at the moment,
+    /// or an error in case of a timeout or an EOF.
+    /// The timeout is used only for this call, the expect timeout is left intact.
+    pub async fn expect_any_output(&mut self, timeout: Duration) -> Result<Vec<u8>, Error> {
+        let mut session = TimeoutGuard::new(self, Some(timeout));
+        let found = crate::AsyncExpect::expect(&mut *session, crate::needle::NonEmpty).await?;
+
+        Ok(found.as_bytes().to_vec())
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<P, S> Session<P, S>
+where
+    Self: crate::Expect,
+{
+    /// Sends a command as a line and expects a needle,
+    /// re-sending the command on a timeout.
+    ///
+    /// The command is sent at most `retries + 1` times.
+    /// The timeout is used for each attempt, the expect timeout is left intact.
+    /// Errors other than [`Error::ExpectTimeout`] are returned right away.
+    ///
+    /// It's handy for devices which may drop the first command sent to them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut p = expectrl::spawn("cat").unwrap();
+    /// p.send_expect_retry("ping", "ping", 3, Duration::from_secs(1)).unwrap();
+    /// ```
+    pub fn send_expect_retry<N>(
+        &mut self,
+        cmd: &str,
+        needle: N,
+        retries: usize,
+        timeout: Duration,
+    ) -> Result<crate::Captures, Error>
+    where
+        N: crate::Needle,
+    {
+        let mut session = TimeoutGuard::new(self, Some(timeout));
+
+        let mut result = Err(Error::ExpectTimeout);
+        for _ in 0..=retries {
+            result = crate::Expect::send_line(&mut *session, cmd)
+                .and_then(|_| crate::Expect::expect(&mut *session, &needle));
+            if !matches!(result, Err(Error::ExpectTimeout)) {
+                break;
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, S> Session<P, S>
+where
+    Self: crate::AsyncExpect,
+{
+    /// Sends a command as a line and expects a needle,
+    /// re-sending the command on a timeout.
+    ///
+    /// The command is sent at most `retries + 1` times.
+    /// The timeout is used for each attempt, the expect timeout is left intact.
+    /// Errors other than [`Error::ExpectTimeout`] are returned right away.
+    pub async fn send_expect_retry<N>(
+        &mut self,
+        cmd: &str,
+        needle: N,
+        retries: usize,
+        timeout: Duration,
+    ) -> Result<crate::Captures, Error>
+    where
+        N: crate::Needle,
+    {
+        let mut session = TimeoutGuard::new(self, Some(timeout));
+
+        let mut result = Err(Error::ExpectTimeout);
+        for _ in 0..=retries {
+            result = match crate::AsyncExpect::send_line(&mut *session, cmd).await {
+                Ok(()) => crate::AsyncExpect::expect(&mut *session, &needle).await,
+                Err(err) => Err(err),
+            };
+            if !matches!(result, Err(Error::ExpectTimeout)) {
+                break;
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<P, S> Session<P, S>
+where
+    Self: crate::Expect,
+{
+    /// Responds to prompts by a set of rules until a final needle is matched.
+    ///
+    /// It waits for any of the rules needles, sends its response as a line,
+    /// and repeats it until the `until` needle is matched.
+    /// The `until` needle takes priority over the rules,
+    /// and the rules are checked in order they were provided.
+    ///
+    /// The timeout limits the whole call, the expect timeout is left intact.
+    /// It returns [`Error::ExpectTimeout`] if the `until` needle was not matched in time
+    /// and [`Error::Eof`] if the process exited before it.
+    ///
+    /// It's handy when the exact sequence of prompts isn't known in advance.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use expectrl::{Needle, NoCase};
+    ///
+    /// let mut p = expectrl::spawn("apt install vim").unwrap();
+    /// let rules: Vec<(Box<dyn Needle>, String)> = vec![
+    ///     (Box::new("[Y/n]"), String::from("Y")),
+    ///     (Box::new(NoCase("restart services")), String::from("yes")),
+    /// ];
+    /// p.autorespond(rules, "Setting up vim", Duration::from_secs(300)).unwrap();
+    /// ```
+    pub fn autorespond<N>(
+        &mut self,
+        rules: Vec<(Box<dyn crate::Needle>, String)>,
+        until: N,
+        timeout: Duration,
+    ) -> Result<(), Error>
+    where
+        N: crate::Needle,
+    {
+        let mut needles: Vec<Box<dyn crate::Needle + '_>> = vec![Box::new(&until)];
+        for (needle, _) in &rules {
+            needles.push(Box::new(needle));
+        }
+        let needle = crate::Any(needles);
+
+        let mut session = TimeoutGuard::new(self, Some(timeout));
+        let start = Instant::now();
+        loop {
+            let left = match timeout.checked_sub(start.elapsed()) {
+                Some(left) => left,
+                None => return Err(Error::ExpectTimeout),
+            };
+
+            session.set_expect_timeout(Some(left));
+            let found = crate::Expect::expect(&mut *session, &needle)?;
+
+            match found.needle_index() {
+                Some(0) | None => return Ok(()),
+                Some(i) => crate::Expect::send_line(&mut *session, &rules[i - 1].1)?,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, S> Session<P, S>
+where
+    Self: crate::AsyncExpect,
+{
+    /// Responds to prompts by a set of rules until a final needle is matched.
+    ///
+    /// It waits for any of the rules needles, sends its response as a line,
+    /// and repeats it until the `until` needle is matched.
+    /// The `until` needle takes priority over the rules,
+    /// and the rules are checked in order they were provided.
+    ///
+    /// The timeout limits the whole call, the expect timeout is left intact.
+    /// It returns [`Error::ExpectTimeout`] if the `until` needle was not matched in time
+    /// and [`Error::Eof`] if the process exited before it.
+    pub async fn autorespond<N>(
+        &mut self,
+        rules: Vec<(Box<dyn crate::Needle>, String)>,
+        until: N,
+        timeout: Duration,
+    ) -> Result<(), Error>
+    where
+        N: crate::Needle,
+    {
+        let mut needles: Vec<Box<dyn crate::Needle + '_>> = vec![Box::new(&until)];
+        for (needle, _) in &rules {
+            needles.push(Box::new(needle));
+        }
+        let needle = crate::Any(needles);
+
+        let mut session = TimeoutGuard::new(self, Some(timeout));
+        let start = Instant::now();
+        loop {
+            let left = match timeout.checked_sub(start.elapsed()) {
+                Some(left) => left,
+                None => return Err(Error::ExpectTimeout),
+            };
+
+            session.set_expect_timeout(Some(left));
+            let found = crate::AsyncExpect::expect(&mut *session, &needle).await?;
+
+            match found.needle_index() {
+                Some(0) | None => return Ok(()),
+                Some(i) => crate::AsyncExpect::send_line(&mut *session, &rules[i - 1].1).await?,
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<P, S> Session<P, S>
+where
+    Self: crate::Expect,
+{
+    /// Expects a needle calling a callback periodically while waiting.
+    ///
+    /// The callback is called at most once per `interval`
+    /// with the output which was read so far but not matched yet.
+    /// It's handy for showing a progress of long operations.
+    ///
+    /// The needle is matched the same way as by [`Expect::expect`](crate::Expect::expect),
+    /// and the expect timeout limits the whole call.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut p = expectrl::spawn("make").unwrap();
+    /// p.expect_with_progress("Finished", Duration::from_secs(5), |buf| {
+    ///     println!("still waiting, received {} bytes...", buf.len());
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn expect_with_progress<N, F>(
+        &mut self,
+        needle: N,
+        interval: Duration,
+        mut f: F,
+    ) -> Result<crate::Captures, Error>
+    where
+        N: crate::Needle,
+        F: FnMut(&[u8]),
+    {
+        let expect_timeout = self.get_expect_timeout();
+        let discards_buffer = self.is_timeout_discards_buffer();
+        let mut session = TimeoutGuard::new(self, expect_timeout);
+        let start = Instant::now();
+        let result = loop {
+            let left = expect_timeout.map(|timeout| timeout.saturating_sub(start.elapsed()));
+            let is_last = matches!(left, Some(left) if left <= interval);
+            let step = left.map_or(interval, |left| left.min(interval));
+
+            // only the final timeout may discard the buffer
+            session.set_timeout_discards_buffer(discards_buffer && is_last);
+            session.set_expect_timeout(Some(step));
+            match crate::Expect::expect(&mut *session, &needle) {
+                Err(Error::ExpectTimeout) if !is_last => f(session.get_available()),
+                result => break result,
+            }
+        };
+        session.set_timeout_discards_buffer(discards_buffer);
+
+        result
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, S> Session<P, S>
+where
+    Self: crate::AsyncExpect,
+{
+    /// Expects a needle calling a callback periodically while waiting.
+    ///
+    /// The callback is called at most once per `interval`
+    /// with the output which was read so far but not matched yet.
+    /// It's handy for showing a progress of long operations.
+    ///
+    /// The needle is matched the same way as by [`AsyncExpect::expect`](crate::AsyncExpect::expect),
+    /// and the expect timeout limits the whole call.
+    pub async fn expect_with_progress<N, F>(
+        &mut self,
+        needle: N,
+        interval: Duration,
+        mut f: F,
+    ) -> Result<crate::Captures, Error>
+    where
+        N: crate::Needle,
+        F: FnMut(&[u8]),
+    {
+        let expect_timeout = self.get_expect_timeout();
+        let discards_buffer = self.is_timeout_discards_buffer();
+        let mut session = TimeoutGuard::new(self, expect_timeout);
+        let start = Instant::now();
+        let result = loop {
+            let left = expect_timeout.map(|timeout| timeout.saturating_sub(start.elapsed()));
+            let is_last = matches!(left, Some(left) if left <= interval);
+            let step = left.map_or(interval, |left| left.min(interval));
+
+            // only the final timeout may discard the buffer
+            session.set_timeout_discards_buffer(discards_buffer && is_last);
+            session.set_expect_timeout(Some(step));
+            match crate::AsyncExpect::expect(&mut *session, &needle).await {
+                Err(Error::ExpectTimeout) if !is_last => f(session.get_available()),
+                result => break result,
+            }
+        };
+        session.set_timeout_discards_buffer(discards_buffer);
+
+        result
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<P, S> Session<P, S>
+where
+    Self: crate::Expect,
+{
+    /// Expects the nth occurrence of a needle, counting from 1, skipping the earlier ones.
+    ///
+    /// Each next occurrence is looked up right after the end of the previous one,
+    /// and everything up to the end of the nth occurrence is consumed.
+    /// The returned matches refer to the nth occurrence,
+    /// while [`Captures::before`] includes the earlier occurrences.
+    ///
+    /// `n` equal to 0 is treated as 1.
+    ///
+    /// [`Captures::before`]: crate::Captures::before
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use expectrl::Expect;
+    ///
+    /// let mut p = expectrl::spawn("sh").unwrap();
+    /// p.send_line("echo 1; echo 2; echo 3").unwrap();
+    /// p.expect_nth("\r\n", 2).unwrap();
+    /// ```
+    pub fn expect_nth<N>(&mut self, needle: N, n: usize) -> Result<crate::Captures, Error>
+    where
+        N: crate::Needle,
+    {
+        crate::Expect::expect(self, crate::needle::Nth::new(needle, n))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, S> Session<P, S>
+where
+    Self: crate::AsyncExpect,
+{
+    /// Expects the nth occurrence of a needle, counting from 1, skipping the earlier ones.
+    ///
+    /// Each next occurrence is looked up right after the end of the previous one,
+    /// and everything up to the end of the nth occurrence is consumed.
+    /// The returned matches refer to the nth occurrence,
+    /// while [`Captures::before`] includes the earlier occurrences.
+    ///
+    /// `n` equal to 0 is treated as 1.
+    ///
+    /// [`Captures::before`]: crate::Captures::before
+    pub async fn expect_nth<N>(&mut self, needle: N, n: usize) -> Result<crate::Captures, Error>
+    where
+        N: crate::Needle,
+    {
+        crate::AsyncExpect::expect(self, crate::needle::Nth::new(needle, n)).await
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<P, S> Session<P, S>
+where
+    Self: crate::Expect,
+{
+    /// Answers a yes/no prompt.
+    ///
+    /// It expects the prompt and then sends a `yes` or a `no` answer as a line.
+    /// The answers can be changed via [`Session::set_confirm_answers`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut p = expectrl::spawn("apt remove vim").unwrap();
+    /// p.set_confirm_answers("Y", "n");
+    /// p.confirm("[Y/n]", true).unwrap();
+    /// ```
+    pub fn confirm<N>(&mut self, prompt: N, answer: bool) -> Result<(), Error>
+    where
+        N: crate::Needle,
+    {
+        let _ = crate::Expect::expect(self, prompt)?;
+        let answer = self.get_confirm_answer(answer).to_owned();
+        crate::Expect::send_line(self, answer)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, S> Session<P, S>
+where
+    Self: crate::AsyncExpect,
+{
+    /// Answers a yes/no prompt.
+    ///
+    /// It expects the prompt and then sends a `yes` or a `no` answer as a line.
+    /// The answers can be changed via [`Session::set_confirm_answers`].
+    pub async fn confirm<N>(&mut self, prompt: N, answer: bool) -> Result<(), Error>
+    where
+        N: crate::Needle,
+    {
+        let _ = crate::AsyncExpect::expect(self, prompt).await?;
+        let answer = self.get_confirm_answer(answer).to_owned();
+        crate::AsyncExpect::send_line(self, answer).await
+    }
+}
 
 #[cfg(not(feature = "async"))]
-use std::io::Read;
+impl<P, S> Session<P, S>
+where
+    Self: crate::Expect,
+{
+    /// Sends a line and waits for it to be echoed back.
+    ///
+    /// It's handy for line oriented protocols where the next line must not be sent
+    /// until the previous one was processed.
+    /// Everything up to the echo is consumed.
+    ///
+    /// It requires echo to be on, otherwise the line is not repeated in the output
+    /// and [`Error::ExpectTimeout`] is returned once the timeout is reached.
+    /// The timeout is used only for this call, the expect timeout is left intact.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use expectrl::process::Termios;
+    ///
+    /// let mut p = expectrl::spawn("cat").unwrap();
+    /// p.set_echo(true).unwrap();
+    /// p.send_line_wait_echo("first", Duration::from_secs(1)).unwrap();
+    /// p.send_line_wait_echo("second", Duration::from_secs(1)).unwrap();
+    /// ```
+    pub fn send_line_wait_echo(&mut self, text: &str, timeout: Duration) -> Result<(), Error> {
+        crate::Expect::send_line(self, text)?;
+
+        let mut session = TimeoutGuard::new(self, Some(timeout));
+        let _ = crate::Expect::expect(&mut *session, text)?;
+
+        Ok(())
+    }
+}
 
 #[cfg(feature = "async")]
-use crate::process::IntoAsyncStream;
+impl<P, S> Session<P, S>
+where
+    Self: crate::AsyncExpect,
+{
+    /// Sends a line and waits for it to be echoed back.
+    ///
+    /// It's handy for line oriented protocols where the next line must not be sent
+    /// until the previous one was processed.
+    /// Everything up to the echo is consumed.
+    ///
+    /// It requires echo to be on, otherwise the line is not repeated in the output
+    /// and [`Error::ExpectTimeout`] is returned once the timeout is reached.
+    /// The timeout is used only for this call, the expect timeout is left intact.
+    pub async fn send_line_wait_echo(
+        &mut self,
+        text: &str,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        crate::AsyncExpect::send_line(self, text).await?;
 
-#[cfg(unix)]
-type OsProc = crate::process::unix::UnixProcess;
-#[cfg(windows)]
-type OsProc = crate::process::windows::WinProcess;
+        let mut session = TimeoutGuard::new(self, Some(timeout));
+        let _ = crate::AsyncExpect::expect(&mut *session, text).await?;
 
-#[cfg(all(unix, not(feature = "async")))]
-type OsProcStream = crate::process::unix::PtyStream;
-#[cfg(all(unix, feature = "async"))]
-type OsProcStream = crate::process::unix::AsyncPtyStream;
-#[cfg(all(windows, not(feature = "async")))]
-type OsProcStream = crate::process::windows::ProcessStream;
-#[cfg(all(windows, feature = "async"))]
-type OsProcStream = crate::process::windows::AsyncProcessStream;
+        Ok(())
+    }
+}
 
-/// A type alias for OS process which can run a [`Session`] and a default one.
-pub type OsProcess = OsProc;
-/// A type alias for OS process stream which is a default one for [`Session`].
-pub type OsStream = OsProcStream;
-/// A type alias for OS session.
-pub type OsSession = Session<OsProc, OsStream>;
+#[cfg(not(feature = "async"))]
+impl<P, S> Session<P, S>
+where
+    Self: crate::Expect,
+{
+    /// Expects a needle and panics if it's not found.
+    ///
+    /// It's meant for tests, as the panic message includes the needle,
+    /// the expect timeout and the bytes which were read but not matched,
+    /// which is what is usually needed to figure out why a test failed.
+    ///
+    /// # Panics
+    ///
+    /// It panics if [`Expect::expect`] returns an error.
+    ///
+    /// [`Expect::expect`]: crate::Expect::expect
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut p = expectrl::spawn("echo Hello World").unwrap();
+    /// let found = p.must_expect("World");
+    /// assert_eq!(found.before(), b"Hello ");
+    /// ```
+    #[track_caller]
+    pub fn must_expect<N>(&mut self, needle: N) -> crate::Captures
+    where
+        N: crate::Needle + std::fmt::Debug,
+    {
+        let name = format!("{:?}", needle);
+        match crate::Expect::expect(self, needle) {
+            Ok(found) => found,
+            Err(err) => {
+                let timeout = self.get_expect_timeout();
+                panic!(
+                    "{}",
+                    must_expect_message(&name, timeout, self.get_available(), &err)
+                )
+            }
+        }
+    }
+}
 
 #[cfg(feature = "async")]
-pub use async_session::Session;
+impl<P, S> Session<P, S>
+where
+    Self: crate::AsyncExpect,
+{
+    /// Expects a needle and panics if it's not found.
+    ///
+    /// It's meant for tests, as the panic message includes the needle,
+    /// the expect timeout and the bytes which were read but not matched,
+    /// which is what is usually needed to figure out why a test failed.
+    ///
+    /// # Panics
+    ///
+    /// It panics if [`AsyncExpect::expect`] returns an error.
+    ///
+    /// [`AsyncExpect::expect`]: crate::AsyncExpect::expect
+    pub async fn must_expect<N>(&mut self, needle: N) -> crate::Captures
+    where
+        N: crate::Needle + std::fmt::Debug,
+    {
+        let name = format!("{:?}", needle);
+        match crate::AsyncExpect::expect(self, needle).await {
+            Ok(found) => found,
+            Err(err) => {
+                let timeout = self.get_expect_timeout();
+                panic!(
+                    "{}",
+                    must_expect_message(&name, timeout, self.get_available(), &err)
+                )
+            }
+        }
+    }
+}
+
+fn must_expect_message(
+    needle: &str,
+    timeout: Option<Duration>,
+    buffer: &[u8],
+    err: &Error,
+) -> String {
+    let timeout = match timeout {
+        Some(timeout) => format!("{:?}", timeout),
+        None => String::from("none"),
+    };
+
+    format!(
+        "expect failed: {}\n  needle: {}\n  timeout: {}\n  buffer: {:?}",
+        err,
+        needle,
+        timeout,
+        String::from_utf8_lossy(buffer)
+    )
+}
 
 #[cfg(not(feature = "async"))]
-pub use sync_session::Session;
+impl<P, S> Session<P, S>
+where
+    Self: crate::Expect,
+{
+    /// Expects a needle and then reads all following output until there's a silence
+    /// of at least `quiet` duration or an EOF.
+    ///
+    /// It returns the match along with the bytes which followed it.
+    /// The expect timeout is used for the match only,
+    /// the following output is read for as long as it keeps coming.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use expectrl::Expect;
+    ///
+    /// let mut p = expectrl::spawn("sh").unwrap();
+    /// p.send_line("echo START; ls").unwrap();
+    /// let (_, listing) = p.expect_then_drain("START", Duration::from_millis(300)).unwrap();
+    /// ```
+    pub fn expect_then_drain<N>(
+        &mut self,
+        needle: N,
+        quiet: Duration,
+    ) -> Result<(crate::Captures, Vec<u8>), Error>
+    where
+        N: crate::Needle,
+    {
+        let found = crate::Expect::expect(self, needle)?;
 
-impl Session<OsProc, OsProcStream> {
-    /// Spawns a session on a platform process.
+        let mut rest = Vec::new();
+        loop {
+            match self.expect_any_output(quiet) {
+                Ok(output) => rest.extend(output),
+                Err(Error::ExpectTimeout | Error::Eof) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok((found, rest))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, S> Session<P, S>
+where
+    Self: crate::AsyncExpect,
+{
+    /// Expects a needle and then reads all following output until there's a silence
+    /// of at least `quiet` duration or an EOF.
+    ///
+    /// It returns the match along with the bytes which followed it.
+    /// The expect timeout is used for the match only,
+    /// the following output is read for as long as it keeps coming.
+    pub async fn expect_then_drain<N>(
+        &mut self,
+        needle: N,
+        quiet: Duration,
+    ) -> Result<(crate::Captures, Vec<u8>), Error>
+    where
+        N: crate::Needle,
+    {
+        let found = crate::AsyncExpect::expect(self, needle).await?;
+
+        let mut rest = Vec::new();
+        loop {
+            match self.expect_any_output(quiet).await {
+                Ok(output) => rest.extend(output),
+                Err(Error::ExpectTimeout | Error::Eof) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok((found, rest))
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<P, S> Session<P, S>
+where
+    Self: crate::Expect,
+{
+    /// Expects a needle with an inactivity timeout instead of the expect timeout.
+    ///
+    /// The clock is reset every time new bytes arrive,
+    /// so it returns [`Error::ExpectTimeout`] only if the process stayed silent for `inactivity`.
+    /// It suits long operations which report a progress.
     ///
     /// # Example
     ///
     /// ```no_run
-    /// use std::process::Command;
-    /// use expectrl::Session;
+    /// use std::time::Duration;
+    /// use expectrl::Expect;
     ///
-    /// let p = Session::spawn(Command::new("cat"));
+    /// let mut p = expectrl::spawn("sh").unwrap();
+    /// p.send_line("cargo build").unwrap();
+    /// p.expect_active("Finished", Duration::from_secs(30)).unwrap();
     /// ```
-    pub fn spawn(command: Command) -> Result<Self, Error> {
-        let mut process = OsProcess::spawn_command(command)?;
-        let stream = process.open_stream()?;
+    pub fn expect_active<N>(
+        &mut self,
+        needle: N,
+        inactivity: Duration,
+    ) -> Result<crate::Captures, Error>
+    where
+        N: crate::Needle,
+    {
+        loop {
+            let found = crate::Expect::check(self, &needle)?;
+            if !found.is_empty() {
+                return Ok(found);
+            }
 
-        #[cfg(feature = "async")]
-        let stream = stream.into_async_stream()?;
+            let buffered = self.get_available().len();
+            if !self.wait_output(buffered, inactivity)? {
+                return Err(Error::ExpectTimeout);
+            }
+        }
+    }
 
-        let session = Self::new(process, stream)?;
+    /// Waits up to `timeout` until more than `buffered` bytes are available,
+    /// leaving them in the buffer.
+    ///
+    /// It returns `false` on a silence and [`Error::Eof`] if an EOF came instead of new bytes.
+    fn wait_output(&mut self, buffered: usize, timeout: Duration) -> Result<bool, Error> {
+        let start = Instant::now();
+        loop {
+            let is_eof = crate::Expect::is_matched(self, crate::Eof)?;
+            if self.get_available().len() > buffered {
+                return Ok(true);
+            }
 
-        Ok(session)
+            if is_eof {
+                return Err(Error::Eof);
+            }
+
+            if start.elapsed() >= timeout {
+                return Ok(false);
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
     }
+}
 
-    /// Spawns a session on a platform process.
-    /// Using a string commandline.
-    pub(crate) fn spawn_cmd(cmd: &str) -> Result<Self, Error> {
-        let mut process = OsProcess::spawn(cmd)?;
-        let stream = process.open_stream()?;
+#[cfg(feature = "async")]
+impl<P, S> Session<P, S>
+where
+    Self: crate::AsyncExpect,
+{
+    /// Expects a needle with an inactivity timeout instead of the expect timeout.
+    ///
+    /// The clock is reset every time new bytes arrive,
+    /// so it returns [`Error::ExpectTimeout`] only if the process stayed silent for `inactivity`.
+    /// It suits long operations which report a progress.
+    pub async fn expect_active<N>(
+        &mut self,
+        needle: N,
+        inactivity: Duration,
+    ) -> Result<crate::Captures, Error>
+    where
+        N: crate::Needle,
+    {
+        loop {
+            let found = crate::AsyncExpect::check(self, &needle).await?;
+            if !found.is_empty() {
+                return Ok(found);
+            }
 
-        #[cfg(feature = "async")]
-        let stream = stream.into_async_stream()?;
+            let buffered = self.get_available().len();
+            if !self.wait_output(buffered, inactivity).await? {
+                return Err(Error::ExpectTimeout);
+            }
+        }
+    }
 
-        let session = Self::new(process, stream)?;
+    /// Waits up to `timeout` until more than `buffered` bytes are available,
+    /// leaving them in the buffer.
+    ///
+    /// It returns `false` on a silence and [`Error::Eof`] if an EOF came instead of new bytes.
+    async fn wait_output(&mut self, buffered: usize, timeout: Duration) -> Result<bool, Error> {
+        let start = Instant::now();
+        loop {
+            let is_eof = crate::AsyncExpect::is_matched(self, crate::Eof).await?;
+            if self.get_available().len() > buffered {
+                return Ok(true);
+            }
 
-        Ok(session)
+            if is_eof {
+                return Err(Error::Eof);
+            }
+
+            if start.elapsed() >= timeout {
+                return Ok(false);
+            }
+
+            crate::runtime::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<P, S> Session<P, S>
+where
+    Self: crate::Expect,
+{
+    /// Reads a fixed-width record, exactly `width` bytes.
+    ///
+    /// It respects the expect timeout,
+    /// and returns [`Error::Eof`] if the process exited before a whole record was read.
+    /// A zero `width` is rejected with an error, as such a record never ends a stream.
+    ///
+    /// See [`Session::records`] to read records one by one.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut p = expectrl::spawn("cat records.dat").unwrap();
+    /// let header = p.read_record(80).unwrap();
+    /// ```
+    pub fn read_record(&mut self, width: usize) -> Result<Vec<u8>, Error> {
+        check_record_width(width)?;
+
+        let found = crate::Expect::expect(self, crate::NBytes(width))?;
+        Ok(found[0].to_vec())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, S> Session<P, S>
+where
+    Self: crate::AsyncExpect,
+{
+    /// Reads a fixed-width record, exactly `width` bytes.
+    ///
+    /// It respects the expect timeout,
+    /// and returns [`Error::Eof`] if the process exited before a whole record was read.
+    /// A zero `width` is rejected with an error, as such a record never ends a stream.
+    ///
+    /// See [`Session::records`] to read records one by one.
+    pub async fn read_record(&mut self, width: usize) -> Result<Vec<u8>, Error> {
+        check_record_width(width)?;
+
+        let found = crate::AsyncExpect::expect(self, crate::NBytes(width)).await?;
+        Ok(found[0].to_vec())
+    }
+}
+
+fn check_record_width(width: usize) -> Result<(), Error> {
+    match width {
+        0 => Err(Error::Other {
+            message: String::from("invalid record width"),
+            err: String::from("a record must be at least 1 byte wide"),
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<P, S> Session<P, S>
+where
+    Self: crate::Expect,
+{
+    /// Expects a prompt which isn't followed by a newline, like `Password: `,
+    /// and makes sure the program is waiting for input.
+    ///
+    /// Such prompts never complete a line, so line based reads hang on them,
+    /// and a plain expect may match a prompt like text which is followed by more output.
+    /// Once the needle is matched it waits for a silence of `quiet` duration;
+    /// if more output comes, the next occurrence of the needle is looked up.
+    /// An EOF counts as a silence.
+    ///
+    /// The expect timeout is applied to each lookup of the needle.
+    /// If the needle was found several times,
+    /// [`Captures::before`] contains only the output which followed the previous occurrence.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use expectrl::Expect;
+    ///
+    /// let mut p = expectrl::spawn("ssh localhost").unwrap();
+    /// p.expect_prompt_inline("password: ", Duration::from_millis(300)).unwrap();
+    /// p.send_line("secret").unwrap();
+    /// ```
+    ///
+    /// [`Captures::before`]: crate::Captures::before
+    pub fn expect_prompt_inline<N>(
+        &mut self,
+        needle: N,
+        quiet: Duration,
+    ) -> Result<crate::Captures, Error>
+    where
+        N: crate::Needle,
+    {
+        loop {
+            let found = crate::Expect::expect(self, &needle)?;
+
+            // an EOF counts as a silence
+            match self.wait_output(0, quiet) {
+                Ok(true) => {}
+                Ok(false) | Err(Error::Eof) => return Ok(found),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, S> Session<P, S>
+where
+    Self: crate::AsyncExpect,
+{
+    /// Expects a prompt which isn't followed by a newline, like `Password: `,
+    /// and makes sure the program is waiting for input.
+    ///
+    /// Such prompts never complete a line, so line based reads hang on them,
+    /// and a plain expect may match a prompt like text which is followed by more output.
+    /// Once the needle is matched it waits for a silence of `quiet` duration;
+    /// if more output comes, the next occurrence of the needle is looked up.
+    /// An EOF counts as a silence.
+    ///
+    /// The expect timeout is applied to each lookup of the needle.
+    /// If the needle was found several times,
+    /// [`Captures::before`] contains only the output which followed the previous occurrence.
+    ///
+    /// [`Captures::before`]: crate::Captures::before
+    pub async fn expect_prompt_inline<N>(
+        &mut self,
+        needle: N,
+        quiet: Duration,
+    ) -> Result<crate::Captures, Error>
+    where
+        N: crate::Needle,
+    {
+        loop {
+            let found = crate::AsyncExpect::expect(self, &needle).await?;
+
+            // an EOF counts as a silence
+            match self.wait_output(0, quiet).await {
+                Ok(true) => {}
+                Ok(false) | Err(Error::Eof) => return Ok(found),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<P, S> Session<P, S>
+where
+    P: crate::process::Termios,
+    S: Write + Read + crate::process::NonBlocking,
+{
+    /// Sends a line with echo turned off, e.g. a password.
+    ///
+    /// Echo is turned off before the line is sent,
+    /// the line is written only once the ECHO flag of the terminal is off.
+    /// Then the terminal is made to process the line and echo is restored,
+    /// so the line will not be echoed back even if the child reads it later.
+    ///
+    /// Echo is restored only if it was on before the call.
+    /// With `tracing` feature on only a length of the line is recorded.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use expectrl::Expect;
+    ///
+    /// let mut p = expectrl::spawn("sudo -k true").unwrap();
+    /// p.expect("password").unwrap();
+    /// p.send_line_no_echo("secret").unwrap();
+    /// ```
+    pub fn send_line_no_echo(&mut self, text: &str) -> Result<(), Error> {
+        use crate::process::Termios;
+
+        let is_echo = self.is_echo()?;
+        if is_echo {
+            let _ = self.set_echo(false)?;
+        }
+
+        let result = self.write_line(text.as_bytes(), crate::trace::send_secret);
+
+        if is_echo {
+            let settled = match result {
+                Ok(()) => self.settle_input(),
+                Err(_) => Ok(()),
+            };
+
+            // echo is restored even if settling failed,
+            // otherwise the terminal would be left without it.
+            let restored = self.set_echo(true);
+
+            result?;
+            settled?;
+            let _ = restored?;
+
+            return Ok(());
+        }
+
+        result
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, S> Session<P, S>
+where
+    P: crate::process::Termios,
+    S: futures_lite::AsyncWrite + futures_lite::AsyncRead + Unpin,
+{
+    /// Sends a line with echo turned off, e.g. a password.
+    ///
+    /// Echo is turned off before the line is sent,
+    /// the line is written only once the ECHO flag of the terminal is off.
+    /// Then the terminal is made to process the line and echo is restored,
+    /// so the line will not be echoed back even if the child reads it later.
+    ///
+    /// Echo is restored only if it was on before the call.
+    /// With `tracing` feature on only a length of the line is recorded.
+    pub async fn send_line_no_echo(&mut self, text: &str) -> Result<(), Error> {
+        use crate::process::Termios;
+
+        let is_echo = self.is_echo()?;
+        if is_echo {
+            let _ = self.set_echo(false)?;
+        }
+
+        let result = self
+            .write_line(text.as_bytes(), crate::trace::send_secret)
+            .await;
+
+        if is_echo {
+            let settled = match result {
+                Ok(()) => self.settle_input(),
+                Err(_) => Ok(()),
+            };
+
+            // echo is restored even if settling failed,
+            // otherwise the terminal would be left without it.
+            let restored = self.set_echo(true);
+
+            result?;
+            settled?;
+            let _ = restored?;
+
+            return Ok(());
+        }
+
+        result
+    }
+}
+
+fn exit_code_error(expected: i32, code: i32) -> Error {
+    Error::Other {
+        message: format!("expected process to exit with code {}", expected),
+        err: format!("it exited with code {}", code),
+    }
+}
+
+/// Returns a length of the valid UTF-8 prefix of a buffer,
+/// leaving out an incomplete character at the end as the rest of it may come later.
+fn utf8_prefix_len(buf: &[u8]) -> std::io::Result<usize> {
+    match std::str::from_utf8(buf) {
+        Ok(_) => Ok(buf.len()),
+        Err(err) if err.error_len().is_none() => Ok(err.valid_up_to()),
+        Err(err) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, err)),
     }
 }
 
@@ -111,6 +1861,10 @@ impl<P, S> Session<P, S> {
     /// This simply echos the child `stdout` and `stderr` to the real `stdout` and
     /// it echos the real `stdin` to the child `stdin`.
     ///
+    /// Control characters like `Ctrl-C` are forwarded to the child as they are,
+    /// so its terminal turns them into signals (e.g. `SIGINT`) for the child rather than the current process.
+    /// It requires the input terminal to be in a raw mode, which [`Stdin`] sets up on unix.
+    ///
     /// BEWARE that interact finishes after a process stops.
     /// So after the return you may not obtain a correct status of a process.
     ///
@@ -142,6 +1896,7 @@ impl<P, S> Session<P, S> {
     /// ```
     ///
     /// [`Read`]: std::io::Read
+    /// [`Stdin`]: crate::stream::stdin::Stdin
     pub fn interact<I, O>(&mut self, input: I, output: O) -> InteractSession<&mut Self, I, O, ()> {
         InteractSession::new(self, input, output, ())
     }
@@ -183,3 +1938,48 @@ where
 {
     session.swap_stream(|s| LogStream::new(s, dst))
 }
+
+/// Set a logger which will write each Read/Write operation into the writter in a given format.
+///
+/// # Example
+///
+/// ```
+/// use expectrl::{spawn, session::log_with_format, stream::log::timestamped_format};
+///
+/// let p = spawn("cat").unwrap();
+/// let p = log_with_format(p, std::io::stdout(), timestamped_format);
+/// ```
+#[cfg(not(feature = "async"))]
+pub fn log_with_format<W, P, S>(
+    session: Session<P, S>,
+    dst: W,
+    format: LogFormat,
+) -> Result<Session<P, LogStream<S, W>>, Error>
+where
+    W: Write,
+    S: Read,
+{
+    session.swap_stream(|s| LogStream::with_format(s, dst, format))
+}
+
+/// Set a logger which will write each Read/Write operation into the writter in a given format.
+///
+/// # Example
+///
+/// ```
+/// use expectrl::{spawn, session::log_with_format, stream::log::timestamped_format};
+///
+/// let p = spawn("cat").unwrap();
+/// let p = log_with_format(p, std::io::stdout(), timestamped_format);
+/// ```
+#[cfg(feature = "async")]
+pub fn log_with_format<W, P, S>(
+    session: Session<P, S>,
+    dst: W,
+    format: LogFormat,
+) -> Result<Session<P, LogStream<S, W>>, Error>
+where
+    W: Write,
+{
+    session.swap_stream(|s| LogStream::with_format(s, dst, format))
+}
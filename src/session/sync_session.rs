@@ -6,21 +6,63 @@ use std::{
 };
 
 use crate::{
-    error::Error,
+    error::{send_error, Error},
     expect::Expect,
-    needle::Needle,
+    needle::{capture, CollapseCr, MatchTransforms, Needle, Transformed, Until},
     process::{Healthcheck, NonBlocking, Termios},
-    Captures,
+    Captures, Eof, Key, LineEnding,
 };
 
+use super::{eof_hook::EofHook, history::OutputHistory, lifetime::Lifetime};
+
+/// An interval between read attempts of calls which wait for output without blocking.
+const READ_CHECK_INTERVAL: Duration = Duration::from_millis(5);
+
 /// Session represents a spawned process and its streams.
 /// It controlls process and communication with it.
 #[derive(Debug)]
 pub struct Session<P, S> {
     proc: P,
     stream: TryStream<S>,
+    settings: Settings<P>,
+}
+
+/// Settings of a session.
+///
+/// They're kept together so a session moves all of them over when its stream is swapped.
+#[derive(Debug)]
+struct Settings<P> {
     expect_timeout: Option<Duration>,
     expect_lazy: bool,
+    max_read_iterations: Option<usize>,
+    collapse_cr: bool,
+    match_transforms: MatchTransforms,
+    output_trim: Vec<u8>,
+    timeout_discards_buffer: bool,
+    confirm_answers: (String, String),
+    application_cursor_keys: bool,
+    line_ending: LineEnding,
+    send_alive_check: Option<fn(&P) -> io::Result<bool>>,
+    lifetime: Lifetime<P>,
+}
+
+impl<P> Default for Settings<P> {
+    fn default() -> Self {
+        Self {
+            expect_timeout: Some(Duration::from_millis(10000)),
+            expect_lazy: false,
+            max_read_iterations: None,
+            collapse_cr: false,
+            match_transforms: MatchTransforms::default(),
+            output_trim: Vec::new(),
+            timeout_discards_buffer: false,
+            confirm_answers: (String::from("yes"), String::from("no")),
+            application_cursor_keys: false,
+            line_ending: LineEnding::default(),
+            send_alive_check: None,
+            lifetime: Lifetime::new(),
+        }
+    }
 }
 
 impl<P, S> Session<P, S>
@@ -28,17 +70,49 @@ where
     S: Read,
 {
     /// Creates a new session.
+    ///
+    /// The stream may be any [`Read`] + [`Write`] + [`NonBlocking`] type,
+    /// so a session can be built over a custom stream with [`NoProcess`](crate::process::NoProcess).
     pub fn new(process: P, stream: S) -> io::Result<Self> {
         let stream = TryStream::new(stream)?;
 
         Ok(Self {
             proc: process,
             stream,
-            expect_timeout: Some(Duration::from_millis(10000)),
-            expect_lazy: false,
+            settings: Settings::default(),
         })
     }
 
+    /// Puts bytes to the end of the inner buffer,
+    /// so they'll be examined by the next expect call before anything read from the process.
+    ///
+    /// It's a counterpart of [`Session::export_buffer`].
+    pub fn import_buffer(&mut self, buf: &[u8]) {
+        self.stream.flush_in_buffer();
+        self.stream.keep_in_buffer(buf);
+    }
+
+    /// Takes bytes which were read from the process but not consumed yet out of the buffer.
+    ///
+    /// It's handy to find out what was read before an [`Error::ExpectTimeout`].
+    /// The bytes are consumed, so a next expect call won't see them;
+    /// use [`Session::export_buffer`] to get a copy instead.
+    pub fn drain_buffer(&mut self) -> Vec<u8> {
+        self.stream.flush_in_buffer();
+        let buf = self.stream.get_available().to_vec();
+        self.stream.consume_available(buf.len());
+        buf
+    }
+
+    /// Drops bytes which were read from the process but not consumed yet.
+    ///
+    /// It discards unread output, so a next expect call examines only what comes afterwards;
+    /// it's handy to resync before expecting a fresh prompt.
+    /// Use [`Session::drain_buffer`] to get the discarded bytes.
+    pub fn clear_buffer(&mut self) {
+        let _ = self.drain_buffer();
+    }
+
     pub(crate) fn swap_stream<F, R>(mut self, new: F) -> Result<Session<P, R>, Error>
     where
         F: FnOnce(S) -> R,
@@ -46,12 +120,25 @@ where
     {
         self.stream.flush_in_buffer();
         let buf = self.stream.get_available().to_owned();
+        let history = self.stream.history_mut().take();
+        let on_eof = std::mem::take(self.stream.on_eof_mut());
+        #[cfg(feature = "render")]
+        let screen = std::mem::take(self.stream.screen_mut());
+        let eof_confirm_reads = self.stream.eof_confirm_reads;
 
         let stream = self.stream.into_inner();
         let stream = new(stream);
 
         let mut session = Session::new(self.proc, stream)?;
         session.stream.keep_in_buffer(&buf);
+        *session.stream.history_mut() = history;
+        *session.stream.on_eof_mut() = on_eof;
+        #[cfg(feature = "render")]
+        {
+            *session.stream.screen_mut() = screen;
+        }
+        session.stream.eof_confirm_reads = eof_confirm_reads;
+        session.settings = self.settings;
 
         Ok(session)
     }
@@ -60,7 +147,7 @@ where
 impl<P, S> Session<P, S> {
     /// Set the pty session's expect timeout.
     pub fn set_expect_timeout(&mut self, expect_timeout: Option<Duration>) {
-        self.expect_timeout = expect_timeout;
+        self.settings.expect_timeout = expect_timeout;
     }
 
     /// Set a expect algorithm to be either gready or lazy.
@@ -69,7 +156,185 @@ impl<P, S> Session<P, S> {
     ///
     /// See [Session::expect].
     pub fn set_expect_lazy(&mut self, lazy: bool) {
-        self.expect_lazy = lazy;
+        self.settings.expect_lazy = lazy;
+    }
+
+    /// Sets a maximum number of reads an expect call can do without a match.
+    ///
+    /// Only reads which bring new data are counted.
+    /// Once the limit is reached [`Error::MaxIterations`] is returned.
+    /// It's a safety valve against needles which never match,
+    /// unlike a timeout it doesn't depend on a wall clock.
+    ///
+    /// Default is `None` which means no limit.
+    pub fn set_max_read_iterations(&mut self, max: Option<usize>) {
+        self.settings.max_read_iterations = max;
+    }
+
+    /// Sets a number of consecutive empty reads required to declare an EOF.
+    ///
+    /// Some streams may return a transient empty read while a process is still alive,
+    /// which would be treated as an EOF otherwise.
+    /// Requiring a few consecutive empty reads makes an EOF detection more robust.
+    ///
+    /// Default is 1; 0 is treated as 1.
+    pub fn set_eof_confirm_reads(&mut self, n: usize) {
+        self.stream.eof_confirm_reads = n.max(1);
+    }
+
+    /// Sets whether lines overwritten via carriage returns are collapsed for matching.
+    ///
+    /// When it's on, needles are matched against the output as a terminal would display it,
+    /// e.g. `10%\r20%\r30%` is seen as `30%`.
+    /// It's a transformation of a view used for matching, the buffer itself is left intact,
+    /// so [`Captures`] contain the original bytes.
+    ///
+    /// Default is `false`.
+    pub fn set_collapse_cr(&mut self, on: bool) {
+        self.settings.collapse_cr = on;
+    }
+
+    /// Adds a transformation of a view used for matching.
+    ///
+    /// Transformations are chained in the order they were added,
+    /// e.g. to strip ANSI sequences, normalize newlines or lowercase the output.
+    /// Only a view the needles are matched against is transformed,
+    /// the buffer and what gets consumed stay in original bytes, so [`Captures`] contain them.
+    ///
+    /// A transformation may be called several times per match and on prefixes of the buffer
+    /// in order to map a match back onto the original bytes,
+    /// so it must be a pure function of its input where a prefix is transformed into a prefix.
+    ///
+    /// The transformations are applied before [`Session::set_collapse_cr`].
+    pub fn add_match_transform<F>(&mut self, transform: F)
+    where
+        F: FnMut(&[u8]) -> Vec<u8> + Send + 'static,
+    {
+        self.settings.match_transforms.push(Box::new(transform));
+    }
+
+    /// Removes all transformations added by [`Session::add_match_transform`].
+    pub fn clear_match_transforms(&mut self) {
+        self.settings.match_transforms.clear();
+    }
+
+    /// Sets a pattern which is trimmed from the end of [`Captures::before`]
+    /// in captures returned by expect calls.
+    ///
+    /// It's handy for REPLs, where the output before a prompt ends with a part of it
+    /// or with whitespace, so the output of a command comes out clean.
+    /// It's only a view of the returned captures, the stream and the buffer are left intact
+    /// and [`Captures::as_bytes`] still contains the pattern.
+    ///
+    /// An empty pattern, which is the default, turns it off.
+    pub fn set_output_trim(&mut self, pattern: impl Into<Vec<u8>>) {
+        self.settings.output_trim = pattern.into();
+    }
+
+    /// Sets whether an expect timeout discards the output which was read but not matched.
+    ///
+    /// By default on [`Error::ExpectTimeout`] the buffer is left intact,
+    /// so the next expect call can match the partial output.
+    /// When it's on the buffer is cleared instead, so the next call starts fresh
+    /// and the partial output can't cause a spurious match.
+    ///
+    /// Default is `false`.
+    pub fn set_timeout_discards_buffer(&mut self, on: bool) {
+        self.settings.timeout_discards_buffer = on;
+    }
+
+    /// Sets answers which are sent by [`Session::confirm`].
+    ///
+    /// Default answers are `yes` and `no`.
+    pub fn set_confirm_answers(&mut self, yes: &str, no: &str) {
+        self.settings.confirm_answers = (yes.to_owned(), no.to_owned());
+    }
+
+    /// Sets whether keys sent by [`Session::send_keys`] use an application cursor mode (`DECCKM`).
+    ///
+    /// Full screen programs (e.g. `vim` or `less`) usually switch a terminal into the mode,
+    /// so they expect arrow keys as `\x1bOA` rather than `\x1b[A`.
+    ///
+    /// Default is `false`.
+    pub fn set_application_cursor_keys(&mut self, on: bool) {
+        self.settings.application_cursor_keys = on;
+    }
+
+    /// Sets a line ending which `send_line` puts after a line.
+    ///
+    /// Default is `\r\n` on windows and `\n` on other platforms,
+    /// see [`LineEnding`].
+    pub fn set_line_ending(&mut self, ending: LineEnding) {
+        self.settings.line_ending = ending;
+    }
+
+    /// Sets a callback which is called the first time an EOF is confirmed,
+    /// see [`Session::set_eof_confirm_reads`].
+    ///
+    /// It's called once, from whichever call waiting for the output confirms the EOF first,
+    /// so it's a precise notification of the process output being over
+    /// without polling the process liveness.
+    ///
+    /// Setting a new callback replaces the previous one if it wasn't called yet.
+    pub fn set_on_eof<F>(&mut self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.stream.on_eof_mut().set(f);
+    }
+
+    /// Sets whether the process liveness is verified before anything is sent to it.
+    ///
+    /// Writing to a process which has exited may succeed silently or even block,
+    /// as the bytes are buffered by a terminal.
+    /// When it's on, [`Expect::send`] and [`Expect::send_line`]
+    /// return [`Error::ProcessExited`] for a process which is not alive.
+    ///
+    /// Regardless of the setting a write error caused by a gone process
+    /// (e.g. a broken pipe) is reported as [`Error::ProcessExited`].
+    ///
+    /// Default is `false`.
+    pub fn set_send_checks_alive(&mut self, on: bool)
+    where
+        P: Healthcheck,
+    {
+        let check: fn(&P) -> io::Result<bool> = P::is_alive;
+        self.settings.send_alive_check = on.then_some(check);
+    }
+
+    /// Sets a limit of the total lifetime of the session, counting from its creation.
+    ///
+    /// Once it's exceeded expect, read and send calls fail with [`Error::LifetimeExceeded`].
+    /// It's a watchdog for tests and CI, unlike the expect timeout it limits the session as a whole,
+    /// so a loop of successful expect calls can't run forever.
+    ///
+    /// An expect call checks it on every read, so it's interrupted once the lifetime is over,
+    /// even if its own timeout is longer.
+    /// Other calls, like a send or a plain read, check it only at the beginning,
+    /// so a blocking one is not interrupted.
+    ///
+    /// Default is `None`, no limit.
+    pub fn set_lifetime(&mut self, lifetime: Option<Duration>) {
+        self.settings.lifetime.set_limit(lifetime);
+    }
+
+    /// Sets a function which is called to kill the process once the lifetime is exceeded.
+    pub(crate) fn set_lifetime_kill(&mut self, kill: Option<fn(&mut P) -> io::Result<()>>) {
+        self.settings.lifetime.set_kill(kill);
+    }
+
+    fn check_lifetime(&mut self) -> Result<(), Error> {
+        self.settings.lifetime.check(&mut self.proc)
+    }
+
+    fn check_alive_before_send(&self) -> Result<(), Error> {
+        if let Some(is_alive) = self.settings.send_alive_check {
+            if !is_alive(&self.proc)? {
+                return Err(Error::ProcessExited);
+            }
+        }
+
+        Ok(())
     }
 
     /// Get a reference to original stream.
@@ -91,6 +356,73 @@ impl<P, S> Session<P, S> {
     pub fn get_process_mut(&mut self) -> &mut P {
         &mut self.proc
     }
+
+    /// Returns bytes which were read but not consumed by an expect call yet.
+    pub(crate) fn get_available(&mut self) -> &[u8] {
+        self.stream.get_available()
+    }
+
+    /// Returns a copy of bytes which were read from the process but not consumed yet.
+    ///
+    /// Together with [`Session::import_buffer`] it allows to hand the state over to another session,
+    /// e.g. when reattaching to an existing pty.
+    /// Use [`Session::drain_buffer`] to take the bytes out instead.
+    pub fn export_buffer(&self) -> Vec<u8> {
+        self.stream.buffered()
+    }
+
+    pub(crate) fn get_expect_timeout(&self) -> Option<Duration> {
+        self.settings.expect_timeout
+    }
+
+    pub(crate) fn get_confirm_answer(&self, answer: bool) -> &str {
+        match answer {
+            true => &self.settings.confirm_answers.0,
+            false => &self.settings.confirm_answers.1,
+        }
+    }
+
+    pub(crate) fn is_timeout_discards_buffer(&self) -> bool {
+        self.settings.timeout_discards_buffer
+    }
+
+    pub(crate) fn is_expect_lazy(&self) -> bool {
+        self.settings.expect_lazy
+    }
+
+    /// Starts keeping the last `capacity` bytes read from the process.
+    ///
+    /// All reads are recorded, either they're done by expect calls or by [`Read`] methods.
+    /// Calling it again changes the capacity keeping the most recent bytes.
+    ///
+    /// It may be useful for diagnostics, e.g. to dump the recent output when a process crashes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use expectrl::{spawn, Eof, Expect};
+    ///
+    /// let mut p = spawn("echo Hello World").unwrap();
+    /// p.enable_output_history(1024);
+    /// p.expect(Eof).unwrap();
+    /// assert_eq!(p.output_history(), b"Hello World\r\n");
+    /// ```
+    pub fn enable_output_history(&mut self, capacity: usize) {
+        match self.stream.history_mut() {
+            Some(history) => history.set_capacity(capacity),
+            history => *history = Some(OutputHistory::new(capacity)),
+        }
+    }
+
+    /// Returns the last bytes read from the process.
+    ///
+    /// It's empty unless [`Session::enable_output_history`] was called.
+    pub fn output_history(&self) -> Vec<u8> {
+        self.stream
+            .history()
+            .map(OutputHistory::to_vec)
+            .unwrap_or_default()
+    }
 }
 
 impl<P, S> Expect for Session<P, S>
@@ -101,38 +433,42 @@ where
     where
         N: Needle,
     {
-        match self.expect_lazy {
-            true => self.expect_lazy(needle),
-            false => self.expect_gready(needle),
+        self.check_lifetime()?;
+
+        let needle = Transformed::new(
+            CollapseCr::new(needle, self.settings.collapse_cr),
+            &self.settings.match_transforms,
+        );
+        let result = crate::trace::expect(&needle, || match self.settings.expect_lazy {
+            true => self.expect_lazy(&needle),
+            false => self.expect_gready(&needle),
+        });
+
+        if self.settings.timeout_discards_buffer && matches!(result, Err(Error::ExpectTimeout)) {
+            let n = self.stream.get_available().len();
+            self.stream.consume_available(n);
         }
+
+        result.map(|found| found.with_before_trim(&self.settings.output_trim))
     }
 
     fn check<N>(&mut self, needle: N) -> Result<Captures, Error>
     where
         N: Needle,
     {
-        let eof = self.stream.read_available()?;
-        let buf = self.stream.get_available();
-
-        let found = needle.check(buf, eof)?;
-        if !found.is_empty() {
-            let end_index = Captures::right_most_index(&found);
-            let involved_bytes = buf[..end_index].to_vec();
-            self.stream.consume_available(end_index);
-            return Ok(Captures::new(involved_bytes, found));
-        }
-
-        if eof {
-            return Err(Error::Eof);
-        }
-
-        Ok(Captures::new(Vec::new(), Vec::new()))
+        self.check_available(needle, true)
     }
 
     fn is_matched<N>(&mut self, needle: N) -> Result<bool, Error>
     where
         N: Needle,
     {
+        self.check_lifetime()?;
+
+        let needle = Transformed::new(
+            CollapseCr::new(needle, self.settings.collapse_cr),
+            &self.settings.match_transforms,
+        );
         let eof = self.stream.read_available()?;
         let buf = self.stream.get_available();
 
@@ -152,7 +488,11 @@ where
     where
         B: AsRef<[u8]>,
     {
-        self.stream.write_all(buf.as_ref())?;
+        self.check_lifetime()?;
+        self.check_alive_before_send()?;
+
+        crate::trace::send(buf.as_ref());
+        self.stream.write_all(buf.as_ref()).map_err(send_error)?;
 
         Ok(())
     }
@@ -161,22 +501,244 @@ where
     where
         B: AsRef<[u8]>,
     {
-        #[cfg(windows)]
-        const LINE_ENDING: &[u8] = b"\r\n";
-        #[cfg(not(windows))]
-        const LINE_ENDING: &[u8] = b"\n";
+        self.write_line(buf.as_ref(), crate::trace::send)
+    }
+}
+
+impl<P, S> Session<P, S>
+where
+    S: Write + Read + NonBlocking,
+{
+    /// Sends a line, the line is recorded in a trace by a given function.
+    pub(crate) fn write_line(&mut self, buf: &[u8], trace: fn(&[u8])) -> Result<(), Error> {
+        let line_ending = self.settings.line_ending.as_bytes();
+
+        self.check_lifetime()?;
+        self.check_alive_before_send()?;
 
-        self.stream.write_all(buf.as_ref())?;
-        self.write_all(LINE_ENDING)?;
+        trace(buf);
+        crate::trace::send(line_ending);
+        self.stream.write_all(buf).map_err(send_error)?;
+        self.write_all(line_ending).map_err(send_error)?;
 
         Ok(())
     }
+
+    /// Sends special keys, like arrows or function keys,
+    /// as the VT100/xterm escape sequences a terminal would send.
+    ///
+    /// See [`Session::set_application_cursor_keys`] for a mode the arrows are sent in.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use expectrl::{spawn, Key};
+    ///
+    /// let mut p = spawn("bash").unwrap();
+    /// p.send_keys(&[Key::Up, Key::Enter]).unwrap();
+    /// ```
+    pub fn send_keys(&mut self, keys: &[Key]) -> Result<(), Error> {
+        let bytes = keys
+            .iter()
+            .flat_map(|key| key.as_bytes(self.settings.application_cursor_keys))
+            .copied()
+            .collect::<Vec<_>>();
+        self.send(bytes)
+    }
+
+    /// Expects a needle using a given timeout instead of the session one.
+    ///
+    /// The timeout is used only for this call,
+    /// the session timeout is restored afterwards regardless of the result.
+    /// `None` waits for the needle without a limit.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use expectrl::{spawn, Expect};
+    ///
+    /// let mut p = spawn("sh").unwrap();
+    /// p.send_line("make").unwrap();
+    /// p.expect_timeout("$ ", Some(Duration::from_secs(600))).unwrap();
+    /// ```
+    pub fn expect_timeout<N>(
+        &mut self,
+        needle: N,
+        timeout: Option<Duration>,
+    ) -> Result<Captures, Error>
+    where
+        N: Needle,
+    {
+        super::TimeoutGuard::new(self, timeout).expect(needle)
+    }
+
+    /// Reads until an EOF and returns all the output,
+    /// using a given timeout instead of the session one like [`Session::expect_timeout`].
+    ///
+    /// On [`Error::ExpectTimeout`] the bytes read so far are left in the buffer,
+    /// so they can be taken via [`Session::drain_buffer`]
+    /// (unless [`Session::set_timeout_discards_buffer`] is on).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut p = expectrl::spawn("make").unwrap();
+    /// let output = p.expect_eof(Some(Duration::from_secs(600))).unwrap();
+    /// println!("{}", String::from_utf8_lossy(&output));
+    /// ```
+    pub fn expect_eof(&mut self, timeout: Option<Duration>) -> Result<Vec<u8>, Error> {
+        let found = self.expect_timeout(Eof, timeout)?;
+        Ok(found.as_bytes().to_vec())
+    }
+
+    /// Checks whether a needle is matched in available bytes like [`Expect::check`] does,
+    /// but leaves all bytes in the buffer,
+    /// so a following call sees them again.
+    ///
+    /// Unlike [`Expect::is_matched`] it returns the captures,
+    /// which are empty if nothing was found.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use expectrl::{spawn, Expect};
+    ///
+    /// let mut p = spawn("sh").unwrap();
+    /// p.send_line("make").unwrap();
+    /// if !p.peek("error").unwrap().is_empty() {
+    ///     p.expect("$ ").unwrap();
+    /// }
+    /// ```
+    pub fn peek<N>(&mut self, needle: N) -> Result<Captures, Error>
+    where
+        N: Needle,
+    {
+        self.check_available(needle, false)
+    }
+
+    /// Returns an iterator which repeatedly expects an `item`
+    /// until a `stop` needle is matched.
+    ///
+    /// Whichever needle is found first in the buffer is taken.
+    /// The iterator ends when `stop` is matched (its bytes are consumed),
+    /// or right after an error (e.g. timeout or EOF) is yielded.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use expectrl::spawn;
+    ///
+    /// let mut p = spawn("tail -f /var/log/syslog").unwrap();
+    /// for line in p.expect_until('\n', "DONE") {
+    ///     let line = line.unwrap();
+    ///     println!("{}", String::from_utf8_lossy(line.before()));
+    /// }
+    /// ```
+    pub fn expect_until<'a, I, T>(
+        &'a mut self,
+        item: I,
+        stop: T,
+    ) -> impl Iterator<Item = Result<Captures, Error>> + 'a
+    where
+        I: Needle + 'a,
+        T: Needle + 'a,
+    {
+        let needle = Until::new(item, stop);
+        let mut is_done = false;
+
+        std::iter::from_fn(move || {
+            if is_done {
+                return None;
+            }
+
+            match self.expect(&needle) {
+                Ok(_) if needle.is_stopped() => {
+                    is_done = true;
+                    None
+                }
+                Ok(found) => Some(Ok(found)),
+                Err(err) => {
+                    is_done = true;
+                    Some(Err(err))
+                }
+            }
+        })
+    }
+
+    /// Returns an iterator of fixed-width records, `width` bytes each.
+    ///
+    /// The iterator ends on an EOF, bytes of an incomplete record are left in the buffer.
+    /// Other errors (e.g. timeout) are yielded and end the iterator as well.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut p = expectrl::spawn("cat records.dat").unwrap();
+    /// for record in p.records(80) {
+    ///     let record = record.unwrap();
+    ///     println!("{}", String::from_utf8_lossy(&record));
+    /// }
+    /// ```
+    pub fn records(&mut self, width: usize) -> impl Iterator<Item = Result<Vec<u8>, Error>> + '_ {
+        let mut is_done = false;
+
+        std::iter::from_fn(move || {
+            if is_done {
+                return None;
+            }
+
+            match self.read_record(width) {
+                Ok(record) => Some(Ok(record)),
+                Err(Error::Eof) => {
+                    is_done = true;
+                    None
+                }
+                Err(err) => {
+                    is_done = true;
+                    Some(Err(err))
+                }
+            }
+        })
+    }
 }
 
 impl<P, S> Session<P, S>
 where
     S: Read + NonBlocking,
 {
+    /// Checks a needle against available bytes,
+    /// consuming the matched ones only if `consume` is set.
+    fn check_available<N>(&mut self, needle: N, consume: bool) -> Result<Captures, Error>
+    where
+        N: Needle,
+    {
+        self.check_lifetime()?;
+
+        let needle = Transformed::new(
+            CollapseCr::new(needle, self.settings.collapse_cr),
+            &self.settings.match_transforms,
+        );
+        let eof = self.stream.read_available()?;
+        let buf = self.stream.get_available();
+
+        if let Some(found) = capture(&needle, buf, eof)? {
+            if consume {
+                self.stream.consume_available(found.as_bytes().len());
+            }
+
+            return Ok(found.with_before_trim(&self.settings.output_trim));
+        }
+
+        if eof {
+            return Err(Error::Eof);
+        }
+
+        Ok(Captures::new(Vec::new(), Vec::new()))
+    }
+
     /// Expect which fills as much as possible to the buffer.
     ///
     /// See [Session::expect].
@@ -184,29 +746,128 @@ where
     where
         N: Needle,
     {
+        // A needle may be satisfied by bytes left in the buffer by previous calls,
+        // in which case there's no need to read anything.
+        let data = self.stream.get_available();
+        if !data.is_empty() {
+            if let Some(found) = capture(&needle, data, false)? {
+                self.stream.consume_available(found.as_bytes().len());
+                return Ok(found);
+            }
+        }
+
         let start = time::Instant::now();
+        let mut iterations = 0;
         loop {
+            let length = self.stream.get_available().len();
             let eof = self.stream.read_available()?;
             let data = self.stream.get_available();
+            if data.len() > length {
+                iterations += 1;
+            }
 
-            let found = needle.check(data, eof)?;
-            if !found.is_empty() {
-                let end_index = Captures::right_most_index(&found);
-                let involved_bytes = data[..end_index].to_vec();
-                self.stream.consume_available(end_index);
+            if let Some(found) = capture(&needle, data, eof)? {
+                self.stream.consume_available(found.as_bytes().len());
+                return Ok(found);
+            }
+
+            if eof {
+                return Err(Error::Eof);
+            }
+
+            self.check_read_iterations(iterations)?;
+            self.check_lifetime()?;
 
-                return Ok(Captures::new(involved_bytes, found));
+            if let Some(timeout) = self.settings.expect_timeout {
+                if start.elapsed() > timeout {
+                    return Err(Error::ExpectTimeout);
+                }
+            }
+        }
+    }
+
+    /// Expects a needle to be displayed on a screen.
+    ///
+    /// The output is rendered by a minimal terminal emulator, see [`Screen`],
+    /// and the needle is checked against the rendered text rather than the raw output.
+    /// Rows of the screen are separated by `\n`.
+    ///
+    /// All output read so far is rendered, including one consumed by other calls,
+    /// and on a match all buffered output is consumed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut p = expectrl::spawn("top").unwrap();
+    /// p.expect_screen("load average").unwrap();
+    /// ```
+    ///
+    /// [`Screen`]: crate::render::Screen
+    #[cfg(feature = "render")]
+    pub fn expect_screen<N>(&mut self, needle: N) -> Result<(), Error>
+    where
+        N: Needle,
+    {
+        let start = time::Instant::now();
+        let mut iterations = 0;
+        loop {
+            let length = self.stream.get_available().len();
+            let eof = self.stream.read_available()?;
+            let is_new_read = self.stream.get_available().len() > length;
+            if is_new_read {
+                iterations += 1;
+            }
+
+            let contents = self.stream.screen().contents();
+            let found = needle.check(contents.as_bytes(), eof)?;
+            if !found.is_empty() {
+                let n = self.stream.get_available().len();
+                self.stream.consume_available(n);
+                return Ok(());
             }
 
             if eof {
                 return Err(Error::Eof);
             }
 
-            if let Some(timeout) = self.expect_timeout {
+            self.check_read_iterations(iterations)?;
+            self.check_lifetime()?;
+
+            if let Some(timeout) = self.settings.expect_timeout {
                 if start.elapsed() > timeout {
                     return Err(Error::ExpectTimeout);
                 }
             }
+
+            if !is_new_read {
+                std::thread::sleep(READ_CHECK_INTERVAL);
+            }
+        }
+    }
+
+    /// Returns a screen with all output read so far rendered onto it.
+    ///
+    /// The output which is available at the moment is read first,
+    /// but it's not consumed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut p = expectrl::spawn("top").unwrap();
+    /// p.expect_screen("load average").unwrap();
+    /// let screen = p.screen().unwrap();
+    /// println!("{}", screen.row(0).unwrap());
+    /// ```
+    #[cfg(feature = "render")]
+    pub fn screen(&mut self) -> Result<crate::render::Screen, Error> {
+        let _ = self.stream.read_available()?;
+        Ok(self.stream.screen().clone())
+    }
+
+    fn check_read_iterations(&self, iterations: usize) -> Result<(), Error> {
+        match self.settings.max_read_iterations {
+            Some(max) if iterations >= max => Err(Error::MaxIterations),
+            _ => Ok(()),
         }
     }
 
@@ -219,6 +880,8 @@ where
     {
         let mut checking_data_length = 0;
         let mut eof = false;
+        let mut iterations = 0;
+        let mut is_new_read = true;
         let start = time::Instant::now();
         loop {
             let mut available = self.stream.get_available();
@@ -236,7 +899,22 @@ where
                 // We could read all data available via `read_available` to reduce IO operations,
                 // but in such case we would need to keep a EOF indicator internally in stream,
                 // which is OK if EOF happens onces, but I am not sure if this is a case.
-                eof = self.stream.read_available_once(&mut [0; 1])? == Some(0);
+                let n = self.stream.read_available_once(&mut [0; 1])?;
+                eof = n == Some(0);
+
+                // Bytes are read one by one,
+                // so a read is counted once per chunk of available data as in a greedy mode.
+                match n {
+                    Some(n) if n > 0 => {
+                        if is_new_read {
+                            iterations += 1;
+                        }
+
+                        is_new_read = false;
+                    }
+                    _ => is_new_read = true,
+                }
+
                 available = self.stream.get_available();
             }
 
@@ -249,19 +927,19 @@ where
 
             let data = &available[..checking_data_length];
 
-            let found = needle.check(data, eof)?;
-            if !found.is_empty() {
-                let end_index = Captures::right_most_index(&found);
-                let involved_bytes = data[..end_index].to_vec();
-                self.stream.consume_available(end_index);
-                return Ok(Captures::new(involved_bytes, found));
+            if let Some(found) = capture(&needle, data, eof)? {
+                self.stream.consume_available(found.as_bytes().len());
+                return Ok(found);
             }
 
             if eof {
                 return Err(Error::Eof);
             }
 
-            if let Some(timeout) = self.expect_timeout {
+            self.check_read_iterations(iterations)?;
+            self.check_lifetime()?;
+
+            if let Some(timeout) = self.settings.expect_timeout {
                 if start.elapsed() > timeout {
                     return Err(Error::ExpectTimeout);
                 }
@@ -286,17 +964,184 @@ where
     pub fn is_empty(&mut self) -> io::Result<bool> {
         self.stream.is_empty()
     }
+
+    /// Reads until `max` bytes are read, an EOF is reached or the timeout is elapsed.
+    ///
+    /// It returns whatever was read, which may be less than `max` bytes or even nothing,
+    /// so unlike [`Read::read_to_end`](std::io::Read::read_to_end)
+    /// the memory used is bounded, which is safer for untrusted processes.
+    ///
+    /// Bytes which were read by previous calls but not consumed are returned first.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut p = expectrl::spawn("cat /dev/urandom").unwrap();
+    /// let bytes = p.read_up_to(1024, Duration::from_secs(1)).unwrap();
+    /// assert!(bytes.len() <= 1024);
+    /// ```
+    pub fn read_up_to(&mut self, max: usize, timeout: Duration) -> io::Result<Vec<u8>> {
+        let start = time::Instant::now();
+        let mut buf = vec![0; max];
+        let mut n = 0;
+        while n < max {
+            let is_waiting = match self.stream.try_read(&mut buf[n..]) {
+                Ok(0) if self.stream.check_eof(0) => break,
+                Ok(0) => true,
+                Ok(read) => {
+                    let _ = self.stream.check_eof(read);
+                    n += read;
+                    false
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => true,
+                Err(err) => return Err(err),
+            };
+
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                break;
+            }
+
+            if is_waiting {
+                std::thread::sleep(READ_CHECK_INTERVAL.min(timeout - elapsed));
+            }
+        }
+
+        buf.truncate(n);
+
+        Ok(buf)
+    }
+
+    /// Reads everything which is available right now without blocking.
+    ///
+    /// Bytes which were read by previous calls but not consumed are returned first.
+    /// Unlike [`Session::try_read`] it drains all pending output rather than a single read,
+    /// and it returns an empty buffer if nothing is ready.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut p = expectrl::spawn("cat").unwrap();
+    /// let progress = p.read_available().unwrap();
+    /// ```
+    pub fn read_available(&mut self) -> io::Result<Vec<u8>> {
+        let _ = self.stream.read_available()?;
+        Ok(self.drain_buffer())
+    }
+
+    /// Reads everything which is available right now without blocking as a string,
+    /// see [`Session::read_available`].
+    ///
+    /// An incomplete UTF-8 character at the end is left in the buffer until the rest of it comes.
+    /// Invalid UTF-8 results in [`io::ErrorKind::InvalidData`] error,
+    /// in which case nothing is consumed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut p = expectrl::spawn("cat").unwrap();
+    /// print!("{}", p.read_available_string().unwrap());
+    /// ```
+    pub fn read_available_string(&mut self) -> io::Result<String> {
+        let _ = self.stream.read_available()?;
+        let buf = self.stream.get_available();
+        let n = super::utf8_prefix_len(buf)?;
+        let text = String::from_utf8_lossy(&buf[..n]).into_owned();
+        self.stream.consume_available(n);
+
+        Ok(text)
+    }
+
+    /// Waits until there's something to read or the timeout is elapsed.
+    ///
+    /// It returns `true` if there's data (or an EOF) to read and `false` on timeout.
+    /// Read bytes are kept in the buffer, so they're available for following expect and read calls.
+    ///
+    /// A conpty pipe can't be waited on, so it's checked with a short interval,
+    /// but it saves from hand written loops around [`Session::try_read`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut p = expectrl::spawn("powershell").unwrap();
+    /// if p.wait_readable(Duration::from_secs(1)).unwrap() {
+    ///     let mut buf = [0; 128];
+    ///     let n = p.try_read(&mut buf).unwrap();
+    /// }
+    /// ```
+    #[cfg(windows)]
+    pub fn wait_readable(&mut self, timeout: Duration) -> io::Result<bool> {
+        let start = time::Instant::now();
+        let mut buf = [0; 248];
+        loop {
+            let is_eof = self.stream.read_available_once(&mut buf)? == Some(0);
+            if is_eof || !self.stream.get_available().is_empty() {
+                return Ok(true);
+            }
+
+            if start.elapsed() >= timeout {
+                return Ok(false);
+            }
+
+            std::thread::sleep(READ_CHECK_INTERVAL);
+        }
+    }
+}
+
+impl<P, S> Session<P, S>
+where
+    S: Write,
+{
+    /// Sends a text byte by byte with a delay between them.
+    ///
+    /// Each byte is flushed right away.
+    /// It mimics a human typing, which may be necessary for programs
+    /// which drop input sent too fast (e.g. devices connected over serial).
+    ///
+    /// Like [`Session::send`] it fails if the process has exited or the session lifetime is exceeded,
+    /// the lifetime is checked before each byte as the delays may add up.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut p = expectrl::spawn("cat").unwrap();
+    /// p.send_slow("Hello World\n", Duration::from_millis(50)).unwrap();
+    /// ```
+    pub fn send_slow(&mut self, text: &str, per_char: Duration) -> Result<(), Error> {
+        self.check_lifetime()?;
+        self.check_alive_before_send()?;
+
+        crate::trace::send(text.as_bytes());
+
+        for (i, b) in text.bytes().enumerate() {
+            if i > 0 {
+                std::thread::sleep(per_char);
+                self.check_lifetime()?;
+            }
+
+            self.stream.write_all(&[b]).map_err(send_error)?;
+            self.stream.flush().map_err(send_error)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl<P, S> Write for Session<P, S>
 where
     S: Write,
 {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         self.stream.write(buf)
     }
 
-    fn flush(&mut self) -> std::io::Result<()> {
+    fn flush(&mut self) -> io::Result<()> {
         self.stream.flush()
     }
 
@@ -309,7 +1154,8 @@ impl<P, S> Read for Session<P, S>
 where
     S: Read,
 {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check_lifetime()?;
         self.stream.read(buf)
     }
 }
@@ -318,7 +1164,8 @@ impl<P, S> BufRead for Session<P, S>
 where
     S: Read,
 {
-    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.check_lifetime()?;
         self.stream.fill_buf()
     }
 
@@ -353,6 +1200,10 @@ where
     fn set_echo(&mut self, on: bool) -> io::Result<bool> {
         self.get_process_mut().set_echo(on)
     }
+
+    fn settle_input(&mut self) -> io::Result<()> {
+        self.get_process_mut().settle_input()
+    }
 }
 
 impl<P, S> NonBlocking for Session<P, S>
@@ -397,6 +1248,8 @@ where
 #[derive(Debug)]
 struct TryStream<S> {
     stream: ControlledReader<S>,
+    eof_confirm_reads: usize,
+    zero_reads: usize,
 }
 
 impl<S> TryStream<S> {
@@ -411,6 +1264,54 @@ impl<S> TryStream<S> {
     fn as_mut(&mut self) -> &mut S {
         &mut self.stream.inner.get_mut().inner
     }
+
+    fn history(&self) -> Option<&OutputHistory> {
+        self.stream.inner.get_ref().history.as_ref()
+    }
+
+    /// Returns all bytes which were read but not consumed,
+    /// in the order [`ControlledReader::flush_in_buffer`] would keep them.
+    fn buffered(&self) -> Vec<u8> {
+        let mut buf = self.stream.inner.get_ref().buffer.clone();
+        buf.extend_from_slice(self.stream.inner.buffer());
+        buf
+    }
+
+    fn history_mut(&mut self) -> &mut Option<OutputHistory> {
+        &mut self.stream.inner.get_mut().history
+    }
+
+    fn on_eof_mut(&mut self) -> &mut EofHook {
+        &mut self.stream.inner.get_mut().on_eof
+    }
+
+    #[cfg(feature = "render")]
+    fn screen(&self) -> &crate::render::Screen {
+        &self.stream.inner.get_ref().screen
+    }
+
+    #[cfg(feature = "render")]
+    fn screen_mut(&mut self) -> &mut crate::render::Screen {
+        &mut self.stream.inner.get_mut().screen
+    }
+
+    /// Registers a read result and verifies whether an EOF is confirmed.
+    ///
+    /// An EOF is confirmed only after `eof_confirm_reads` consecutive zero reads.
+    fn check_eof(&mut self, n: usize) -> bool {
+        if n > 0 {
+            self.zero_reads = 0;
+            return false;
+        }
+
+        self.zero_reads = self.zero_reads.saturating_add(1);
+        if self.zero_reads < self.eof_confirm_reads {
+            return false;
+        }
+
+        self.on_eof_mut().call();
+        true
+    }
 }
 
 impl<S> TryStream<S>
@@ -421,6 +1322,8 @@ where
     fn new(stream: S) -> io::Result<Self> {
         Ok(Self {
             stream: ControlledReader::new(stream),
+            eof_confirm_reads: 1,
+            zero_reads: 0,
         })
     }
 
@@ -472,14 +1375,15 @@ where
         }
     }
 
-    fn read_available(&mut self) -> std::io::Result<bool> {
+    fn read_available(&mut self) -> io::Result<bool> {
         self.stream.flush_in_buffer();
 
         let mut buf = [0; 248];
         loop {
             match self.try_read_inner(&mut buf) {
-                Ok(0) => break Ok(true),
+                Ok(0) => break Ok(self.check_eof(0)),
                 Ok(n) => {
+                    let _ = self.check_eof(n);
                     self.stream.keep_in_buffer(&buf[..n]);
                 }
                 Err(err) if err.kind() == io::ErrorKind::WouldBlock => break Ok(false),
@@ -488,12 +1392,14 @@ where
         }
     }
 
-    fn read_available_once(&mut self, buf: &mut [u8]) -> std::io::Result<Option<usize>> {
+    fn read_available_once(&mut self, buf: &mut [u8]) -> io::Result<Option<usize>> {
         self.stream.flush_in_buffer();
 
         match self.try_read_inner(buf) {
-            Ok(0) => Ok(Some(0)),
+            Ok(0) if self.check_eof(0) => Ok(Some(0)),
+            Ok(0) => Ok(None),
             Ok(n) => {
+                let _ = self.check_eof(n);
                 self.stream.keep_in_buffer(&buf[..n]);
                 Ok(Some(n))
             }
@@ -506,7 +1412,7 @@ where
     fn try_read_inner(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.stream.get_mut().set_blocking(false)?;
 
-        let result = self.stream.get_mut().read(buf);
+        let result = self.stream.read_inner(buf);
 
         // As file is DUPed changes in one descriptor affects all ones
         // so we need to make blocking file after we finished.
@@ -579,6 +1485,11 @@ where
         self.inner.consume(b.len());
         self.keep_in_buffer(&b);
     }
+
+    // non-buffered read
+    fn read_inner(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.get_mut().read_inner(buf)
+    }
 }
 
 impl<R> ControlledReader<R> {
@@ -603,6 +1514,10 @@ impl<R> ControlledReader<R> {
 struct BufferedReader<R> {
     inner: R,
     buffer: Vec<u8>,
+    history: Option<OutputHistory>,
+    on_eof: EofHook,
+    #[cfg(feature = "render")]
+    screen: crate::render::Screen,
 }
 
 impl<R> BufferedReader<R> {
@@ -610,17 +1525,38 @@ impl<R> BufferedReader<R> {
         Self {
             inner: reader,
             buffer: Vec::new(),
+            history: None,
+            on_eof: EofHook::default(),
+            #[cfg(feature = "render")]
+            screen: crate::render::Screen::default(),
         }
     }
 }
 
+impl<R> BufferedReader<R>
+where
+    R: Read,
+{
+    fn read_inner(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if let Some(history) = &mut self.history {
+            history.push(&buf[..n]);
+        }
+
+        #[cfg(feature = "render")]
+        self.screen.feed(&buf[..n]);
+
+        Ok(n)
+    }
+}
+
 impl<R> Read for BufferedReader<R>
 where
     R: Read,
 {
-    fn read(&mut self, mut buf: &mut [u8]) -> std::io::Result<usize> {
+    fn read(&mut self, mut buf: &mut [u8]) -> io::Result<usize> {
         if self.buffer.is_empty() {
-            self.inner.read(buf)
+            self.read_inner(buf)
         } else {
             let n = buf.write(&self.buffer)?;
             let _ = self.buffer.drain(..n);
@@ -0,0 +1,67 @@
+//! Module contains a guard which overrides an expect timeout of a session for a while.
+
+use std::{
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
+
+use super::Session;
+
+/// ExpectTimeout is implemented by types which have an expect timeout,
+/// so it can be overridden by [`TimeoutGuard`].
+pub(crate) trait ExpectTimeout {
+    fn expect_timeout(&self) -> Option<Duration>;
+
+    fn set_expect_timeout(&mut self, timeout: Option<Duration>);
+}
+
+impl<P, S> ExpectTimeout for Session<P, S> {
+    fn expect_timeout(&self) -> Option<Duration> {
+        self.get_expect_timeout()
+    }
+
+    fn set_expect_timeout(&mut self, timeout: Option<Duration>) {
+        Session::set_expect_timeout(self, timeout)
+    }
+}
+
+/// TimeoutGuard gives access to a session with an overridden expect timeout.
+///
+/// The original timeout is restored once the guard is dropped,
+/// regardless of how a call made through it ended.
+pub(crate) struct TimeoutGuard<'a, T: ExpectTimeout> {
+    session: &'a mut T,
+    timeout: Option<Duration>,
+}
+
+impl<'a, T: ExpectTimeout> TimeoutGuard<'a, T> {
+    pub(crate) fn new(session: &'a mut T, timeout: Option<Duration>) -> Self {
+        let original = session.expect_timeout();
+        ExpectTimeout::set_expect_timeout(session, timeout);
+
+        Self {
+            session,
+            timeout: original,
+        }
+    }
+}
+
+impl<T: ExpectTimeout> Deref for TimeoutGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.session
+    }
+}
+
+impl<T: ExpectTimeout> DerefMut for TimeoutGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.session
+    }
+}
+
+impl<T: ExpectTimeout> Drop for TimeoutGuard<'_, T> {
+    fn drop(&mut self) {
+        ExpectTimeout::set_expect_timeout(self.session, self.timeout);
+    }
+}
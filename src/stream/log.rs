@@ -4,6 +4,7 @@
 use std::{
     io::{self, Read, Result, Write},
     ops::{Deref, DerefMut},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 #[cfg(feature = "async")]
@@ -22,25 +23,210 @@ use crate::process::NonBlocking;
 pub struct LogStream<S, W> {
     stream: S,
     logger: W,
+    format: LogFormat,
 }
 
 impl<S, W> LogStream<S, W> {
     /// Creates a new instance of the stream.
     pub fn new(stream: S, logger: W) -> Self {
-        Self { stream, logger }
+        Self::with_format(stream, logger, plain_format)
+    }
+
+    /// Creates a new instance of the stream which logs records in a given format.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use expectrl::stream::log::{timestamped_format, LogStream};
+    ///
+    /// let input = Cursor::new(b"Hello World".to_vec());
+    /// let mut stream = LogStream::with_format(input, std::io::stderr(), timestamped_format);
+    ///
+    /// let mut buf = String::new();
+    /// stream.read_to_string(&mut buf).unwrap();
+    /// ```
+    pub fn with_format(stream: S, logger: W, format: LogFormat) -> Self {
+        Self {
+            stream,
+            logger,
+            format,
+        }
+    }
+
+    /// Creates a new instance of the stream which logs a hex dump of each operation,
+    /// see [hex_format].
+    ///
+    /// It's handy when a process emits control sequences or other binary data.
+    pub fn hex(stream: S, logger: W) -> Self {
+        Self::with_format(stream, logger, hex_format)
     }
 }
 
 impl<S, W: Write> LogStream<S, W> {
     fn log_write(&mut self, buf: &[u8]) {
-        log(&mut self.logger, "write", buf);
+        self.log(Direction::Write, buf);
     }
 
     fn log_read(&mut self, buf: &[u8]) {
-        log(&mut self.logger, "read", buf);
+        self.log(Direction::Read, buf);
+    }
+
+    fn log(&mut self, direction: Direction, bytes: &[u8]) {
+        let record = LogRecord {
+            direction,
+            bytes,
+            time: SystemTime::now(),
+        };
+
+        (self.format)(&mut self.logger, record);
     }
 }
 
+/// A function which writes a [LogRecord] to a logger.
+pub type LogFormat = fn(&mut dyn Write, LogRecord<'_>);
+
+/// A direction of a logged IO operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Bytes were read from a stream, e.g. an output of a process.
+    Read,
+    /// Bytes were written to a stream, e.g. an input of a process.
+    Write,
+}
+
+/// A logged IO operation.
+#[derive(Debug, Clone, Copy)]
+pub struct LogRecord<'a> {
+    /// A direction of the operation.
+    pub direction: Direction,
+    /// Bytes which were read or written.
+    pub bytes: &'a [u8],
+    /// A time the operation was done at.
+    pub time: SystemTime,
+}
+
+/// A format which is used by [LogStream::new].
+///
+/// It writes a direction and the bytes as a string,
+/// e.g. `read: "Hello World\r\n"`.
+pub fn plain_format(writer: &mut dyn Write, record: LogRecord<'_>) {
+    let target = match record.direction {
+        Direction::Read => "read",
+        Direction::Write => "write",
+    };
+
+    let _ = match std::str::from_utf8(record.bytes) {
+        Ok(data) => writeln!(writer, "{}: {:?}", target, data),
+        Err(..) => writeln!(writer, "{}:(bytes): {:?}", target, record.bytes),
+    };
+}
+
+/// A format which writes a UTC timestamp, a direction and escaped bytes,
+/// e.g. `2024-01-02T03:04:05.678Z <- "Hello World\r\n"`.
+///
+/// `->` is used for written bytes and `<-` for read ones.
+pub fn timestamped_format(writer: &mut dyn Write, record: LogRecord<'_>) {
+    let arrow = match record.direction {
+        Direction::Read => "<-",
+        Direction::Write => "->",
+    };
+
+    let _ = writeln!(
+        writer,
+        "{} {} \"{}\"",
+        Timestamp(record.time),
+        arrow,
+        record.bytes.escape_ascii()
+    );
+}
+
+/// A format which writes a direction and a canonical hex dump of the bytes,
+/// with an offset, 16 bytes in hex and their ASCII representation on each line.
+///
+/// ```text
+/// read: 13 bytes
+/// 00000000  1b 5b 33 31 6d 48 65 6c  6c 6f 1b 5b 6d           |.[31mHello.[m|
+/// ```
+pub fn hex_format(writer: &mut dyn Write, record: LogRecord<'_>) {
+    let target = match record.direction {
+        Direction::Read => "read",
+        Direction::Write => "write",
+    };
+
+    let _ = writeln!(writer, "{}: {} bytes", target, record.bytes.len())
+        .and_then(|_| hex_dump(writer, record.bytes));
+}
+
+fn hex_dump(writer: &mut dyn Write, bytes: &[u8]) -> Result<()> {
+    for (i, line) in bytes.chunks(16).enumerate() {
+        write!(writer, "{:08x} ", i * 16)?;
+
+        for pos in 0..16 {
+            if pos == 8 {
+                write!(writer, " ")?;
+            }
+
+            match line.get(pos) {
+                Some(b) => write!(writer, " {:02x}", b)?,
+                None => write!(writer, "   ")?,
+            }
+        }
+
+        let ascii = line
+            .iter()
+            .map(|&b| match b {
+                0x20..=0x7e => b as char,
+                _ => '.',
+            })
+            .collect::<String>();
+
+        writeln!(writer, "  |{}|", ascii)?;
+    }
+
+    Ok(())
+}
+
+/// Timestamp displays a time in RFC 3339 format with milliseconds in UTC.
+struct Timestamp(SystemTime);
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let since_epoch = self.0.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let secs = since_epoch.as_secs();
+        let (year, month, day) = civil_from_days(secs / 86400);
+        let secs_of_day = secs % 86400;
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            year,
+            month,
+            day,
+            secs_of_day / 3600,
+            secs_of_day % 3600 / 60,
+            secs_of_day % 60,
+            since_epoch.subsec_millis()
+        )
+    }
+}
+
+/// Converts a number of days since the unix epoch to a date,
+/// see <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
 impl<S: Write, W: Write> Write for LogStream<S, W> {
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
         let n = self.stream.write(buf)?;
@@ -149,9 +335,70 @@ impl<S: AsyncRead + Unpin, W: Write + Unpin> AsyncRead for LogStream<S, W> {
     }
 }
 
-fn log(mut writer: impl Write, target: &str, data: &[u8]) {
-    let _ = match std::str::from_utf8(data) {
-        Ok(data) => writeln!(writer, "{}: {:?}", target, data),
-        Err(..) => writeln!(writer, "{}:(bytes): {:?}", target, data),
-    };
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_hex_format() {
+        let mut buf = Vec::new();
+        hex_format(
+            &mut buf,
+            LogRecord {
+                direction: Direction::Read,
+                bytes: b"\x1b[31mHello World\x1b[m\r\n",
+                time: UNIX_EPOCH,
+            },
+        );
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "read: 21 bytes\n\
+             00000000  1b 5b 33 31 6d 48 65 6c  6c 6f 20 57 6f 72 6c 64  |.[31mHello World|\n\
+             00000010  1b 5b 6d 0d 0a                                    |.[m..|\n"
+        );
+
+        let mut buf = Vec::new();
+        hex_format(
+            &mut buf,
+            LogRecord {
+                direction: Direction::Write,
+                bytes: b"",
+                time: UNIX_EPOCH,
+            },
+        );
+        assert_eq!(String::from_utf8(buf).unwrap(), "write: 0 bytes\n");
+    }
+
+    #[test]
+    fn test_timestamped_format() {
+        let mut buf = Vec::new();
+        timestamped_format(
+            &mut buf,
+            LogRecord {
+                direction: Direction::Read,
+                bytes: b"Hello\r\n\x1b",
+                time: UNIX_EPOCH + Duration::from_millis(1_704_164_645_678),
+            },
+        );
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "2024-01-02T03:04:05.678Z <- \"Hello\\r\\n\\x1b\"\n"
+        );
+
+        let mut buf = Vec::new();
+        timestamped_format(
+            &mut buf,
+            LogRecord {
+                direction: Direction::Write,
+                bytes: b"ls",
+                time: UNIX_EPOCH + Duration::from_secs(951_782_400),
+            },
+        );
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "2000-02-29T00:00:00.000Z -> \"ls\"\n"
+        );
+    }
 }
@@ -2,3 +2,7 @@
 
 pub mod log;
 pub mod stdin;
+#[cfg(feature = "telnet")]
+pub mod telnet;
+#[cfg(feature = "tokio")]
+pub mod tokio;
@@ -32,6 +32,9 @@ impl Stdin {
     ///
     /// It may change terminal's STDIN state therefore, after
     /// it's used you must call [Stdin::close].
+    ///
+    /// On unix a terminal is put into a raw mode,
+    /// so signal generating characters like `Ctrl-C` are read as plain bytes.
     pub fn open() -> Result<Self, Error> {
         #[cfg(not(feature = "async"))]
         {
@@ -107,7 +110,7 @@ impl polling::Source for Stdin {
 mod inner {
     use super::*;
 
-    use std::os::unix::prelude::AsRawFd;
+    use std::os::unix::prelude::{AsRawFd, RawFd};
 
     use nix::{
         libc::STDIN_FILENO,
@@ -120,44 +123,49 @@ mod inner {
     pub(super) struct StdinInner {
         orig_flags: Option<Termios>,
         #[cfg(feature = "async")]
-        stdin: async_io::Async<std::io::Stdin>,
+        stdin: crate::runtime::Async<io::Stdin>,
         #[cfg(not(feature = "async"))]
-        stdin: std::io::Stdin,
+        stdin: io::Stdin,
     }
 
     impl StdinInner {
         pub(super) fn new() -> Result<Self, Error> {
-            let stdin = std::io::stdin();
+            let stdin = io::stdin();
             #[cfg(feature = "async")]
-            let stdin = async_io::Async::new(stdin)?;
+            let stdin = crate::runtime::Async::new(stdin)?;
 
-            #[cfg(target_os = "macos")]
-            let orig_flags = None;
-
-            #[cfg(not(target_os = "macos"))]
             let orig_flags = Self::prepare()?;
 
             Ok(Self { stdin, orig_flags })
         }
 
+        /// Puts a terminal into a raw mode.
+        ///
+        /// Besides turning echo off it turns ISIG off,
+        /// so control characters like Ctrl-C are passed through as bytes
+        /// and reach a child via its own terminal instead of signaling the current process.
         pub(super) fn prepare() -> Result<Option<Termios>, Error> {
             // flush buffers
             // self.stdin.flush()?;
 
-            let mut o_pty_flags = None;
-
             // verify: possible controlling fd can be stdout and stderr as well?
             // https://stackoverflow.com/questions/35873843/when-setting-terminal-attributes-via-tcsetattrfd-can-fd-be-either-stdout
-            let isatty_terminal = isatty(STDIN_FILENO)
-                .map_err(|e| Error::unknown("failed to call isatty", e.to_string()))?;
+            Self::prepare_fd(STDIN_FILENO)
+        }
+
+        fn prepare_fd(fd: RawFd) -> Result<Option<Termios>, Error> {
+            let mut o_pty_flags = None;
+
+            let isatty_terminal =
+                isatty(fd).map_err(|e| Error::unknown("failed to call isatty", e.to_string()))?;
             if isatty_terminal {
                 // tcgetattr issues error if a provided fd is not a tty,
                 // but we can work with such input as it may be redirected.
-                o_pty_flags = termios::tcgetattr(STDIN_FILENO)
+                o_pty_flags = termios::tcgetattr(fd)
                     .map(Some)
                     .map_err(|e| Error::unknown("failed to call tcgetattr", e.to_string()))?;
 
-                set_raw(STDIN_FILENO)
+                set_raw(fd)
                     .map_err(|e| Error::unknown("failed to set a raw tty", e.to_string()))?;
             }
 
@@ -180,7 +188,7 @@ mod inner {
     }
 
     impl AsRawFd for StdinInner {
-        fn as_raw_fd(&self) -> std::os::unix::prelude::RawFd {
+        fn as_raw_fd(&self) -> RawFd {
             self.stdin.as_raw_fd()
         }
     }
@@ -202,6 +210,36 @@ mod inner {
             AsyncRead::poll_read(Pin::new(&mut self.stdin), cx, buf)
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        use nix::{pty::openpty, sys::termios::LocalFlags, unistd::close};
+
+        #[test]
+        fn test_prepare_raw_mode() {
+            let pty = openpty(None, None).unwrap();
+
+            let orig = StdinInner::prepare_fd(pty.slave).unwrap().unwrap();
+            assert!(orig.local_flags.contains(LocalFlags::ISIG));
+
+            // Ctrl-C must be read as a byte rather than signal the current process
+            let raw = termios::tcgetattr(pty.slave).unwrap();
+            assert!(!raw.local_flags.contains(LocalFlags::ISIG));
+            assert!(!raw.local_flags.contains(LocalFlags::ICANON));
+            assert!(!raw.local_flags.contains(LocalFlags::ECHO));
+
+            close(pty.slave).unwrap();
+            close(pty.master).unwrap();
+        }
+
+        #[test]
+        fn test_prepare_not_a_tty() {
+            let file = std::fs::File::open("/dev/null").unwrap();
+            assert!(StdinInner::prepare_fd(file.as_raw_fd()).unwrap().is_none());
+        }
+    }
 }
 
 #[cfg(windows)]
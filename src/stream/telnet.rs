@@ -0,0 +1,419 @@
+//! This module contains a [TelnetStream]
+//! which handles telnet negotiation in order to talk to telnet servers.
+//!
+//! All options the server asks for are refused,
+//! so the connection stays in the default NVT mode,
+//! while the negotiation bytes are stripped from the data so they don't interfere with matching.
+//!
+//! # Example
+//!
+#![cfg_attr(feature = "async", doc = "```ignore")]
+#![cfg_attr(not(feature = "async"), doc = "```no_run")]
+//! use std::net::TcpStream;
+//! use expectrl::{stream::telnet::TelnetStream, Expect, Session};
+//!
+//! let stream = TcpStream::connect("127.0.0.1:23").unwrap();
+//! let mut p = Session::over_stream(TelnetStream::new(stream)).unwrap();
+//! p.expect("login:").unwrap();
+//! p.send_line("guest").unwrap();
+//! ```
+
+use std::{
+    io::{self, Read, Result, Write},
+    ops::{Deref, DerefMut},
+};
+
+#[cfg(feature = "async")]
+use futures_lite::{AsyncRead, AsyncWrite};
+#[cfg(feature = "async")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::process::NonBlocking;
+
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+/// TelnetStream is an IO stream wrapper,
+/// which answers telnet negotiation and strips it from the read data.
+///
+/// `IAC` bytes written to the stream are escaped.
+#[derive(Debug)]
+pub struct TelnetStream<S> {
+    stream: S,
+    parser: Parser,
+    replies: Vec<u8>,
+}
+
+impl<S> TelnetStream<S> {
+    /// Creates a new instance of the stream.
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            parser: Parser::default(),
+            replies: Vec::new(),
+        }
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Write> TelnetStream<S> {
+    /// Writes pending negotiation replies.
+    ///
+    /// What isn't written is kept to be sent later,
+    /// e.g. if the stream is in a non-blocking mode.
+    fn write_replies(&mut self) -> Result<()> {
+        while !self.replies.is_empty() {
+            match self.stream.write(&self.replies) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => drop(self.replies.drain(..n)),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: Write> Write for TelnetStream<S> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.write_replies()?;
+
+        match buf.iter().position(|&b| b == IAC) {
+            Some(0) => {
+                self.stream.write_all(&[IAC, IAC])?;
+                Ok(1)
+            }
+            Some(pos) => self.stream.write(&buf[..pos]),
+            None => self.stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.write_replies()?;
+        self.stream.flush()
+    }
+}
+
+impl<S: Read + Write> Read for TelnetStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        loop {
+            let n = self.stream.read(buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+
+            let data = self.parser.filter(&mut buf[..n], &mut self.replies);
+            self.write_replies()?;
+
+            // a chunk of only negotiation must not be taken as an EOF
+            if data > 0 {
+                return Ok(data);
+            }
+        }
+    }
+}
+
+impl<S> NonBlocking for TelnetStream<S>
+where
+    S: NonBlocking,
+{
+    fn set_blocking(&mut self, on: bool) -> Result<()> {
+        self.stream.set_blocking(on)
+    }
+}
+
+impl<S> Deref for TelnetStream<S> {
+    type Target = S;
+
+    fn deref(&self) -> &Self::Target {
+        &self.stream
+    }
+}
+
+impl<S> DerefMut for TelnetStream<S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.stream
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: AsyncWrite + Unpin> TelnetStream<S> {
+    fn poll_write_replies(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        while !self.replies.is_empty() {
+            match Pin::new(&mut self.stream).poll_write(cx, &self.replies) {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Poll::Ready(Ok(n)) => drop(self.replies.drain(..n)),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: AsyncWrite + Unpin> AsyncWrite for TelnetStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize>> {
+        futures_lite::ready!(self.poll_write_replies(cx))?;
+
+        match buf.iter().position(|&b| b == IAC) {
+            Some(0) => {
+                // once the escape is queued the byte is taken,
+                // what isn't written now is sent on the next write or flush.
+                self.replies.extend([IAC, IAC]);
+                let _ = self.poll_write_replies(cx);
+                Poll::Ready(Ok(1))
+            }
+            Some(pos) => Pin::new(&mut self.stream).poll_write(cx, &buf[..pos]),
+            None => Pin::new(&mut self.stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        futures_lite::ready!(self.poll_write_replies(cx))?;
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        futures_lite::ready!(self.poll_write_replies(cx))?;
+        Pin::new(&mut self.stream).poll_close(cx)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for TelnetStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        let this = &mut *self;
+        loop {
+            let n = futures_lite::ready!(Pin::new(&mut this.stream).poll_read(cx, buf))?;
+            if n == 0 {
+                return Poll::Ready(Ok(0));
+            }
+
+            let data = this.parser.filter(&mut buf[..n], &mut this.replies);
+
+            // replies are sent on the next IO call if the stream isn't ready for them
+            if let Poll::Ready(Err(err)) = this.poll_write_replies(cx) {
+                return Poll::Ready(Err(err));
+            }
+
+            if data > 0 {
+                return Poll::Ready(Ok(data));
+            }
+        }
+    }
+}
+
+/// A state of telnet commands parsing,
+/// it's kept between reads as a command may be split.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Parser {
+    #[default]
+    Data,
+    Iac,
+    Negotiation(u8),
+    Subnegotiation,
+    SubnegotiationIac,
+}
+
+impl Parser {
+    /// Removes telnet commands from a buffer in place,
+    /// adding replies to negotiation requests.
+    ///
+    /// Returns the number of data bytes left at the beginning of the buffer.
+    fn filter(&mut self, buf: &mut [u8], replies: &mut Vec<u8>) -> usize {
+        let mut data = 0;
+        for i in 0..buf.len() {
+            let b = buf[i];
+            *self = match (*self, b) {
+                (Parser::Data, IAC) => Parser::Iac,
+                (Parser::Data, _) => {
+                    buf[data] = b;
+                    data += 1;
+                    Parser::Data
+                }
+                (Parser::Iac, IAC) => {
+                    buf[data] = IAC;
+                    data += 1;
+                    Parser::Data
+                }
+                (Parser::Iac, DO | DONT | WILL | WONT) => Parser::Negotiation(b),
+                (Parser::Iac, SB) => Parser::Subnegotiation,
+                (Parser::Iac, _) => Parser::Data,
+                (Parser::Negotiation(command), option) => {
+                    // every option is refused, and refusals aren't acknowledged
+                    // as the options are already disabled.
+                    match command {
+                        DO => replies.extend([IAC, WONT, option]),
+                        WILL => replies.extend([IAC, DONT, option]),
+                        _ => {}
+                    }
+
+                    Parser::Data
+                }
+                (Parser::Subnegotiation, IAC) => Parser::SubnegotiationIac,
+                (Parser::Subnegotiation, _) => Parser::Subnegotiation,
+                (Parser::SubnegotiationIac, SE) => Parser::Data,
+                (Parser::SubnegotiationIac, _) => Parser::Subnegotiation,
+            };
+        }
+
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        let mut parser = Parser::default();
+        let mut replies = Vec::new();
+
+        let mut buf = [
+            b'a', IAC, DO, 1, b'b', IAC, WILL, 3, IAC, IAC, IAC, DONT, 5, b'c',
+        ];
+        let n = parser.filter(&mut buf, &mut replies);
+        assert_eq!(&buf[..n], [b'a', b'b', IAC, b'c']);
+        assert_eq!(replies, [IAC, WONT, 1, IAC, DONT, 3]);
+
+        replies.clear();
+        let mut buf = [b'a', IAC, SB, 24, 1, IAC, IAC, IAC, SE, b'b'];
+        let n = parser.filter(&mut buf, &mut replies);
+        assert_eq!(&buf[..n], b"ab");
+        assert!(replies.is_empty());
+    }
+
+    #[test]
+    fn test_filter_split_command() {
+        let mut parser = Parser::default();
+        let mut replies = Vec::new();
+
+        let mut buf = [b'a', IAC];
+        let n = parser.filter(&mut buf, &mut replies);
+        assert_eq!(&buf[..n], b"a");
+
+        let mut buf = [DO];
+        let n = parser.filter(&mut buf, &mut replies);
+        assert_eq!(n, 0);
+
+        let mut buf = [1, b'b'];
+        let n = parser.filter(&mut buf, &mut replies);
+        assert_eq!(&buf[..n], b"b");
+        assert_eq!(replies, [IAC, WONT, 1]);
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut input = vec![IAC, DO, 1];
+        input.extend(b"login: ");
+        let mut stream = TelnetStream::new(Mock {
+            input: io::Cursor::new(input),
+            output: Vec::new(),
+        });
+
+        let mut buf = [0; 64];
+        let n = stream.read(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"login: ");
+
+        stream.write_all(&[b'a', IAC, b'b']).unwrap();
+        assert_eq!(stream.output, [IAC, WONT, 1, b'a', IAC, IAC, b'b']);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_async_write_escape_when_pending() {
+        use futures_lite::AsyncWriteExt;
+
+        let mut stream = TelnetStream::new(PendingMock {
+            output: Vec::new(),
+            pending: true,
+        });
+
+        futures_lite::future::block_on(async {
+            stream.write_all(&[b'a', IAC, b'b']).await.unwrap();
+            stream.flush().await.unwrap();
+        });
+
+        assert_eq!(stream.output, [b'a', IAC, IAC, b'b']);
+    }
+
+    /// A writer which returns [`Poll::Pending`] on every other call and writes a byte at a time.
+    #[cfg(feature = "async")]
+    #[derive(Debug)]
+    struct PendingMock {
+        output: Vec<u8>,
+        pending: bool,
+    }
+
+    #[cfg(feature = "async")]
+    impl AsyncWrite for PendingMock {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<Result<usize>> {
+            self.pending = !self.pending;
+            if self.pending {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            self.output.push(buf[0]);
+            Poll::Ready(Ok(1))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[derive(Debug)]
+    struct Mock {
+        input: io::Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Mock {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Mock {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! This module contains a [TokioStream]
+//! which lets a session run over tokio IO types, like a tokio TCP connection.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use expectrl::{stream::tokio::TokioStream, AsyncExpect, Session};
+//!
+//! # async fn run() {
+//! let stream = tokio::net::TcpStream::connect("127.0.0.1:25").await.unwrap();
+//! let mut p = Session::over_stream(TokioStream::new(stream)).unwrap();
+//! p.expect("220").await.unwrap();
+//! p.send_line("QUIT").await.unwrap();
+//! # }
+//! ```
+
+use std::{
+    io::Result,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_lite::{AsyncRead, AsyncWrite};
+use tokio::io::ReadBuf;
+
+/// TokioStream is an IO stream wrapper,
+/// which implements [`AsyncRead`] and [`AsyncWrite`] of `futures`
+/// for a stream implementing [`tokio::io::AsyncRead`] and [`tokio::io::AsyncWrite`].
+#[derive(Debug)]
+pub struct TokioStream<S> {
+    stream: S,
+}
+
+impl<S> TokioStream<S> {
+    /// Creates a new instance of the stream.
+    pub fn new(stream: S) -> Self {
+        Self { stream }
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: tokio::io::AsyncWrite + Unpin> AsyncWrite for TokioStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+impl<S: tokio::io::AsyncRead + Unpin> AsyncRead for TokioStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        let mut buf = ReadBuf::new(buf);
+        futures_lite::ready!(Pin::new(&mut self.stream).poll_read(cx, &mut buf))?;
+        Poll::Ready(Ok(buf.filled().len()))
+    }
+}
+
+impl<S> Deref for TokioStream<S> {
+    type Target = S;
+
+    fn deref(&self) -> &Self::Target {
+        &self.stream
+    }
+}
+
+impl<S> DerefMut for TokioStream<S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.stream
+    }
+}
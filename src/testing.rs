@@ -0,0 +1,106 @@
+//! This module contains a [`CliTest`] which helps to test CLIs producing files.
+//!
+//! A command is spawned inside a temporary directory,
+//! so its output can be checked via expect calls
+//! and the files it created can be checked once it's done.
+//!
+//! # Example
+//!
+#![cfg_attr(feature = "async", doc = "```ignore")]
+#![cfg_attr(not(feature = "async"), doc = "```no_run")]
+//! use std::process::Command;
+//! use expectrl::{testing::CliTest, Eof, Expect};
+//!
+//! let test = CliTest::in_temp_dir().unwrap();
+//!
+//! let mut cmd = Command::new("sh");
+//! cmd.args(["-c", "echo Hello > hello.txt; echo created"]);
+//!
+//! let mut p = test.spawn(cmd).unwrap();
+//! p.expect("created").unwrap();
+//! p.expect(Eof).unwrap();
+//!
+//! assert_eq!(test.read_to_string("hello.txt").unwrap(), "Hello\n");
+//! ```
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use tempfile::TempDir;
+
+use crate::{session::OsSession, Error, Session};
+
+/// A test environment which runs commands in its own temporary directory.
+///
+/// The directory and all its content are removed when it's dropped.
+#[derive(Debug)]
+pub struct CliTest {
+    dir: TempDir,
+}
+
+impl CliTest {
+    /// Creates a new empty temporary directory.
+    pub fn in_temp_dir() -> io::Result<Self> {
+        let dir = tempfile::tempdir()?;
+        Ok(Self { dir })
+    }
+
+    /// Returns a path of the temporary directory.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Returns a path of a file relative to the temporary directory.
+    pub fn child<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.dir.path().join(path)
+    }
+
+    /// Verifies whether a file (or a directory) exists in the temporary directory.
+    pub fn exists<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.child(path).exists()
+    }
+
+    /// Reads a file from the temporary directory.
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> io::Result<Vec<u8>> {
+        fs::read(self.child(path))
+    }
+
+    /// Reads a file from the temporary directory as a string.
+    pub fn read_to_string<P: AsRef<Path>>(&self, path: P) -> io::Result<String> {
+        fs::read_to_string(self.child(path))
+    }
+
+    /// Writes a file into the temporary directory, e.g. an input for a command.
+    pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, content: C) -> io::Result<()> {
+        fs::write(self.child(path), content)
+    }
+
+    /// Spawns a command with the temporary directory as its working directory.
+    pub fn spawn(&self, mut command: Command) -> Result<OsSession, Error> {
+        let _ = command.current_dir(self.path());
+        Session::spawn(command)
+    }
+
+    /// Spawns a program with the temporary directory as its working directory,
+    /// letting a closure configure the [`Command`] further.
+    ///
+    /// See [`Session::spawn_with`].
+    pub fn spawn_with<F>(&self, program: &str, configure: F) -> Result<OsSession, Error>
+    where
+        F: FnOnce(&mut Command),
+    {
+        Session::spawn_with(program, |command| {
+            let _ = command.current_dir(self.path());
+            configure(command);
+        })
+    }
+
+    /// Removes the temporary directory reporting an error if it fails,
+    /// unlike dropping which ignores it.
+    pub fn close(self) -> io::Result<()> {
+        self.dir.close()
+    }
+}
@@ -0,0 +1,233 @@
+//! Internal helpers for an optional [`tracing`] instrumentation.
+//!
+//! When `tracing` feature is off all the functions are no-op wrappers.
+//!
+//! [`tracing`]: https://docs.rs/tracing
+
+use std::fmt::Debug;
+
+#[cfg(feature = "async")]
+use std::future::Future;
+
+use crate::{Captures, Error, Needle};
+
+/// Runs an expect call within a `expect` span.
+#[cfg(all(not(feature = "async"), feature = "tracing"))]
+pub(crate) fn expect<N, F>(needle: &N, f: F) -> Result<Captures, Error>
+where
+    N: Needle,
+    F: FnOnce() -> Result<Captures, Error>,
+{
+    let _span = expect_span(needle).entered();
+    let result = f();
+    expect_outcome(&result);
+    result
+}
+
+/// Runs an expect call within a `expect` span.
+#[cfg(all(not(feature = "async"), not(feature = "tracing")))]
+pub(crate) fn expect<N, F>(_: &N, f: F) -> Result<Captures, Error>
+where
+    N: Needle,
+    F: FnOnce() -> Result<Captures, Error>,
+{
+    f()
+}
+
+/// Runs an expect future within a `expect` span.
+#[cfg(all(feature = "async", feature = "tracing"))]
+pub(crate) async fn expect_async<N, F>(needle: &N, f: F) -> Result<Captures, Error>
+where
+    N: Needle,
+    F: Future<Output = Result<Captures, Error>>,
+{
+    use tracing::Instrument;
+
+    async {
+        let result = f.await;
+        expect_outcome(&result);
+        result
+    }
+    .instrument(expect_span(needle))
+    .await
+}
+
+/// Runs an expect future within a `expect` span.
+#[cfg(all(feature = "async", not(feature = "tracing")))]
+pub(crate) async fn expect_async<N, F>(_: &N, f: F) -> Result<Captures, Error>
+where
+    N: Needle,
+    F: Future<Output = Result<Captures, Error>>,
+{
+    f.await
+}
+
+/// Runs a spawn call within a `spawn` span.
+#[cfg(feature = "tracing")]
+pub(crate) fn spawn<T, F>(command: &dyn Debug, f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error>,
+{
+    let _span = tracing::debug_span!("spawn", command = ?command).entered();
+    let result = f();
+    match &result {
+        Ok(_) => tracing::debug!("process spawned"),
+        Err(err) => tracing::debug!(error = %err, "spawn failed"),
+    }
+
+    result
+}
+
+/// Runs a spawn call within a `spawn` span.
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn spawn<T, F>(_: &dyn Debug, f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error>,
+{
+    f()
+}
+
+/// Records bytes sent to a process.
+#[cfg(feature = "tracing")]
+pub(crate) fn send(buf: &[u8]) {
+    tracing::debug!(bytes = ?String::from_utf8_lossy(buf), "send");
+}
+
+/// Records bytes sent to a process.
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn send(_: &[u8]) {}
+
+/// Records a secret sent to a process, only its length is recorded.
+#[cfg(feature = "tracing")]
+pub(crate) fn send_secret(buf: &[u8]) {
+    tracing::debug!(len = buf.len(), "send secret");
+}
+
+/// Records a secret sent to a process, only its length is recorded.
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn send_secret(_: &[u8]) {}
+
+#[cfg(feature = "tracing")]
+fn expect_span<N: Needle>(needle: &N) -> tracing::Span {
+    tracing::debug_span!("expect", needle = %needle.describe())
+}
+
+#[cfg(feature = "tracing")]
+fn expect_outcome(result: &Result<Captures, Error>) {
+    match result {
+        Ok(found) => tracing::debug!(
+            before = ?String::from_utf8_lossy(found.before()),
+            matched = ?found.get(0).map(String::from_utf8_lossy),
+            "expect matched"
+        ),
+        Err(err) => tracing::debug!(error = %err, "expect failed"),
+    }
+}
+
+#[cfg(all(test, feature = "tracing", not(feature = "async")))]
+mod tests {
+    use std::{
+        fmt::Write,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    use super::*;
+    use crate::{needle::Match, Any, NoCase};
+
+    /// Recorder keeps spans and events as `name field=value ...` lines.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Recorder {
+        fn lines(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    struct Fields<'a>(&'a mut String);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            let mut line = span.metadata().name().to_owned();
+            span.record(&mut Fields(&mut line));
+            self.0.lock().unwrap().push(line);
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut line = String::from("event");
+            event.record(&mut Fields(&mut line));
+            self.0.lock().unwrap().push(line);
+        }
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    fn trace_expect<N: Needle>(needle: N) -> Vec<String> {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let found = Captures::new(b"Hello World".to_vec(), vec![Match::new(6, 11)]);
+            let _ = expect(&needle, || Ok(found));
+        });
+
+        recorder.lines()
+    }
+
+    #[test]
+    fn test_expect_span() {
+        let lines = trace_expect("World");
+        assert_eq!(lines[0], r#"expect needle="World""#);
+        assert_eq!(
+            lines[1],
+            r#"event message=expect matched before="Hello " matched=Some("World")"#
+        );
+
+        assert_eq!(
+            trace_expect(NoCase("world"))[0],
+            r#"expect needle=NoCase("world")"#
+        );
+        assert_eq!(
+            trace_expect(Any(["Hello", "World"]))[0],
+            r#"expect needle=Any(["Hello", "World"])"#
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_send_line_no_echo_hides_secret() {
+        let mut session = crate::spawn("cat").unwrap();
+
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            session.send_line_no_echo("hunter2").unwrap();
+        });
+
+        let lines = recorder.lines();
+        assert!(lines.contains(&"event message=send secret len=7".to_owned()));
+        assert!(
+            lines.iter().all(|line| !line.contains("hunter2")),
+            "{lines:?}"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! This module contains helpers to clean up an output of a process,
+//! like [`strip_ansi`] which removes ANSI escape sequences (colors, cursor movements, titles).
+//!
+//! To match an output regardless of escape sequences use [`Stripped`] needle,
+//! and to get rid of them in an interactive mode use [`strip_ansi_filter`].
+//!
+//! [`Stripped`]: crate::Stripped
+
+use std::borrow::Cow;
+
+use crate::Error;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+/// Removes ANSI escape sequences from a buffer.
+///
+/// `CSI` sequences (like colors and cursor movements), `OSC` sequences (like a window title),
+/// `DCS`/`SOS`/`PM`/`APC` strings and 2 byte escapes are removed.
+/// An incomplete sequence at the end of the buffer is removed as well.
+///
+/// # Example
+///
+/// ```
+/// use expectrl::util::strip_ansi;
+///
+/// let stripped = strip_ansi(b"\x1b[1;31mError\x1b[0m: \x1b]0;title\x07not found");
+/// assert_eq!(stripped, b"Error: not found");
+/// ```
+pub fn strip_ansi(buf: &[u8]) -> Vec<u8> {
+    let mut stripped = Vec::with_capacity(buf.len());
+    let _ = strip(buf, |i| stripped.push(buf[i]));
+    stripped
+}
+
+/// Returns an output filter which removes ANSI escape sequences,
+/// to be used with [`InteractSession::set_output_filter`].
+///
+/// A sequence may be split between reads,
+/// so an incomplete sequence at the end of a chunk is held until the next chunk comes.
+/// The held bytes are dropped if no chunk comes after them,
+/// e.g. when the process exits in the middle of a sequence.
+///
+/// # Example
+///
+#[cfg_attr(feature = "async", doc = "```ignore")]
+#[cfg_attr(not(feature = "async"), doc = "```no_run")]
+/// use std::io::stdout;
+/// use expectrl::{spawn, stream::stdin::Stdin, util::strip_ansi_filter};
+///
+/// let mut p = spawn("ls --color=always").unwrap();
+/// let mut stdin = Stdin::open().unwrap();
+///
+/// p.interact(&mut stdin, stdout())
+///     .set_output_filter(strip_ansi_filter())
+///     .spawn()
+///     .unwrap();
+///
+/// stdin.close().unwrap();
+/// ```
+///
+/// [`InteractSession::set_output_filter`]: crate::interact::InteractSession::set_output_filter
+pub fn strip_ansi_filter() -> impl FnMut(&[u8]) -> Result<Cow<'_, [u8]>, Error> {
+    let mut pending = Vec::new();
+    move |buf| {
+        pending.extend_from_slice(buf);
+
+        let mut stripped = Vec::with_capacity(pending.len());
+        let end = strip(&pending, |i| stripped.push(pending[i]));
+        drop(pending.drain(..end));
+
+        Ok(Cow::Owned(stripped))
+    }
+}
+
+/// Removes ANSI escape sequences from a buffer,
+/// returning the left bytes along with their indexes in the buffer.
+pub(crate) fn strip_ansi_indexed(buf: &[u8]) -> (Vec<u8>, Vec<usize>) {
+    let mut stripped = Vec::with_capacity(buf.len());
+    let mut index = Vec::with_capacity(buf.len());
+    let _ = strip(buf, |i| {
+        stripped.push(buf[i]);
+        index.push(i);
+    });
+
+    (stripped, index)
+}
+
+/// Calls `keep` with an index of each byte which isn't a part of an escape sequence.
+///
+/// Returns a position of an incomplete sequence at the end of the buffer,
+/// or the buffer length if there's none.
+fn strip(buf: &[u8], mut keep: impl FnMut(usize)) -> usize {
+    let mut i = 0;
+    while i < buf.len() {
+        if buf[i] == ESC {
+            match escape_len(&buf[i..]) {
+                Some(n) => {
+                    i += n;
+                    continue;
+                }
+                None => return i,
+            }
+        }
+
+        keep(i);
+        i += 1;
+    }
+
+    buf.len()
+}
+
+/// Returns a length of an escape sequence at the start of the buffer,
+/// or [`None`] if it's not complete yet.
+///
+/// A malformed sequence ends right before the byte which breaks it.
+fn escape_len(buf: &[u8]) -> Option<usize> {
+    match *buf.get(1)? {
+        // CSI: parameters and intermediates are followed by a final byte
+        b'[' => {
+            let pos = buf[2..].iter().position(|b| !(0x20..=0x3f).contains(b))?;
+            match buf[pos + 2] {
+                0x40..=0x7e => Some(pos + 3),
+                _ => Some(pos + 2),
+            }
+        }
+        // OSC, DCS, SOS, PM and APC: a string which ends with ST or BEL
+        b']' | b'P' | b'X' | b'^' | b'_' => {
+            let pos = buf[2..].iter().position(|&b| b == BEL || b == ESC)? + 2;
+            match (buf[pos], buf.get(pos + 1)) {
+                (BEL, _) => Some(pos + 1),
+                (_, Some(b'\\')) => Some(pos + 2),
+                (_, Some(_)) => Some(pos),
+                (_, None) => None,
+            }
+        }
+        // nF escapes, like a character set designation `ESC ( B`
+        0x20..=0x2f => {
+            let pos = buf[2..].iter().position(|b| !(0x20..=0x2f).contains(b))?;
+            match buf[pos + 2] {
+                0x30..=0x7e => Some(pos + 3),
+                _ => Some(pos + 2),
+            }
+        }
+        0x30..=0x7e => Some(2),
+        _ => Some(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi(b"Hello World"), b"Hello World");
+        assert_eq!(strip_ansi(b"\x1b[31mHello\x1b[m World"), b"Hello World");
+        assert_eq!(strip_ansi(b"\x1b[?25l\x1b[2J\x1b[1;1HHello"), b"Hello");
+        assert_eq!(strip_ansi(b"\x1b]0;title\x07Hello"), b"Hello");
+        assert_eq!(strip_ansi(b"\x1b]0;title\x1b\\Hello"), b"Hello");
+        assert_eq!(strip_ansi(b"\x1bP1$r0m\x1b\\Hello"), b"Hello");
+        assert_eq!(strip_ansi(b"\x1b(BHello\x1b=\x1b>"), b"Hello");
+        assert_eq!(strip_ansi(b"Hello\x1b[3"), b"Hello");
+        assert_eq!(strip_ansi(b"Hello\x1b"), b"Hello");
+        assert_eq!(strip_ansi(b"\x1b[3\nHello"), b"\nHello");
+        assert_eq!(strip_ansi(b"\x1b\rHello"), b"\rHello");
+        assert_eq!(strip_ansi("\x1b[1mПривет".as_bytes()), "Привет".as_bytes());
+    }
+
+    #[test]
+    fn test_strip_ansi_indexed() {
+        let (stripped, index) = strip_ansi_indexed(b"a\x1b[1mb\x1b[mc");
+        assert_eq!(stripped, b"abc");
+        assert_eq!(index, [0, 5, 9]);
+    }
+
+    #[test]
+    fn test_strip_ansi_filter() {
+        let mut filter = strip_ansi_filter();
+        assert_eq!(filter(b"\x1b[31mHello\x1b[").unwrap().as_ref(), b"Hello");
+        assert_eq!(filter(b"m World\x1b]0;ti").unwrap().as_ref(), b" World");
+        assert_eq!(filter(b"tle\x07!").unwrap().as_ref(), b"!");
+        assert_eq!(filter(b"").unwrap().as_ref(), b"");
+    }
+}
@@ -201,6 +201,52 @@ fn check_eof_timeout() {
     })
 }
 
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn peek_doesnt_consume() {
+    let mut session = spawn("cat").unwrap();
+    session.send_line("Hello World").unwrap();
+
+    thread::sleep(Duration::from_millis(600));
+
+    let f = session.peek("World").unwrap();
+    assert_eq!(f.before(), b"Hello ");
+    assert_eq!(f.get(0).unwrap(), b"World");
+
+    let f = session.peek("World").unwrap();
+    assert_eq!(f.before(), b"Hello ");
+
+    assert!(session.peek("Bye").unwrap().is_empty());
+
+    let f = session.expect("Hello").unwrap();
+    assert_eq!(f.before(), b"");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn peek_doesnt_consume() {
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.send_line("Hello World").await.unwrap();
+
+        thread::sleep(Duration::from_millis(600));
+
+        let f = session.peek("World").await.unwrap();
+        assert_eq!(f.before(), b"Hello ");
+        assert_eq!(f.get(0).unwrap(), b"World");
+
+        let f = session.peek("World").await.unwrap();
+        assert_eq!(f.before(), b"Hello ");
+
+        assert!(session.peek("Bye").await.unwrap().is_empty());
+
+        let f = session.expect("Hello").await.unwrap();
+        assert_eq!(f.before(), b"");
+    })
+}
+
 #[cfg(unix)]
 #[cfg(not(feature = "async"))]
 #[test]
@@ -101,6 +101,32 @@ fn expect_lazy_regex() {
     })
 }
 
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_regex_groups() {
+    let mut session = spawn("echo port=8080").unwrap();
+    let m = session.expect(Regex(r"(\w+)=(?P<port>\d+)")).unwrap();
+    assert_eq!(m.group(1), Some(b"port".as_ref()));
+    assert_eq!(m.group(2), Some(b"8080".as_ref()));
+    assert_eq!(m.name("port"), Some(b"8080".as_ref()));
+    assert_eq!(m.name("host"), None);
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_regex_groups() {
+    futures_lite::future::block_on(async {
+        let mut session = spawn("echo port=8080").unwrap();
+        let m = session.expect(Regex(r"(\w+)=(?P<port>\d+)")).await.unwrap();
+        assert_eq!(m.group(1), Some(b"port".as_ref()));
+        assert_eq!(m.group(2), Some(b"8080".as_ref()));
+        assert_eq!(m.name("port"), Some(b"8080".as_ref()));
+        assert_eq!(m.name("host"), None);
+    })
+}
+
 #[cfg(windows)]
 #[test]
 fn expect_regex() {
@@ -122,6 +148,128 @@ fn expect_regex() {
     }
 }
 
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_any_of_strings() {
+    let mut session = spawn("cat").unwrap();
+    session.send_line("Do you agree? no").unwrap();
+    let m = session.expect(&["yes", "no"][..]).unwrap();
+    assert_eq!(m.needle_index(), Some(1));
+    assert_eq!(m.get(0).unwrap(), b"no");
+
+    session.send_line("yes").unwrap();
+    let needles = vec!["maybe".to_string(), "yes".to_string()];
+    let m = session.expect(needles).unwrap();
+    assert_eq!(m.needle_index(), Some(1));
+
+    session.send_line("Hello").unwrap();
+    let m = session.expect("Hello").unwrap();
+    assert_eq!(m.needle_index(), None);
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_any_of_strings() {
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.send_line("Do you agree? no").await.unwrap();
+        let m = session.expect(&["yes", "no"][..]).await.unwrap();
+        assert_eq!(m.needle_index(), Some(1));
+        assert_eq!(m.get(0).unwrap(), b"no");
+
+        session.send_line("yes").await.unwrap();
+        let needles = vec!["maybe".to_string(), "yes".to_string()];
+        let m = session.expect(needles).await.unwrap();
+        assert_eq!(m.needle_index(), Some(1));
+
+        session.send_line("Hello").await.unwrap();
+        let m = session.expect("Hello").await.unwrap();
+        assert_eq!(m.needle_index(), None);
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_any() {
+    use expectrl::Needle;
+
+    let mut session = spawn("cat").unwrap();
+    session.send_line("Do you agree? no").unwrap();
+    let needles: Vec<Box<dyn Needle>> =
+        vec![Box::new("yes"), Box::new(Regex("n.")), Box::new("no")];
+    let (index, found) = session.expect_any(needles).unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(&found[0], b"no");
+    assert_eq!(found.before(), b"Do you agree? ");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_any() {
+    use expectrl::Needle;
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.send_line("Do you agree? no").await.unwrap();
+        let needles: Vec<Box<dyn Needle>> =
+            vec![Box::new("yes"), Box::new(Regex("n.")), Box::new("no")];
+        let (index, found) = session.expect_any(needles).await.unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(&found[0], b"no");
+        assert_eq!(found.before(), b"Do you agree? ");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_until_signal() {
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.send_line("Hello World").await.unwrap();
+
+        let cancel = futures_timer::Delay::new(Duration::from_millis(300));
+        let found = session.expect_until_signal("Bye", cancel).await.unwrap();
+        assert!(found.is_none());
+
+        let found = session
+            .expect_until_signal("World", futures_lite::future::pending())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.before(), b"Hello ");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_cancellation_safety() {
+    for lazy in [false, true] {
+        futures_lite::future::block_on(async {
+            let mut session = spawn("cat").unwrap();
+            session.set_expect_lazy(lazy);
+            session.send("Hello ").await.unwrap();
+
+            let cancelled =
+                futures_lite::future::or(async { Some(session.expect("World").await) }, async {
+                    futures_timer::Delay::new(Duration::from_millis(300)).await;
+                    None
+                })
+                .await;
+            assert!(cancelled.is_none());
+
+            session.send_line("World").await.unwrap();
+            let found = session.expect("Hello World").await.unwrap();
+            assert_eq!(found.before(), b"");
+        })
+    }
+}
+
 #[cfg(unix)]
 #[cfg(not(feature = "async"))]
 #[test]
@@ -337,3 +485,1090 @@ fn expect_eof_timeout() {
         })
     }
 }
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn spawn_expect() {
+    let m = expectrl::spawn_expect("echo Hello World", "World", Duration::from_secs(5)).unwrap();
+    assert_eq!(m.get(0).unwrap(), b"World");
+
+    let err =
+        expectrl::spawn_expect("echo Hello World", "Bye", Duration::from_secs(5)).unwrap_err();
+    assert!(matches!(err.root(), expectrl::Error::Eof), "{:?}", err);
+
+    let err = err.to_string();
+    assert!(err.contains("echo Hello World"), "{:?}", err);
+    assert!(err.contains("Hello World\\r\\n"), "{:?}", err);
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn spawn_expect() {
+    futures_lite::future::block_on(async {
+        let m = expectrl::spawn_expect("echo Hello World", "World", Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(m.get(0).unwrap(), b"World");
+
+        let err = expectrl::spawn_expect("echo Hello World", "Bye", Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(matches!(err.root(), expectrl::Error::Eof), "{:?}", err);
+
+        let err = err.to_string();
+        assert!(err.contains("echo Hello World"), "{:?}", err);
+        assert!(err.contains("Hello World\\r\\n"), "{:?}", err);
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_until() {
+    let mut session = sh("echo 1; echo 2; echo 3; echo DONE; echo 4");
+
+    let lines = session
+        .expect_until('\n', "DONE")
+        .map(|m| String::from_utf8_lossy(m.unwrap().before()).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(lines, ["1\r", "2\r", "3\r"]);
+
+    session.expect("4").unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_until_eof() {
+    let mut session = sh("echo 1; echo 2");
+
+    let lines = session.expect_until('\n', "DONE").collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(matches!(lines[2], Err(expectrl::Error::Eof)));
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_until() {
+    use futures_lite::StreamExt;
+
+    futures_lite::future::block_on(async {
+        let mut session = sh("echo 1; echo 2; echo 3; echo DONE; echo 4");
+
+        let lines = session
+            .expect_until('\n', "DONE")
+            .map(|m| String::from_utf8_lossy(m.unwrap().before()).to_string())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(lines, ["1\r", "2\r", "3\r"]);
+
+        session.expect("4").await.unwrap();
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_max_read_iterations() {
+    let mut session = sh("for i in 1 2 3 4 5; do echo $i; sleep 0.2; done");
+    session.set_max_read_iterations(Some(2));
+    let err = session.expect("Hello").unwrap_err();
+    assert!(matches!(err, expectrl::Error::MaxIterations), "{err:?}");
+
+    session.set_max_read_iterations(None);
+    session.expect("5").unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_max_read_iterations() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("for i in 1 2 3 4 5; do echo $i; sleep 0.2; done");
+        session.set_max_read_iterations(Some(2));
+        let err = session.expect("Hello").await.unwrap_err();
+        assert!(matches!(err, expectrl::Error::MaxIterations), "{err:?}");
+
+        session.set_max_read_iterations(None);
+        session.expect("5").await.unwrap();
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_max_read_iterations_lazy() {
+    let mut session = sh("echo Hello World; sleep 0.2; echo 1; sleep 0.2; echo 2");
+    session.set_expect_lazy(true);
+    session.set_max_read_iterations(Some(3));
+    session.expect("1").unwrap();
+
+    session.set_max_read_iterations(Some(1));
+    let err = session.expect("3").unwrap_err();
+    assert!(matches!(err, expectrl::Error::MaxIterations), "{err:?}");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_max_read_iterations_lazy() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("echo Hello World; sleep 0.2; echo 1; sleep 0.2; echo 2");
+        session.set_expect_lazy(true);
+        session.set_max_read_iterations(Some(3));
+        session.expect("1").await.unwrap();
+
+        session.set_max_read_iterations(Some(1));
+        let err = session.expect("3").await.unwrap_err();
+        assert!(matches!(err, expectrl::Error::MaxIterations), "{err:?}");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_any_output() {
+    let mut session = sh("sleep 0.3; echo Hello");
+    let output = session.expect_any_output(Duration::from_secs(5)).unwrap();
+    assert!(!output.is_empty());
+    assert!(b"Hello\r\n".starts_with(&output), "{output:?}");
+
+    let mut session = spawn("sleep 5").unwrap();
+    let err = session
+        .expect_any_output(Duration::from_millis(100))
+        .unwrap_err();
+    assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+    assert!(session.dump().contains("expect timeout: 10s"));
+
+    let mut session = sh("true");
+    let err = session
+        .expect_any_output(Duration::from_secs(5))
+        .unwrap_err();
+    assert!(matches!(err, expectrl::Error::Eof), "{err:?}");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_any_output() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("sleep 0.3; echo Hello");
+        let output = session
+            .expect_any_output(Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(!output.is_empty());
+        assert!(b"Hello\r\n".starts_with(&output), "{output:?}");
+
+        let mut session = spawn("sleep 5").unwrap();
+        let err = session
+            .expect_any_output(Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+        assert!(session.dump().contains("expect timeout: 10s"));
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn send_expect_retry() {
+    let mut session = sh("read a; read b; echo got $b");
+    let found = session
+        .send_expect_retry("ping", "got ping", 2, Duration::from_millis(300))
+        .unwrap();
+    assert_eq!(found.get(0), Some(b"got ping".as_ref()));
+
+    let mut session = spawn("sleep 5").unwrap();
+    let now = std::time::Instant::now();
+    let err = session
+        .send_expect_retry("ping", "pong", 1, Duration::from_millis(100))
+        .unwrap_err();
+    assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+    assert!(now.elapsed() >= Duration::from_millis(200));
+    assert!(session.dump().contains("expect timeout: 10s"));
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn send_expect_retry() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("read a; read b; echo got $b");
+        let found = session
+            .send_expect_retry("ping", "got ping", 2, Duration::from_millis(300))
+            .await
+            .unwrap();
+        assert_eq!(found.get(0), Some(b"got ping".as_ref()));
+
+        let mut session = spawn("sleep 5").unwrap();
+        let now = std::time::Instant::now();
+        let err = session
+            .send_expect_retry("ping", "pong", 1, Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+        assert!(now.elapsed() >= Duration::from_millis(200));
+    })
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn sync_adapter() {
+    use expectrl::{process::Healthcheck, SyncAdapter};
+
+    let mut session = SyncAdapter::new(spawn("cat").unwrap());
+    session.send_line("Hello World").unwrap();
+    let found = Expect::expect(&mut session, "World").unwrap();
+    assert_eq!(found.before(), b"Hello ");
+    assert!(session.get_mut().is_alive().unwrap());
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn autorespond() {
+    use expectrl::Needle;
+
+    let mut session =
+        sh("for q in name age name; do echo \"$q?\"; read a; echo \"got $a\"; done; echo done");
+    let rules: Vec<(Box<dyn Needle>, String)> = vec![
+        (Box::new("name?"), String::from("bob")),
+        (Box::new("age?"), String::from("42")),
+    ];
+    session
+        .autorespond(rules, "done", Duration::from_secs(5))
+        .unwrap();
+
+    let mut session = spawn("sleep 5").unwrap();
+    let err = session
+        .autorespond(Vec::new(), "done", Duration::from_millis(100))
+        .unwrap_err();
+    assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+    assert!(session.dump().contains("expect timeout: 10s"));
+
+    let mut session = sh("echo name?; read a");
+    let rules: Vec<(Box<dyn Needle>, String)> = vec![(Box::new("name?"), String::from("bob"))];
+    let err = session
+        .autorespond(rules, "done", Duration::from_secs(5))
+        .unwrap_err();
+    assert!(matches!(err, expectrl::Error::Eof), "{err:?}");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn autorespond() {
+    use expectrl::Needle;
+
+    futures_lite::future::block_on(async {
+        let mut session =
+            sh("for q in name age name; do echo \"$q?\"; read a; echo \"got $a\"; done; echo done");
+        let rules: Vec<(Box<dyn Needle>, String)> = vec![
+            (Box::new("name?"), String::from("bob")),
+            (Box::new("age?"), String::from("42")),
+        ];
+        session
+            .autorespond(rules, "done", Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        let mut session = spawn("sleep 5").unwrap();
+        let err = session
+            .autorespond(Vec::new(), "done", Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn collapse_cr() {
+    let mut session = sh("printf '10%%\\r20%%\\r'; sleep 0.2; printf '100%%\\r\\n'");
+    session.set_collapse_cr(true);
+    let found = session.expect("100%\r\n").unwrap();
+    assert_eq!(found.before(), b"10%\r20%\r");
+
+    let mut session = sh("printf '1000\\r99\\r\\n'");
+    session.set_expect_timeout(Some(Duration::from_millis(300)));
+    session.set_collapse_cr(true);
+    assert!(session.expect("1000").is_err());
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn collapse_cr() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("printf '10%%\\r20%%\\r'; sleep 0.2; printf '100%%\\r\\n'");
+        session.set_collapse_cr(true);
+        let found = session.expect("100%\r\n").await.unwrap();
+        assert_eq!(found.before(), b"10%\r20%\r");
+    })
+}
+
+#[cfg(all(unix, feature = "render"))]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_screen() {
+    let mut session = sh("printf 'Loading...\\033[1G\\033[KDone\\033[5;3HStatus: ok'; sleep 5");
+    session.expect_screen("Done\n\n\n\n  Status: ok").unwrap();
+
+    let mut session = sh("printf 'Loading\\033[1GL'");
+    session.set_expect_timeout(Some(Duration::from_millis(300)));
+    session.expect_screen("Loading").unwrap();
+    let err = session.expect_screen("Done").unwrap_err();
+    assert!(matches!(err, expectrl::Error::Eof), "{err:?}");
+}
+
+#[cfg(all(unix, feature = "render"))]
+#[cfg(not(feature = "async"))]
+#[test]
+fn screen() {
+    let mut session = sh("printf 'top\\033[3;2Hbottom\\033[2;1H'; sleep 5");
+    session.expect("\x1b[2;1H").unwrap();
+
+    let screen = session.screen().unwrap();
+    assert_eq!(screen.row(0).unwrap(), "top");
+    assert_eq!(screen.row(2).unwrap(), " bottom");
+    assert_eq!(screen.cursor(), (1, 0));
+}
+
+#[cfg(all(unix, feature = "render"))]
+#[cfg(feature = "async")]
+#[test]
+fn screen() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("printf 'top\\033[3;2Hbottom\\033[2;1H'; sleep 5");
+        session.expect("\x1b[2;1H").await.unwrap();
+
+        let screen = session.screen().await.unwrap();
+        assert_eq!(screen.row(0).unwrap(), "top");
+        assert_eq!(screen.row(2).unwrap(), " bottom");
+        assert_eq!(screen.cursor(), (1, 0));
+    })
+}
+
+#[cfg(all(unix, feature = "render"))]
+#[cfg(feature = "async")]
+#[test]
+fn expect_screen() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("printf 'Loading...\\033[1G\\033[KDone\\033[5;3HStatus: ok'; sleep 5");
+        session
+            .expect_screen("Done\n\n\n\n  Status: ok")
+            .await
+            .unwrap();
+    })
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_after_process_exit() {
+    for lazy in [false, true] {
+        let mut session = spawn("echo Hello World").unwrap();
+        session.set_expect_lazy(lazy);
+        session
+            .wait_for_exit_code(0, Duration::from_secs(5))
+            .unwrap();
+
+        let found = session.expect("World").unwrap();
+        assert_eq!(found.before(), b"Hello ");
+        let found = session.expect(Eof).unwrap();
+        assert_eq!(found.get(0), Some(b"\r\n".as_ref()));
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(feature = "async")]
+#[test]
+fn expect_after_process_exit() {
+    futures_lite::future::block_on(async {
+        for lazy in [false, true] {
+            let mut session = spawn("echo Hello World").unwrap();
+            session.set_expect_lazy(lazy);
+            session
+                .wait_for_exit_code(0, Duration::from_secs(5))
+                .unwrap();
+
+            let found = session.expect("World").await.unwrap();
+            assert_eq!(found.before(), b"Hello ");
+            let found = session.expect(Eof).await.unwrap();
+            assert_eq!(found.get(0), Some(b"\r\n".as_ref()));
+        }
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_with_progress() {
+    let mut session = sh("echo started; sleep 0.5; echo done");
+    let mut calls = Vec::new();
+    let found = session
+        .expect_with_progress("done", Duration::from_millis(100), |buf| {
+            calls.push(String::from_utf8_lossy(buf).into_owned())
+        })
+        .unwrap();
+    assert_eq!(found.before(), b"started\r\n");
+    assert!(calls.len() >= 2, "{calls:?}");
+    assert!(calls.iter().all(|buf| buf == "started\r\n"), "{calls:?}");
+
+    let mut session = spawn("sleep 5").unwrap();
+    session.set_expect_timeout(Some(Duration::from_millis(250)));
+    let mut calls = 0;
+    let err = session
+        .expect_with_progress("never", Duration::from_millis(100), |_| calls += 1)
+        .unwrap_err();
+    assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+    assert_eq!(calls, 2);
+    assert!(session.dump().contains("expect timeout: 250ms"));
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_with_progress() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("echo started; sleep 0.5; echo done");
+        let mut calls = Vec::new();
+        let found = session
+            .expect_with_progress("done", Duration::from_millis(100), |buf| {
+                calls.push(String::from_utf8_lossy(buf).into_owned())
+            })
+            .await
+            .unwrap();
+        assert_eq!(found.before(), b"started\r\n");
+        assert!(calls.len() >= 2, "{calls:?}");
+        assert!(calls.iter().all(|buf| buf == "started\r\n"), "{calls:?}");
+
+        let mut session = spawn("sleep 5").unwrap();
+        session.set_expect_timeout(Some(Duration::from_millis(250)));
+        let mut calls = 0;
+        let err = session
+            .expect_with_progress("never", Duration::from_millis(100), |_| calls += 1)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+        assert_eq!(calls, 2);
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn timeout_discards_buffer() {
+    let mut session = spawn("cat").unwrap();
+    session.set_expect_timeout(Some(Duration::from_millis(300)));
+    session.send_line("Hello World").unwrap();
+    let err = session.expect("Bye").unwrap_err();
+    assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+    session.expect("World").unwrap();
+
+    session.set_timeout_discards_buffer(true);
+    session.send_line("Hello World").unwrap();
+    let err = session.expect("Bye").unwrap_err();
+    assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+    let err = session.expect("World").unwrap_err();
+    assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn timeout_discards_buffer() {
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.set_expect_timeout(Some(Duration::from_millis(300)));
+        session.send_line("Hello World").await.unwrap();
+        let err = session.expect("Bye").await.unwrap_err();
+        assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+        session.expect("World").await.unwrap();
+
+        session.set_timeout_discards_buffer(true);
+        session.send_line("Hello World").await.unwrap();
+        let err = session.expect("Bye").await.unwrap_err();
+        assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+        let err = session.expect("World").await.unwrap_err();
+        assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_nth() {
+    let mut session = sh("echo 'prompt> a'; echo 'prompt> b'; echo 'prompt> c'");
+    let found = session.expect_nth("prompt> ", 2).unwrap();
+    assert_eq!(found.get(0), Some(b"prompt> ".as_ref()));
+    assert_eq!(found.before(), b"prompt> a\r\n");
+
+    let found = session.expect("\r\n").unwrap();
+    assert_eq!(found.before(), b"b");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_nth() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("echo 'prompt> a'; echo 'prompt> b'; echo 'prompt> c'");
+        let found = session.expect_nth("prompt> ", 2).await.unwrap();
+        assert_eq!(found.get(0), Some(b"prompt> ".as_ref()));
+        assert_eq!(found.before(), b"prompt> a\r\n");
+
+        let found = session.expect("\r\n").await.unwrap();
+        assert_eq!(found.before(), b"b");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn confirm() {
+    let script = "printf 'Continue? '; read a; echo got $a; printf 'Sure? '; read b; echo got $b";
+
+    let mut session = sh(script);
+    session.confirm("Continue? ", true).unwrap();
+    session.expect("got yes").unwrap();
+    session.confirm("Sure? ", false).unwrap();
+    session.expect("got no").unwrap();
+
+    let mut session = sh(script);
+    session.set_confirm_answers("Y", "n");
+    session.confirm("Continue? ", true).unwrap();
+    session.expect("got Y").unwrap();
+    session.confirm("Sure? ", false).unwrap();
+    session.expect("got n").unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn confirm() {
+    futures_lite::future::block_on(async {
+        let script =
+            "printf 'Continue? '; read a; echo got $a; printf 'Sure? '; read b; echo got $b";
+
+        let mut session = sh(script);
+        session.confirm("Continue? ", true).await.unwrap();
+        session.expect("got yes").await.unwrap();
+        session.confirm("Sure? ", false).await.unwrap();
+        session.expect("got no").await.unwrap();
+
+        let mut session = sh(script);
+        session.set_confirm_answers("Y", "n");
+        session.confirm("Continue? ", true).await.unwrap();
+        session.expect("got Y").await.unwrap();
+        session.confirm("Sure? ", false).await.unwrap();
+        session.expect("got n").await.unwrap();
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn send_line_wait_echo() {
+    use expectrl::process::Termios;
+
+    let mut session = spawn("cat").unwrap();
+    let _ = session.set_echo(true).unwrap();
+    session
+        .send_line_wait_echo("Hello", Duration::from_secs(1))
+        .unwrap();
+    let found = session.expect("Hello").unwrap();
+    assert_eq!(found.before(), b"\r\n");
+
+    let mut session = spawn("sleep 5").unwrap();
+    let err = session
+        .send_line_wait_echo("Hello", Duration::from_millis(300))
+        .unwrap_err();
+    assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+    assert!(session.dump().contains("expect timeout: 10s"));
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn send_line_wait_echo() {
+    use expectrl::process::Termios;
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        let _ = session.set_echo(true).unwrap();
+        session
+            .send_line_wait_echo("Hello", Duration::from_secs(1))
+            .await
+            .unwrap();
+        let found = session.expect("Hello").await.unwrap();
+        assert_eq!(found.before(), b"\r\n");
+
+        let mut session = spawn("sleep 5").unwrap();
+        let err = session
+            .send_line_wait_echo("Hello", Duration::from_millis(300))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn must_expect() {
+    let mut session = spawn("echo Hello World").unwrap();
+    let found = session.must_expect("World");
+    assert_eq!(found.before(), b"Hello ");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn must_expect() {
+    futures_lite::future::block_on(async {
+        let mut session = spawn("echo Hello World").unwrap();
+        let found = session.must_expect("World").await;
+        assert_eq!(found.before(), b"Hello ");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+#[should_panic(expected = "needle: \"Bye\"\n  timeout: 300ms\n  buffer: \"Hello World\\r\\n\"")]
+fn must_expect_panics() {
+    let mut session = sh("echo Hello World; sleep 5");
+    session.set_expect_timeout(Some(Duration::from_millis(300)));
+    let _ = session.must_expect("Bye");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+#[should_panic(expected = "needle: \"Bye\"\n  timeout: 300ms\n  buffer: \"Hello World\\r\\n\"")]
+fn must_expect_panics() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("echo Hello World; sleep 5");
+        session.set_expect_timeout(Some(Duration::from_millis(300)));
+        let _ = session.must_expect("Bye").await;
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_then_drain() {
+    let mut session = sh("echo START; echo 1; sleep 0.1; echo 2; sleep 2; echo 3");
+    let (found, rest) = session
+        .expect_then_drain("START", Duration::from_millis(500))
+        .unwrap();
+    assert_eq!(&found[0], b"START");
+    assert_eq!(rest, b"\r\n1\r\n2\r\n");
+
+    session.expect("3").unwrap();
+
+    // an EOF ends the output
+    let mut session = sh("echo START; echo 1");
+    let (_, rest) = session
+        .expect_then_drain("START", Duration::from_secs(5))
+        .unwrap();
+    assert_eq!(rest, b"\r\n1\r\n");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_then_drain() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("echo START; echo 1; sleep 0.1; echo 2; sleep 2; echo 3");
+        let (found, rest) = session
+            .expect_then_drain("START", Duration::from_millis(500))
+            .await
+            .unwrap();
+        assert_eq!(&found[0], b"START");
+        assert_eq!(rest, b"\r\n1\r\n2\r\n");
+
+        session.expect("3").await.unwrap();
+
+        // an EOF ends the output
+        let mut session = sh("echo START; echo 1");
+        let (_, rest) = session
+            .expect_then_drain("START", Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(rest, b"\r\n1\r\n");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn output_trim() {
+    let mut session = sh("echo hello; printf '>>> '; sleep 0.1; echo world; printf '>>> '");
+    session.set_output_trim("\r\n");
+
+    let found = session.expect(">>> ").unwrap();
+    assert_eq!(found.before(), b"hello");
+    assert_eq!(found.as_bytes(), b"hello\r\n>>> ");
+
+    session.set_output_trim("");
+    let found = session.expect(">>> ").unwrap();
+    assert_eq!(found.before(), b"world\r\n");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn output_trim() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("echo hello; printf '>>> '; sleep 0.1; echo world; printf '>>> '");
+        session.set_output_trim("\r\n");
+
+        let found = session.expect(">>> ").await.unwrap();
+        assert_eq!(found.before(), b"hello");
+        assert_eq!(found.as_bytes(), b"hello\r\n>>> ");
+
+        session.set_output_trim("");
+        let found = session.expect(">>> ").await.unwrap();
+        assert_eq!(found.before(), b"world\r\n");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_active() {
+    let mut session = sh("for i in 1 2 3 4 5 6; do echo $i; sleep 0.2; done; echo DONE");
+    session.set_expect_timeout(Some(Duration::from_millis(300)));
+    let found = session
+        .expect_active("DONE", Duration::from_millis(600))
+        .unwrap();
+    assert_eq!(&found[0], b"DONE");
+
+    let mut session = sh("echo START; sleep 2; echo DONE");
+    let err = session
+        .expect_active("DONE", Duration::from_millis(300))
+        .unwrap_err();
+    assert!(matches!(err, expectrl::Error::ExpectTimeout));
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_active() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("for i in 1 2 3 4 5 6; do echo $i; sleep 0.2; done; echo DONE");
+        session.set_expect_timeout(Some(Duration::from_millis(300)));
+        let found = session
+            .expect_active("DONE", Duration::from_millis(600))
+            .await
+            .unwrap();
+        assert_eq!(&found[0], b"DONE");
+
+        let mut session = sh("echo START; sleep 2; echo DONE");
+        let err = session
+            .expect_active("DONE", Duration::from_millis(300))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, expectrl::Error::ExpectTimeout));
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn read_record() {
+    let mut session = sh("printf 'AAAABBBB'; sleep 0.1; printf 'CCCCDD'");
+    assert_eq!(session.read_record(4).unwrap(), b"AAAA");
+
+    let records = session.records(4).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(records, [b"BBBB", b"CCCC"]);
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn read_record_zero_width() {
+    let mut session = sh("printf 'AAAA'");
+    assert!(matches!(
+        session.read_record(0),
+        Err(expectrl::Error::Other { .. })
+    ));
+
+    let records = session.records(0).collect::<Vec<_>>();
+    assert_eq!(records.len(), 1);
+    assert!(matches!(records[0], Err(expectrl::Error::Other { .. })));
+
+    assert_eq!(session.read_record(4).unwrap(), b"AAAA");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn read_record() {
+    use futures_lite::StreamExt;
+
+    futures_lite::future::block_on(async {
+        let mut session = sh("printf 'AAAABBBB'; sleep 0.1; printf 'CCCCDD'");
+        assert_eq!(session.read_record(4).await.unwrap(), b"AAAA");
+
+        let records = session
+            .records(4)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(records, [b"BBBB", b"CCCC"]);
+    })
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn read_record_zero_width() {
+    use futures_lite::StreamExt;
+
+    futures_lite::future::block_on(async {
+        let mut session = sh("printf 'AAAA'");
+        assert!(matches!(
+            session.read_record(0).await,
+            Err(expectrl::Error::Other { .. })
+        ));
+
+        let records = session.records(0).collect::<Vec<_>>().await;
+        assert_eq!(records.len(), 1);
+        assert!(matches!(records[0], Err(expectrl::Error::Other { .. })));
+
+        assert_eq!(session.read_record(4).await.unwrap(), b"AAAA");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn match_transform() {
+    let mut session = sh("echo 'Hello *WORLD*'");
+    session.add_match_transform(strip_stars);
+    session.add_match_transform(|buf| buf.to_ascii_lowercase());
+
+    let found = session.expect("hello world").unwrap();
+    assert_eq!(&found[0], b"Hello *WORLD");
+    assert_eq!(found.before(), b"");
+
+    session.clear_match_transforms();
+    session.expect("*\r\n").unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn match_transform() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("echo 'Hello *WORLD*'");
+        session.add_match_transform(strip_stars);
+        session.add_match_transform(|buf| buf.to_ascii_lowercase());
+
+        let found = session.expect("hello world").await.unwrap();
+        assert_eq!(&found[0], b"Hello *WORLD");
+        assert_eq!(found.before(), b"");
+
+        session.clear_match_transforms();
+        session.expect("*\r\n").await.unwrap();
+    })
+}
+
+#[cfg(unix)]
+fn strip_stars(buf: &[u8]) -> Vec<u8> {
+    buf.iter().filter(|&&b| b != b'*').copied().collect()
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_prompt_inline() {
+    let mut session = sh(
+        "printf 'Password: is required\\n'; sleep 0.1; printf 'Password: '; read x; echo got $x",
+    );
+    let found = session
+        .expect_prompt_inline("Password: ", Duration::from_millis(300))
+        .unwrap();
+    assert_eq!(found.before(), b"is required\r\n");
+
+    session.send_line("secret").unwrap();
+    session.expect("got secret").unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_prompt_inline() {
+    futures_lite::future::block_on(async {
+        let mut session =
+            sh("printf 'Password: is required\\n'; sleep 0.1; printf 'Password: '; read x; echo got $x");
+        let found = session
+            .expect_prompt_inline("Password: ", Duration::from_millis(300))
+            .await
+            .unwrap();
+        assert_eq!(found.before(), b"is required\r\n");
+
+        session.send_line("secret").await.unwrap();
+        session.expect("got secret").await.unwrap();
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_timeout() {
+    let mut session = sh("sleep 0.3; echo Hello");
+    session.set_expect_timeout(Some(Duration::from_millis(100)));
+
+    let err = session
+        .expect_timeout("Hello", Some(Duration::from_millis(10)))
+        .unwrap_err();
+    assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+    assert!(session.dump().contains("expect timeout: 100ms"));
+
+    session
+        .expect_timeout("Hello", Some(Duration::from_secs(5)))
+        .unwrap();
+    assert!(session.dump().contains("expect timeout: 100ms"));
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_timeout() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("sleep 0.3; echo Hello");
+        session.set_expect_timeout(Some(Duration::from_millis(100)));
+
+        let err = session
+            .expect_timeout("Hello", Some(Duration::from_millis(10)))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, expectrl::Error::ExpectTimeout), "{err:?}");
+        assert!(session.dump().contains("expect timeout: 100ms"));
+
+        session
+            .expect_timeout("Hello", Some(Duration::from_secs(5)))
+            .await
+            .unwrap();
+        assert!(session.dump().contains("expect timeout: 100ms"));
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_stripped() {
+    let mut session = sh(r"printf '\033[1;32mok\033[0m: \033[4mdone\033[0m\n'");
+    let m = session.expect(expectrl::Stripped("ok: done")).unwrap();
+    assert_eq!(m.before(), b"\x1b[1;32m");
+    assert_eq!(&m[0], b"ok\x1b[0m: \x1b[4mdone");
+    session.expect("\x1b[0m").unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_stripped() {
+    futures_lite::future::block_on(async {
+        let mut session = sh(r"printf '\033[1;32mok\033[0m: \033[4mdone\033[0m\n'");
+        let m = session
+            .expect(expectrl::Stripped("ok: done"))
+            .await
+            .unwrap();
+        assert_eq!(m.before(), b"\x1b[1;32m");
+        assert_eq!(&m[0], b"ok\x1b[0m: \x1b[4mdone");
+        session.expect("\x1b[0m").await.unwrap();
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_eof_output() {
+    let mut session = sh("echo Hello; echo World");
+    let output = session.expect_eof(Some(Duration::from_secs(5))).unwrap();
+    assert_eq!(output, b"Hello\r\nWorld\r\n");
+
+    let mut session = sh("echo started; sleep 5");
+    let err = session
+        .expect_eof(Some(Duration::from_millis(300)))
+        .unwrap_err();
+    assert!(matches!(err, expectrl::Error::ExpectTimeout));
+    assert_eq!(session.drain_buffer(), b"started\r\n");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_eof_output() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("echo Hello; echo World");
+        let output = session
+            .expect_eof(Some(Duration::from_secs(5)))
+            .await
+            .unwrap();
+        assert_eq!(output, b"Hello\r\nWorld\r\n");
+
+        let mut session = sh("echo started; sleep 5");
+        let err = session
+            .expect_eof(Some(Duration::from_millis(300)))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, expectrl::Error::ExpectTimeout));
+        assert_eq!(session.drain_buffer(), b"started\r\n");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_line() {
+    let mut session = sh("printf 'first\\nsecond\\nlast'");
+    let m = session.expect(expectrl::Line).unwrap();
+    assert_eq!(&m[0], b"first");
+    assert_eq!(m.matches().count(), 1);
+    assert!(m.as_bytes().ends_with(b"\n"));
+    let m = session.expect(expectrl::Line).unwrap();
+    assert_eq!(&m[0], b"second");
+    let m = session.expect(expectrl::Line).unwrap();
+    assert_eq!(&m[0], b"last");
+    assert_eq!(m.matches().count(), 1);
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_line() {
+    futures_lite::future::block_on(async {
+        let mut session = sh("printf 'first\\nsecond\\nlast'");
+        let m = session.expect(expectrl::Line).await.unwrap();
+        assert_eq!(&m[0], b"first");
+        assert_eq!(m.matches().count(), 1);
+        assert!(m.as_bytes().ends_with(b"\n"));
+        let m = session.expect(expectrl::Line).await.unwrap();
+        assert_eq!(&m[0], b"second");
+        let m = session.expect(expectrl::Line).await.unwrap();
+        assert_eq!(&m[0], b"last");
+        assert_eq!(m.matches().count(), 1);
+    })
+}
+
+#[cfg(unix)]
+fn sh(script: &str) -> expectrl::session::OsSession {
+    let mut cmd = std::process::Command::new("sh");
+    let _ = cmd.args(["-c", script]);
+    expectrl::Session::spawn(cmd).unwrap()
+}
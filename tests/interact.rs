@@ -174,7 +174,7 @@ fn interact_filters() {
             let v = buf
                 .chunks(2)
                 .flat_map(|s| match s {
-                    &[b'N', b'O'] => &[b'Y', b'E', b'S'],
+                    &[b'N', b'O'] => b"YES",
                     other => other,
                 })
                 .copied()
@@ -191,6 +191,274 @@ fn interact_filters() {
     assert_eq!(buffer, "19\r\nYES\r\n19\r\nYES\r\n");
 }
 
+#[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
+#[test]
+fn interact_escape_sequence() {
+    let reader = ListReaderWithDelayedEof::new(
+        vec![
+            "Hello~\n".to_string(),
+            "World~".to_string(),
+            ".\n".to_string(),
+            "Not sent\n".to_string(),
+        ],
+        Duration::from_secs(2),
+    );
+    let mut writer = io::Cursor::new(vec![0; 2048]);
+
+    let mut session = spawn("cat").unwrap();
+    let is_alive = session
+        .interact(reader, &mut writer)
+        .set_escape_sequence(b"~.")
+        .spawn()
+        .unwrap();
+
+    assert!(is_alive);
+
+    session.send_line("").unwrap();
+    let m = session.expect("World\r\n").unwrap();
+
+    // the output could be read either by interact or by expect
+    let mut output = writer.into_inner();
+    output.extend_from_slice(m.as_bytes());
+    let output = String::from_utf8_lossy(&output);
+
+    assert!(output.contains("Hello~\r\n"), "{output:?}");
+    assert!(!output.contains("Not sent"), "{output:?}");
+}
+
+#[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
+#[test]
+fn interact_echo_input() {
+    let reader =
+        ListReaderWithDelayedEof::new(vec!["Hello World\n".to_string()], Duration::from_secs(2));
+    let mut writer = io::Cursor::new(vec![0; 2048]);
+
+    let mirror = SharedWriter::default();
+
+    let mut session = spawn("cat").unwrap();
+    session
+        .interact(reader, &mut writer)
+        .add_output(mirror.clone())
+        .set_echo_input(true)
+        .spawn()
+        .unwrap();
+
+    let output = String::from_utf8_lossy(writer.get_ref());
+    // the input is written as it is, while cat's output is translated by the terminal
+    assert!(output.contains("Hello World\n"), "{output:?}");
+    assert!(output.contains("Hello World\r\n"), "{output:?}");
+
+    // added outputs get the echoed input as well
+    let output = output.trim_end_matches(char::from(0));
+    assert_eq!(mirror.contents(), output);
+}
+
+#[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
+#[test]
+fn interact_key_map_flushes_held_prefix() {
+    use std::collections::HashMap;
+
+    use expectrl::interact::KeyAction;
+
+    let keys = || {
+        let mut keys = HashMap::new();
+        let _ = keys.insert(b"xy".to_vec(), KeyAction::Drop);
+        keys
+    };
+
+    // a prefix is sent once nothing follows it for a while
+    let reader = ListReaderWithDelayedEof::new(vec!["abx".to_string()], Duration::from_secs(1));
+    let mut writer = io::Cursor::new(vec![0; 2048]);
+
+    let mut session = spawn("cat").unwrap();
+    session
+        .interact(reader, &mut writer)
+        .set_key_map(keys())
+        .spawn()
+        .unwrap();
+
+    let output = String::from_utf8_lossy(writer.get_ref());
+    assert!(output.contains("abx"), "{output:?}");
+
+    // a prefix is sent once the input is over
+    let reader = ListReaderWithDelayedEof::new(vec!["abx".to_string()], Duration::ZERO);
+
+    let mut session = spawn("cat").unwrap();
+    session
+        .interact(reader, sink())
+        .set_key_map(keys())
+        .spawn()
+        .unwrap();
+
+    session.send_line("").unwrap();
+    session.expect("abx\r\n").unwrap();
+}
+
+#[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
+#[test]
+fn interact_escape_sequence_flushes_held_prefix() {
+    // a partially matched escape sequence is sent once the input is over
+    let reader = ListReaderWithDelayedEof::new(vec!["abc~".to_string()], Duration::ZERO);
+
+    let mut session = spawn("cat").unwrap();
+    session
+        .interact(reader, sink())
+        .set_escape_sequence(b"~.")
+        .spawn()
+        .unwrap();
+
+    session.send_line("").unwrap();
+    session.expect("abc~\r\n").unwrap();
+}
+
+#[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
+#[test]
+fn interact_forward_resize_without_terminal() {
+    use std::io::IsTerminal;
+
+    let reader = ListReaderWithDelayedEof::new(vec!["\n".to_string()], Duration::from_secs(2));
+    let mut writer = io::Cursor::new(vec![0; 2048]);
+
+    let mut cmd = std::process::Command::new("sh");
+    let _ = cmd.args(["-c", "read x; stty size; read x"]);
+    let mut session = expectrl::Session::spawn(cmd).unwrap();
+    session.set_window_size(100, 30).unwrap();
+    session
+        .interact(reader, &mut writer)
+        .forward_resize(true)
+        .spawn()
+        .unwrap();
+
+    // the size is left as it is, unless the tests run in a terminal
+    if !io::stdin().is_terminal() && !io::stdout().is_terminal() {
+        let output = String::from_utf8_lossy(writer.get_ref());
+        assert!(output.contains("30 100"), "{output:?}");
+    }
+}
+
+#[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
+#[test]
+fn interact_forwards_ctrl_c() {
+    let reader = ListReaderWithDelayedEof::new(vec!["\x03".to_string()], Duration::from_secs(2));
+    let mut writer = io::Cursor::new(vec![0; 2048]);
+
+    let mut cmd = std::process::Command::new("sh");
+    let _ = cmd.args([
+        "-c",
+        "trap 'echo got SIGINT; exit 3' INT; echo ready; while true; do sleep 0.1; done",
+    ]);
+    let mut session = expectrl::Session::spawn(cmd).unwrap();
+    session.expect("ready").unwrap();
+
+    let mut isession = session.interact(reader, &mut writer);
+    let is_alive = isession.spawn().unwrap();
+    let status = isession.get_status();
+    drop(isession);
+
+    // a trap handler is run only if SIGINT reached the child
+    assert!(!is_alive);
+    assert_eq!(
+        status,
+        Some(WaitStatus::Exited(session.get_process().pid(), 3))
+    );
+
+    // the output could be read either by interact or by expect
+    let mut output = String::from_utf8_lossy(&writer.into_inner()).into_owned();
+    if !output.contains("got SIGINT") {
+        let m = session.expect("got SIGINT").unwrap();
+        output.push_str(&String::from_utf8_lossy(m.as_bytes()));
+    }
+    assert!(output.contains("got SIGINT"), "{output:?}");
+}
+
+#[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
+#[test]
+fn interact_bytes_counters() {
+    let reader = ListReaderWithDelayedEof::new(
+        vec!["Hello\n".to_string(), "World\n".to_string()],
+        Duration::from_secs(2),
+    );
+    let mut writer = io::Cursor::new(vec![0; 2048]);
+
+    let mut session = spawn("cat").unwrap();
+    let mut isession = session.interact(reader, &mut writer).with_state(0);
+    isession.set_input_action(|ctx| {
+        *ctx.state = ctx.bytes_in;
+        Ok(false)
+    });
+
+    let is_alive = isession.spawn().unwrap();
+    assert!(is_alive);
+
+    assert_eq!(isession.bytes_in(), 12);
+    assert!(isession.bytes_out() > 0);
+    assert_eq!(*isession.get_state(), 12);
+}
+
+#[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
+#[test]
+fn interact_log() {
+    let reader = ListReaderWithDelayedEof::new(vec!["Hello\n".to_string()], Duration::from_secs(2));
+    let log = SharedWriter::default();
+
+    let mut session = spawn("cat").unwrap();
+    let is_alive = session
+        .interact(reader, sink())
+        .set_log(log.clone())
+        .spawn()
+        .unwrap();
+    assert!(is_alive);
+
+    let log = log.contents();
+    let mut lines = log.lines();
+    let input = lines.next().unwrap();
+    assert!(input.starts_with('['), "{log:?}");
+    assert!(input.ends_with("] input: \"Hello\\n\""), "{log:?}");
+    assert!(log.contains("] output: \"Hello"), "{log:?}");
+}
+
+#[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
+#[test]
+fn interact_multiple_outputs() {
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let reader = ListReaderWithDelayedEof::new(vec!["Hello\n".to_string()], Duration::from_secs(2));
+    let mirror = SharedWriter::default();
+    let mut writer = io::Cursor::new(vec![0; 2048]);
+
+    let mut session = spawn("cat").unwrap();
+    let is_alive = session
+        .interact(reader, &mut writer)
+        .add_output(mirror.clone())
+        .add_output(FailingWriter)
+        .set_ignore_output_errors(true)
+        .spawn()
+        .unwrap();
+    assert!(is_alive);
+
+    let output = String::from_utf8_lossy(writer.get_ref());
+    let output = output.trim_end_matches(char::from(0));
+    assert!(output.contains("Hello"), "{output:?}");
+    assert_eq!(mirror.contents(), output);
+
+    let reader = ListReaderWithDelayedEof::new(vec!["Hello\n".to_string()], Duration::from_secs(2));
+    let result = session
+        .interact(reader, sink())
+        .add_output(FailingWriter)
+        .spawn();
+    assert!(result.is_err());
+}
+
 #[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
 #[test]
 fn interact_context() {
@@ -351,6 +619,27 @@ fn interact_stream_redirection() {
     });
 }
 
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn interact_escape_sequence_flushes_held_prefix() {
+    futures_lite::future::block_on(async {
+        // a partially matched escape sequence is sent once the input is over
+        let reader = ReaderWithDelayEof::new("abc~", Duration::ZERO);
+
+        let mut session = expectrl::spawn("cat").unwrap();
+        session
+            .interact(reader, AsyncWriter(io::sink()))
+            .set_escape_sequence(b"~.")
+            .spawn()
+            .await
+            .unwrap();
+
+        session.send_line("").await.unwrap();
+        session.expect("abc~\r\n").await.unwrap();
+    });
+}
+
 #[cfg(feature = "async")]
 #[test]
 fn interact_output_callback() {
@@ -384,6 +673,28 @@ fn interact_output_callback() {
     assert!(matches!(state, 1 | 0), "{state:?}");
 }
 
+#[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
+#[derive(Clone, Default)]
+struct SharedWriter(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+#[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
+impl SharedWriter {
+    fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+#[cfg(all(unix, not(any(feature = "async", feature = "polling"))))]
+impl Write for SharedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 struct ListReaderWithDelayedEof {
     lines: Vec<String>,
     eof_timeout: Duration,
@@ -410,7 +721,7 @@ impl Read for ListReaderWithDelayedEof {
         if !self.lines.is_empty() {
             let line = self.lines.remove(0);
             buf.write_all(line.as_bytes())?;
-            Ok(line.as_bytes().len())
+            Ok(line.len())
         } else if self.now.unwrap().elapsed() < self.eof_timeout {
             Err(io::Error::new(io::ErrorKind::WouldBlock, ""))
         } else {
@@ -504,3 +815,63 @@ where
         std::task::Poll::Ready(Ok(()))
     }
 }
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn interact_headless() {
+    use expectrl::{interact::InteractSession, Session};
+    use futures_lite::AsyncRead;
+    use std::{
+        io::{Read, Write},
+        os::unix::net::UnixStream,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    // A reader which gives data once and never reaches an EOF.
+    struct Once(Option<Vec<u8>>);
+
+    impl AsyncRead for Once {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            match self.0.take() {
+                Some(data) => {
+                    buf[..data.len()].copy_from_slice(&data);
+                    Poll::Ready(Ok(data.len()))
+                }
+                None => Poll::Pending,
+            }
+        }
+    }
+
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    let peer = std::thread::spawn(move || {
+        let mut buf = [0; 6];
+        peer.read_exact(&mut buf).unwrap();
+        peer.write_all(b"World\n").unwrap();
+        buf
+    });
+
+    let stream = async_io::Async::new(stream).unwrap();
+    let mut session = Session::over_stream(stream).unwrap();
+    let mut output = Vec::new();
+
+    let is_alive = futures_lite::future::block_on(
+        InteractSession::new(
+            &mut session,
+            Once(Some(b"Hello\n".to_vec())),
+            &mut output,
+            (),
+        )
+        .spawn_headless(),
+    )
+    .unwrap();
+
+    assert!(is_alive);
+    assert_eq!(&peer.join().unwrap(), b"Hello\n");
+    assert_eq!(output, b"World\n");
+}
@@ -110,15 +110,15 @@ fn try_read_by_byte() {
 
     let mut buf = [0; 1];
     _p_try_read(&mut proc, &mut buf).unwrap();
-    assert_eq!(&buf, &[b'1']);
+    assert_eq!(&buf, b"1");
     _p_try_read(&mut proc, &mut buf).unwrap();
-    assert_eq!(&buf, &[b'2']);
+    assert_eq!(&buf, b"2");
     _p_try_read(&mut proc, &mut buf).unwrap();
-    assert_eq!(&buf, &[b'3']);
+    assert_eq!(&buf, b"3");
     _p_try_read(&mut proc, &mut buf).unwrap();
-    assert_eq!(&buf, &[b'\r']);
+    assert_eq!(&buf, b"\r");
     _p_try_read(&mut proc, &mut buf).unwrap();
-    assert_eq!(&buf, &[b'\n']);
+    assert_eq!(&buf, b"\n");
     assert_eq!(
         _p_try_read(&mut proc, &mut buf).unwrap_err().kind(),
         std::io::ErrorKind::WouldBlock
@@ -172,7 +172,7 @@ fn blocking_read_after_non_blocking() {
 
     let mut buf = [0; 1];
     _p_try_read(&mut proc, &mut buf).unwrap();
-    assert_eq!(&buf, &[b'1']);
+    assert_eq!(&buf, b"1");
 
     let mut buf = [0; 64];
     let n = _p_read(&mut proc, &mut buf).unwrap();
@@ -734,3 +734,59 @@ where
         std::task::Poll::Ready(Ok(()))
     }
 }
+
+#[test]
+#[cfg(unix)]
+fn read_up_to() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let mut session = expectrl::spawn("echo Hello World").unwrap();
+    session.set_on_eof({
+        let calls = calls.clone();
+        move || {
+            let _ = calls.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+
+    #[cfg(not(feature = "async"))]
+    let buf = session.read_up_to(5, Duration::from_secs(1)).unwrap();
+    #[cfg(feature = "async")]
+    let buf = block_on(session.read_up_to(5, Duration::from_secs(1))).unwrap();
+    assert_eq!(buf, b"Hello");
+
+    // stops on EOF
+    #[cfg(not(feature = "async"))]
+    let buf = session.read_up_to(1024, Duration::from_secs(5)).unwrap();
+    #[cfg(feature = "async")]
+    let buf = block_on(session.read_up_to(1024, Duration::from_secs(5))).unwrap();
+    assert_eq!(buf, b" World\r\n");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // stops on timeout
+    let mut session = expectrl::spawn("sleep 5").unwrap();
+    let now = std::time::Instant::now();
+    #[cfg(not(feature = "async"))]
+    let buf = session
+        .read_up_to(1024, Duration::from_millis(300))
+        .unwrap();
+    #[cfg(feature = "async")]
+    let buf = block_on(session.read_up_to(1024, Duration::from_millis(300))).unwrap();
+    assert!(buf.is_empty());
+    assert!(now.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+#[cfg(windows)]
+#[cfg(not(feature = "async"))]
+fn wait_readable() {
+    let mut proc = Session::spawn(Command::new("python ./tests/actions/cat/main.py")).unwrap();
+    _p_send_line(&mut proc, "hello cat").unwrap();
+    assert!(proc.wait_readable(Duration::from_secs(5)).unwrap());
+    _p_expect(&mut proc, "hello cat").unwrap();
+    proc.get_process_mut().exit(0).unwrap();
+}
@@ -160,6 +160,61 @@ fn log_read_line() {
     }
 }
 
+#[test]
+#[cfg(unix)]
+fn log_with_format() {
+    use expectrl::stream::log::timestamped_format;
+
+    let writer = StubWriter::default();
+
+    #[cfg(feature = "async")]
+    futures_lite::future::block_on(async {
+        let mut session =
+            session::log_with_format(spawn("cat").unwrap(), writer.clone(), timestamped_format)
+                .unwrap();
+
+        session.send_line("Hello World").await.unwrap();
+        session.expect("Hello World\r\n").await.unwrap();
+    });
+
+    #[cfg(not(feature = "async"))]
+    {
+        let mut session =
+            session::log_with_format(spawn("cat").unwrap(), writer.clone(), timestamped_format)
+                .unwrap();
+
+        session.send_line("Hello World").unwrap();
+        session.expect("Hello World\r\n").unwrap();
+    }
+
+    let bytes = writer.inner.lock().unwrap();
+    let text = String::from_utf8_lossy(bytes.get_ref());
+    let lines = text.lines().collect::<Vec<_>>();
+    assert!(lines.iter().all(|line| line.contains("Z ")), "{text:?}");
+    assert!(lines.iter().any(|line| line.contains(" -> \"")), "{text:?}");
+    assert!(lines.iter().any(|line| line.contains(" <- \"")), "{text:?}");
+}
+
+#[test]
+#[cfg(unix)]
+fn log_keeps_settings() {
+    use std::process::Command;
+
+    let mut command = Command::new("sh");
+    let _ = command.args(["-c", "for i in 1 2 3 4 5; do echo $i; sleep 0.2; done"]);
+
+    let mut session = expectrl::Session::spawn(command).unwrap();
+    session.set_max_read_iterations(Some(2));
+    let mut session = session::log(session, StubWriter::default()).unwrap();
+
+    #[cfg(feature = "async")]
+    let err = futures_lite::future::block_on(session.expect("Hello")).unwrap_err();
+    #[cfg(not(feature = "async"))]
+    let err = session.expect("Hello").unwrap_err();
+
+    assert!(matches!(err, expectrl::Error::MaxIterations), "{err:?}");
+}
+
 #[derive(Debug, Clone, Default)]
 struct StubWriter {
     inner: Arc<Mutex<Cursor<Vec<u8>>>>,
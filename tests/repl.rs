@@ -229,3 +229,144 @@ fn bash_control_chars() {
     p.send(ControlCode::Substitute).unwrap(); // suspend:SIGTSTPcon
     p.expect_prompt().unwrap();
 }
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn bash_execute_with_timeout() {
+    let mut p = spawn_bash().unwrap();
+    let out = p
+        .execute_with_timeout("echo Hello", Duration::from_secs(5))
+        .unwrap();
+    assert!(String::from_utf8_lossy(&out).contains("Hello\r\n"));
+
+    let err = p
+        .execute_with_timeout("sleep 1", Duration::from_millis(100))
+        .unwrap_err();
+    assert!(matches!(err.root(), expectrl::Error::ExpectTimeout));
+    assert!(err.to_string().contains("sleep 1"), "{err}");
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn bash_execute_with_timeout() {
+    futures_lite::future::block_on(async {
+        let mut p = spawn_bash().await.unwrap();
+        let out = p
+            .execute_with_timeout("echo Hello", Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&out).contains("Hello\r\n"));
+
+        let err = p
+            .execute_with_timeout("sleep 1", Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(matches!(err.root(), expectrl::Error::ExpectTimeout));
+        assert!(err.to_string().contains("sleep 1"), "{err}");
+    });
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn spawn_repl() {
+    let mut cmd = std::process::Command::new("sh");
+    let _ = cmd.env("PS1", "REPL> ");
+
+    let mut p =
+        expectrl::repl::spawn_repl(cmd, "REPL> ", Some(String::from("exit")), false).unwrap();
+    assert_eq!(p.get_quit_command(), Some("exit"));
+
+    let out = p.execute("echo Hello World").unwrap();
+    assert_eq!(out, b"Hello World\r\n");
+
+    p.exit().unwrap();
+    p.expect(expectrl::Eof).unwrap();
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn spawn_repl() {
+    futures_lite::future::block_on(async {
+        let mut cmd = std::process::Command::new("sh");
+        let _ = cmd.env("PS1", "REPL> ");
+
+        let mut p = expectrl::repl::spawn_repl(cmd, "REPL> ", Some(String::from("exit")), false)
+            .await
+            .unwrap();
+        assert_eq!(p.get_quit_command(), Some("exit"));
+
+        let out = p.execute("echo Hello World").await.unwrap();
+        assert_eq!(out, b"Hello World\r\n");
+
+        p.exit().await.unwrap();
+        p.expect(expectrl::Eof).await.unwrap();
+    });
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn sh() {
+    let mut p = expectrl::repl::spawn_sh().unwrap();
+
+    let out = p.execute("echo Hello World").unwrap();
+    assert_eq!(out, b"Hello World\r\n");
+
+    p.exit().unwrap();
+    p.expect(expectrl::Eof).unwrap();
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn sh() {
+    futures_lite::future::block_on(async {
+        let mut p = expectrl::repl::spawn_sh().await.unwrap();
+
+        let out = p.execute("echo Hello World").await.unwrap();
+        assert_eq!(out, b"Hello World\r\n");
+
+        p.exit().await.unwrap();
+        p.expect(expectrl::Eof).await.unwrap();
+    });
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn zsh() {
+    if !is_zsh_installed() {
+        return;
+    }
+
+    let mut p = expectrl::repl::spawn_zsh().unwrap();
+
+    let out = p.execute("echo Hello World").unwrap();
+    assert_eq!(out, b"Hello World\r\n");
+
+    p.exit().unwrap();
+    p.expect(expectrl::Eof).unwrap();
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn zsh() {
+    if !is_zsh_installed() {
+        return;
+    }
+
+    futures_lite::future::block_on(async {
+        let mut p = expectrl::repl::spawn_zsh().await.unwrap();
+
+        let out = p.execute("echo Hello World").await.unwrap();
+        assert_eq!(out, b"Hello World\r\n");
+
+        p.exit().await.unwrap();
+        p.expect(expectrl::Eof).await.unwrap();
+    });
+}
+
+/// zsh is not shipped by default on most linux distributions.
+fn is_zsh_installed() -> bool {
+    std::process::Command::new("zsh")
+        .args(["-c", "exit"])
+        .output()
+        .is_ok()
+}
@@ -0,0 +1,95 @@
+#![cfg(unix)]
+
+use expectrl::{
+    script::{Script, Step},
+    spawn,
+};
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn run_script() {
+    let script = Script::new(vec![
+        Step::SetTimeout(Some(1000)),
+        Step::SendLine("Hello World".to_string()),
+        Step::Expect("Hello World".to_string()),
+        Step::Send("123\n".to_string()),
+        Step::ExpectRegex(r"\d+".to_string()),
+    ]);
+
+    let mut session = spawn("cat").unwrap();
+    let report = session.run_script(&script).unwrap();
+
+    assert_eq!(report.len(), 2);
+    assert_eq!(report.get(2).unwrap().get(0).unwrap(), b"Hello World");
+    assert_eq!(report.get(4).unwrap().get(0).unwrap(), b"123");
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn run_script_failed_step() {
+    let script = Script::new(vec![
+        Step::SetTimeout(Some(100)),
+        Step::SendLine("Hello World".to_string()),
+        Step::Expect("Bye".to_string()),
+    ]);
+
+    let mut session = spawn("cat").unwrap();
+    let err = session.run_script(&script).unwrap_err();
+    assert!(
+        matches!(err.root(), expectrl::Error::ExpectTimeout),
+        "{:?}",
+        err
+    );
+
+    let err = err.to_string();
+    assert!(err.contains("step 2"), "{:?}", err);
+    assert!(err.contains("timeout"), "{:?}", err);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn run_script() {
+    let script = Script::new(vec![
+        Step::SetTimeout(Some(1000)),
+        Step::SendLine("Hello World".to_string()),
+        Step::Expect("Hello World".to_string()),
+        Step::Send("123\n".to_string()),
+        Step::ExpectRegex(r"\d+".to_string()),
+    ]);
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        let report = session.run_script(&script).await.unwrap();
+
+        assert_eq!(report.len(), 2);
+        assert_eq!(report.get(2).unwrap().get(0).unwrap(), b"Hello World");
+        assert_eq!(report.get(4).unwrap().get(0).unwrap(), b"123");
+    })
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialize_script() {
+    let script = r#"{
+        "steps": [
+            { "set_timeout": 1000 },
+            { "send_line": "Hello World" },
+            { "expect": "Hello World" },
+            { "expect_regex": "\\d+" },
+            "expect_eof"
+        ]
+    }"#;
+
+    let script: Script = serde_json::from_str(script).unwrap();
+
+    assert_eq!(
+        script,
+        Script::new(vec![
+            Step::SetTimeout(Some(1000)),
+            Step::SendLine("Hello World".to_string()),
+            Step::Expect("Hello World".to_string()),
+            Step::ExpectRegex(r"\d+".to_string()),
+            Step::ExpectEof,
+        ])
+    );
+}
@@ -181,6 +181,1734 @@ fn send_line() {
     }
 }
 
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn send_control_keys() {
+    use expectrl::Eof;
+
+    let mut session = spawn("cat").unwrap();
+    session.eof().unwrap();
+    session.expect(Eof).unwrap();
+
+    let mut session = spawn("cat").unwrap();
+    session.interrupt().unwrap();
+    session.expect(Eof).unwrap();
+
+    // a terminal doesn't stop an orphaned process group, so it's checked on a job of a shell
+    let mut session = expectrl::repl::spawn_bash().unwrap();
+    session.send_line("cat").unwrap();
+    // let cat become the foreground job before the signal
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    session.suspend().unwrap();
+    session.expect("Stopped").unwrap();
+    session.expect_prompt().unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn send_control_keys() {
+    use expectrl::Eof;
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.eof().await.unwrap();
+        session.expect(Eof).await.unwrap();
+
+        let mut session = spawn("cat").unwrap();
+        session.interrupt().await.unwrap();
+        session.expect(Eof).await.unwrap();
+
+        // a terminal doesn't stop an orphaned process group, so it's checked on a job of a shell
+        let mut session = expectrl::repl::spawn_bash().await.unwrap();
+        session.send_line("cat").await.unwrap();
+        // let cat become the foreground job before the signal
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        session.suspend().await.unwrap();
+        session.expect("Stopped").await.unwrap();
+        session.expect_prompt().await.unwrap();
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn spawn_without_controlling_terminal() {
+    use expectrl::{
+        process::{unix::UnixProcess, unix::UnixProcessOptions, Process},
+        Eof, Session,
+    };
+    use std::process::Command;
+
+    let mut cmd = Command::new("sh");
+    let _ = cmd.args(["-c", "cat /dev/tty || echo NO_TTY"]);
+
+    let opts = UnixProcessOptions::new().controlling_terminal(false);
+    let mut proc = UnixProcess::spawn_with_options(cmd, opts).unwrap();
+    let stream = proc.open_stream().unwrap();
+    let mut session = Session::new(proc, stream).unwrap();
+
+    session.expect("NO_TTY").unwrap();
+    session.expect(Eof).unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn spawn_with_clear_env() {
+    use expectrl::{
+        process::{unix::UnixProcess, unix::UnixProcessOptions, Process},
+        Eof, Session,
+    };
+    use std::process::Command;
+
+    std::env::set_var("EXPECTRL_INHERITED", "1");
+
+    let mut cmd = Command::new("/usr/bin/env");
+    let _ = cmd.env("EXPECTRL_SET", "1");
+
+    let opts = UnixProcessOptions::new().clear_env(true);
+    let mut proc = UnixProcess::spawn_with_options(cmd, opts).unwrap();
+    let stream = proc.open_stream().unwrap();
+    let mut session = Session::new(proc, stream).unwrap();
+
+    let output = session.expect(Eof).unwrap();
+    assert_eq!(output.as_bytes(), b"EXPECTRL_SET=1\r\n");
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn termios_flag() {
+    use expectrl::process::unix::TermiosFlag;
+
+    let mut session = spawn("cat").unwrap();
+    assert!(session
+        .get_termios_flag(TermiosFlag::OutputProcessing)
+        .unwrap());
+
+    session
+        .set_termios_flag(TermiosFlag::OutputProcessing, false)
+        .unwrap();
+    assert!(!session
+        .get_termios_flag(TermiosFlag::OutputProcessing)
+        .unwrap());
+
+    session.send_line("Hello World").unwrap();
+    session.expect("Hello World\n").unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn termios_flag() {
+    use expectrl::process::unix::TermiosFlag;
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        assert!(session
+            .get_termios_flag(TermiosFlag::OutputProcessing)
+            .unwrap());
+
+        session
+            .set_termios_flag(TermiosFlag::OutputProcessing, false)
+            .unwrap();
+        assert!(!session
+            .get_termios_flag(TermiosFlag::OutputProcessing)
+            .unwrap());
+
+        session.send_line("Hello World").await.unwrap();
+        session.expect("Hello World\n").await.unwrap();
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_eof_status() {
+    use expectrl::process::unix::{Signal, WaitStatus};
+
+    let mut session = spawn("echo Hello World").unwrap();
+    let (found, status) = session.expect_eof_status().unwrap();
+    assert_eq!(found.as_bytes(), b"Hello World\r\n");
+    assert!(matches!(status, WaitStatus::Exited(_, 0)), "{:?}", status);
+
+    let mut cmd = std::process::Command::new("sh");
+    let _ = cmd.args(["-c", "kill -9 $$"]);
+    let mut session = expectrl::Session::spawn(cmd).unwrap();
+    let (_, status) = session.expect_eof_status().unwrap();
+    assert!(
+        matches!(status, WaitStatus::Signaled(_, Signal::SIGKILL, _)),
+        "{:?}",
+        status
+    );
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn expect_eof_status() {
+    use expectrl::process::unix::WaitStatus;
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("echo Hello World").unwrap();
+        let (found, status) = session.expect_eof_status().await.unwrap();
+        assert_eq!(found.as_bytes(), b"Hello World\r\n");
+        assert!(matches!(status, WaitStatus::Exited(_, 0)), "{:?}", status);
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn wait_for_exit_code() {
+    use std::time::Duration;
+
+    let mut session = spawn("false").unwrap();
+    session
+        .wait_for_exit_code(1, Duration::from_secs(5))
+        .unwrap();
+
+    let mut session = spawn("false").unwrap();
+    let err = session
+        .wait_for_exit_code(0, Duration::from_secs(5))
+        .unwrap_err();
+    assert!(err.to_string().contains("exited with code 1"), "{}", err);
+
+    let mut session = spawn("sleep 5").unwrap();
+    let err = session
+        .wait_for_exit_code(0, Duration::from_millis(100))
+        .unwrap_err();
+    assert!(matches!(err, expectrl::Error::ExpectTimeout), "{}", err);
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn output_history() {
+    use expectrl::Eof;
+
+    let mut session = spawn("echo Hello World").unwrap();
+    assert_eq!(session.output_history(), b"");
+
+    session.enable_output_history(5);
+    session.expect("Hello").unwrap();
+    session.expect(Eof).unwrap();
+
+    assert_eq!(session.output_history(), b"rld\r\n");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn output_history() {
+    use expectrl::Eof;
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("echo Hello World").unwrap();
+        assert_eq!(session.output_history(), b"");
+
+        session.enable_output_history(5);
+        session.expect("Hello").await.unwrap();
+        session.expect(Eof).await.unwrap();
+
+        assert_eq!(session.output_history(), b"rld\r\n");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn send_slow() {
+    use std::time::{Duration, Instant};
+
+    let mut session = spawn("cat").unwrap();
+
+    let now = Instant::now();
+    session
+        .send_slow("Hello\n", Duration::from_millis(20))
+        .unwrap();
+    assert!(now.elapsed() >= Duration::from_millis(100));
+
+    session.expect("Hello\r\n").unwrap();
+
+    session.set_lifetime(Some(Duration::from_millis(100)));
+    assert!(matches!(
+        session.send_slow("Hello\n", Duration::from_millis(50)),
+        Err(expectrl::Error::LifetimeExceeded)
+    ));
+
+    let mut session = spawn("true").unwrap();
+    session.set_send_checks_alive(true);
+    session
+        .wait_for_exit_code(0, Duration::from_secs(5))
+        .unwrap();
+    assert!(matches!(
+        session.send_slow("Hello\n", Duration::from_millis(20)),
+        Err(expectrl::Error::ProcessExited)
+    ));
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn send_slow() {
+    use std::time::{Duration, Instant};
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+
+        let now = Instant::now();
+        session
+            .send_slow("Hello\n", Duration::from_millis(20))
+            .await
+            .unwrap();
+        assert!(now.elapsed() >= Duration::from_millis(100));
+
+        session.expect("Hello\r\n").await.unwrap();
+
+        session.set_lifetime(Some(Duration::from_millis(100)));
+        assert!(matches!(
+            session
+                .send_slow("Hello\n", Duration::from_millis(50))
+                .await,
+            Err(expectrl::Error::LifetimeExceeded)
+        ));
+
+        let mut session = spawn("true").unwrap();
+        session.set_send_checks_alive(true);
+        session
+            .wait_for_exit_code(0, Duration::from_secs(5))
+            .unwrap();
+        assert!(matches!(
+            session
+                .send_slow("Hello\n", Duration::from_millis(20))
+                .await,
+            Err(expectrl::Error::ProcessExited)
+        ));
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn send_line_no_echo() {
+    use expectrl::process::Termios;
+
+    let mut session = spawn("cat").unwrap();
+    let _ = session.set_echo(true).unwrap();
+    session.send_line_no_echo("secret").unwrap();
+    session.expect("secret\r\n").unwrap();
+    assert!(session.is_echo().unwrap());
+
+    session.send_line("done").unwrap();
+    let found = session.expect("done\r\ndone\r\n").unwrap();
+    assert_eq!(found.before(), b"");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn send_line_no_echo() {
+    use expectrl::process::Termios;
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        let _ = session.set_echo(true).unwrap();
+        session.send_line_no_echo("secret").await.unwrap();
+        session.expect("secret\r\n").await.unwrap();
+        assert!(session.is_echo().unwrap());
+
+        session.send_line("done").await.unwrap();
+        let found = session.expect("done\r\ndone\r\n").await.unwrap();
+        assert_eq!(found.before(), b"");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn send_checks_alive() {
+    use std::time::Duration;
+
+    let mut session = spawn("true").unwrap();
+    session.set_send_checks_alive(true);
+    session
+        .wait_for_exit_code(0, Duration::from_secs(5))
+        .unwrap();
+
+    let err = session.send_line("Hello").unwrap_err();
+    assert!(matches!(err, expectrl::Error::ProcessExited), "{err:?}");
+    assert_eq!(err.to_string(), "The process has already exited");
+
+    let mut session = spawn("cat").unwrap();
+    session.set_send_checks_alive(true);
+    session.send_line("Hello").unwrap();
+    session.expect("Hello").unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn send_checks_alive() {
+    futures_lite::future::block_on(async {
+        let mut session = spawn("true").unwrap();
+        session.set_send_checks_alive(true);
+        session
+            .wait_for_exit_code(0, std::time::Duration::from_secs(5))
+            .unwrap();
+
+        let err = session.send_line("Hello").await.unwrap_err();
+        assert!(matches!(err, expectrl::Error::ProcessExited), "{err:?}");
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn control_chars() {
+    let session = spawn("cat").unwrap();
+    assert_eq!(session.eof_char().unwrap(), 0x04);
+    assert_eq!(session.intr_char().unwrap(), 0x03);
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn tty_name() {
+    let mut session = spawn("tty").unwrap();
+    let name = session.tty_name().unwrap();
+    assert!(name.starts_with("/dev/"), "{name:?}");
+
+    let found = session.expect("\r\n").unwrap();
+    assert_eq!(String::from_utf8_lossy(found.before()), name);
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn foreground_pgrp() {
+    let mut session = spawn("cat").unwrap();
+    session.send_line("Hello World").unwrap();
+    session.expect("Hello World").unwrap();
+
+    let pgrp = session.foreground_pgrp().unwrap();
+    assert_eq!(pgrp, session.get_process().pid().as_raw());
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn foreground_pgrp() {
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.send_line("Hello World").await.unwrap();
+        session.expect("Hello World").await.unwrap();
+
+        let pgrp = session.foreground_pgrp().unwrap();
+        assert_eq!(pgrp, session.get_process().pid().as_raw());
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn lifetime() {
+    use expectrl::{process::unix::WaitStatus, Error};
+    use std::time::Duration;
+
+    let mut session = spawn("cat").unwrap();
+    session.set_lifetime(Some(Duration::from_millis(300)));
+    session.set_lifetime_kills_process(true);
+
+    session.send_line("Hello World").unwrap();
+    session.expect("Hello World").unwrap();
+
+    std::thread::sleep(Duration::from_millis(400));
+
+    assert!(matches!(
+        session.send_line("Hello World"),
+        Err(Error::LifetimeExceeded)
+    ));
+    assert!(matches!(
+        session.expect("Hello World"),
+        Err(Error::LifetimeExceeded)
+    ));
+    assert!(session.read(&mut [0; 8]).is_err());
+
+    assert_eq!(
+        session.get_process().wait().unwrap(),
+        WaitStatus::Signaled(
+            session.get_process().pid(),
+            expectrl::process::unix::Signal::SIGKILL,
+            false
+        )
+    );
+
+    session.set_lifetime(None);
+    assert!(!matches!(
+        session.is_matched("Hello World"),
+        Err(Error::LifetimeExceeded)
+    ));
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn lifetime() {
+    use expectrl::{process::unix::WaitStatus, Error};
+    use std::time::Duration;
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.set_lifetime(Some(Duration::from_millis(300)));
+        session.set_lifetime_kills_process(true);
+
+        session.send_line("Hello World").await.unwrap();
+        session.expect("Hello World").await.unwrap();
+
+        std::thread::sleep(Duration::from_millis(400));
+
+        assert!(matches!(
+            session.send_line("Hello World").await,
+            Err(Error::LifetimeExceeded)
+        ));
+        assert!(matches!(
+            session.expect("Hello World").await,
+            Err(Error::LifetimeExceeded)
+        ));
+        assert!(session.read(&mut [0; 8]).await.is_err());
+
+        assert_eq!(
+            session.get_process().wait().unwrap(),
+            WaitStatus::Signaled(
+                session.get_process().pid(),
+                expectrl::process::unix::Signal::SIGKILL,
+                false
+            )
+        );
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn lifetime_caps_expect_timeout() {
+    use expectrl::Error;
+    use std::time::{Duration, Instant};
+
+    let mut session = spawn("cat").unwrap();
+    session.set_expect_timeout(None);
+    session.set_lifetime(Some(Duration::from_millis(300)));
+
+    let now = Instant::now();
+    assert!(matches!(
+        session.expect("Hello World"),
+        Err(Error::LifetimeExceeded)
+    ));
+    assert!(now.elapsed() < Duration::from_secs(2));
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn lifetime_caps_expect_timeout() {
+    use expectrl::Error;
+    use std::time::{Duration, Instant};
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.set_expect_timeout(None);
+        session.set_lifetime(Some(Duration::from_millis(300)));
+
+        let now = Instant::now();
+        assert!(matches!(
+            session.expect("Hello World").await,
+            Err(Error::LifetimeExceeded)
+        ));
+        assert!(now.elapsed() < Duration::from_secs(2));
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn from_raw_fd() {
+    use expectrl::{process::Healthcheck, Session};
+    use std::os::unix::{io::IntoRawFd, net::UnixStream};
+
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    let mut session = unsafe { Session::from_raw_fd(stream.into_raw_fd()) }.unwrap();
+    assert!(session.is_alive().unwrap());
+
+    peer.write_all(b"Hello World").unwrap();
+    session.expect("World").unwrap();
+
+    session.send_line("Hi").unwrap();
+    let mut buf = [0; 3];
+    peer.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"Hi\n");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn from_raw_fd() {
+    use expectrl::{process::Healthcheck, Session};
+    use std::{
+        io::{Read, Write},
+        os::unix::{io::IntoRawFd, net::UnixStream},
+    };
+
+    futures_lite::future::block_on(async {
+        let (stream, mut peer) = UnixStream::pair().unwrap();
+        let mut session = unsafe { Session::from_raw_fd(stream.into_raw_fd()) }.unwrap();
+        assert!(session.is_alive().unwrap());
+
+        peer.write_all(b"Hello World").unwrap();
+        session.expect("World").await.unwrap();
+
+        session.send_line("Hi").await.unwrap();
+        let mut buf = [0; 3];
+        peer.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hi\n");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn spawn_with() {
+    use expectrl::{Eof, Session};
+
+    let mut session = Session::spawn_with("sh", |cmd| {
+        let _ = cmd
+            .args(["-c", "echo $GREETING; pwd"])
+            .env("GREETING", "Hello World")
+            .current_dir("/");
+    })
+    .unwrap();
+    let found = session.expect(Eof).unwrap();
+    assert_eq!(&found[0], b"Hello World\r\n/\r\n");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn spawn_with() {
+    use expectrl::{Eof, Session};
+
+    futures_lite::future::block_on(async {
+        let mut session = Session::spawn_with("sh", |cmd| {
+            let _ = cmd
+                .args(["-c", "echo $GREETING; pwd"])
+                .env("GREETING", "Hello World")
+                .current_dir("/");
+        })
+        .unwrap();
+        let found = session.expect(Eof).await.unwrap();
+        assert_eq!(&found[0], b"Hello World\r\n/\r\n");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn spawn_with_env() {
+    use expectrl::Eof;
+
+    let env = [(String::from("GREETING"), String::from("Hello World"))];
+    let mut session = expectrl::spawn_with_env("printenv GREETING", &env, None).unwrap();
+    let found = session.expect(Eof).unwrap();
+    assert_eq!(&found[0], b"Hello World\r\n");
+
+    let mut session = expectrl::spawn_with_env("pwd", &[], Some("/".into())).unwrap();
+    let found = session.expect(Eof).unwrap();
+    assert_eq!(&found[0], b"/\r\n");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn spawn_with_env() {
+    use expectrl::Eof;
+
+    futures_lite::future::block_on(async {
+        let env = [(String::from("GREETING"), String::from("Hello World"))];
+        let mut session = expectrl::spawn_with_env("printenv GREETING", &env, None).unwrap();
+        let found = session.expect(Eof).await.unwrap();
+        assert_eq!(&found[0], b"Hello World\r\n");
+
+        let mut session = expectrl::spawn_with_env("pwd", &[], Some("/".into())).unwrap();
+        let found = session.expect(Eof).await.unwrap();
+        assert_eq!(&found[0], b"/\r\n");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn spawn_piped() {
+    use expectrl::{
+        process::{unix::WaitStatus, Healthcheck},
+        Eof, Session,
+    };
+    use std::{process::Command, time::Duration};
+
+    let mut session = Session::spawn_piped(Command::new("cat")).unwrap();
+    session.send_line("Hello World").unwrap();
+    let found = session.expect("\n").unwrap();
+    assert_eq!(found.before(), b"Hello World");
+
+    let mut command = Command::new("sh");
+    let _ = command.args(["-c", "echo Hello; exit 3"]);
+    let mut session = Session::spawn_piped(command).unwrap();
+    let found = session.expect(Eof).unwrap();
+    assert_eq!(&found[0], b"Hello\n");
+
+    let status = loop {
+        match session.get_process().get_status().unwrap() {
+            WaitStatus::StillAlive => std::thread::sleep(Duration::from_millis(10)),
+            status => break status,
+        }
+    };
+    assert!(matches!(status, WaitStatus::Exited(_, 3)), "{status:?}");
+    assert!(!session.get_process().is_alive().unwrap());
+
+    let mut session = Session::spawn_piped(Command::new("cat")).unwrap();
+    let pid = session.get_process_mut().child().id();
+    drop(session);
+
+    // the process is killed and reaped, so there's nothing to signal
+    let status = Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn spawn_piped() {
+    use expectrl::{Eof, Session};
+    use std::process::Command;
+
+    futures_lite::future::block_on(async {
+        let mut session = Session::spawn_piped(Command::new("cat")).unwrap();
+        session.send_line("Hello World").await.unwrap();
+        let found = session.expect("\n").await.unwrap();
+        assert_eq!(found.before(), b"Hello World");
+
+        let mut command = Command::new("sh");
+        let _ = command.args(["-c", "echo Hello; exit 3"]);
+        let mut session = Session::spawn_piped(command).unwrap();
+        let found = session.expect(Eof).await.unwrap();
+        assert_eq!(&found[0], b"Hello\n");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn export_import_buffer() {
+    use expectrl::Session;
+    use std::os::unix::{io::IntoRawFd, net::UnixStream};
+
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    let mut session = unsafe { Session::from_raw_fd(stream.into_raw_fd()) }.unwrap();
+
+    peer.write_all(b"Hello World").unwrap();
+    session.expect("Hello").unwrap();
+    let buf = session.export_buffer();
+    assert!(b" World".starts_with(&buf), "{buf:?}");
+    drop(session);
+
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    let mut session = unsafe { Session::from_raw_fd(stream.into_raw_fd()) }.unwrap();
+    session.import_buffer(&buf);
+    assert_eq!(session.export_buffer(), buf);
+
+    peer.write_all(&b" World"[buf.len()..]).unwrap();
+    peer.write_all(b"!").unwrap();
+    let found = session.expect("World").unwrap();
+    assert_eq!(found.before(), b" ");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn export_import_buffer() {
+    use expectrl::Session;
+    use std::{
+        io::Write,
+        os::unix::{io::IntoRawFd, net::UnixStream},
+    };
+
+    futures_lite::future::block_on(async {
+        let (stream, mut peer) = UnixStream::pair().unwrap();
+        let mut session = unsafe { Session::from_raw_fd(stream.into_raw_fd()) }.unwrap();
+
+        peer.write_all(b"Hello World").unwrap();
+        session.expect("Hello").await.unwrap();
+        let buf = session.export_buffer();
+        assert!(b" World".starts_with(&buf), "{buf:?}");
+        drop(session);
+
+        let (stream, mut peer) = UnixStream::pair().unwrap();
+        let mut session = unsafe { Session::from_raw_fd(stream.into_raw_fd()) }.unwrap();
+        session.import_buffer(&buf);
+        assert_eq!(session.export_buffer(), buf);
+
+        peer.write_all(&b" World"[buf.len()..]).unwrap();
+        peer.write_all(b"!").unwrap();
+        let found = session.expect("World").await.unwrap();
+        assert_eq!(found.before(), b" ");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn clear_buffer() {
+    use std::time::Duration;
+
+    let mut session = spawn("cat").unwrap();
+    session.set_expect_timeout(Some(Duration::from_millis(300)));
+    session.send_line("123").unwrap();
+    assert!(matches!(
+        session.expect("Bye"),
+        Err(expectrl::Error::ExpectTimeout)
+    ));
+
+    session.clear_buffer();
+    assert!(session.drain_buffer().is_empty());
+
+    session.send_line("Bye").unwrap();
+    let found = session.expect("Bye").unwrap();
+    assert_eq!(found.before(), b"");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn clear_buffer() {
+    use std::time::Duration;
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.set_expect_timeout(Some(Duration::from_millis(300)));
+        session.send_line("123").await.unwrap();
+        assert!(matches!(
+            session.expect("Bye").await,
+            Err(expectrl::Error::ExpectTimeout)
+        ));
+
+        session.clear_buffer();
+        assert!(session.drain_buffer().is_empty());
+
+        session.send_line("Bye").await.unwrap();
+        let found = session.expect("Bye").await.unwrap();
+        assert_eq!(found.before(), b"");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn drain_buffer() {
+    use std::time::Duration;
+
+    let mut session = spawn("cat").unwrap();
+    session.set_expect_timeout(Some(Duration::from_millis(300)));
+    session.send_line("Hello World").unwrap();
+    assert!(matches!(
+        session.expect("Bye"),
+        Err(expectrl::Error::ExpectTimeout)
+    ));
+
+    assert_eq!(session.drain_buffer(), b"Hello World\r\n");
+    assert!(session.drain_buffer().is_empty());
+
+    session.send_line("Bye").unwrap();
+    let found = session.expect("Bye").unwrap();
+    assert_eq!(found.before(), b"");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn drain_buffer() {
+    use std::time::Duration;
+
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.set_expect_timeout(Some(Duration::from_millis(300)));
+        session.send_line("Hello World").await.unwrap();
+        assert!(matches!(
+            session.expect("Bye").await,
+            Err(expectrl::Error::ExpectTimeout)
+        ));
+
+        assert_eq!(session.drain_buffer(), b"Hello World\r\n");
+        assert!(session.drain_buffer().is_empty());
+
+        session.send_line("Bye").await.unwrap();
+        let found = session.expect("Bye").await.unwrap();
+        assert_eq!(found.before(), b"");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn send_keys() {
+    use expectrl::{Key, Session};
+    use std::os::unix::net::UnixStream;
+
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    let mut session = Session::over_stream(stream).unwrap();
+
+    session.send_keys(&[Key::Up, Key::F5, Key::Enter]).unwrap();
+    session.set_application_cursor_keys(true);
+    session.send_keys(&[Key::Up, Key::PageDown]).unwrap();
+
+    let mut buf = [0; 16];
+    peer.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"\x1b[A\x1b[15~\r\x1bOA\x1b[6~");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn send_keys() {
+    use expectrl::{Key, Session};
+    use std::{io::Read, os::unix::net::UnixStream};
+
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    futures_lite::future::block_on(async {
+        let stream = async_io::Async::new(stream).unwrap();
+        let mut session = Session::over_stream(stream).unwrap();
+
+        session
+            .send_keys(&[Key::Up, Key::F5, Key::Enter])
+            .await
+            .unwrap();
+        session.set_application_cursor_keys(true);
+        session.send_keys(&[Key::Up, Key::PageDown]).await.unwrap();
+    });
+
+    let mut buf = [0; 16];
+    peer.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"\x1b[A\x1b[15~\r\x1bOA\x1b[6~");
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn window_size() {
+    let mut command = std::process::Command::new("sh");
+    let _ = command.args(["-c", "read x; stty size"]);
+    let mut session = OsSession::spawn(command).unwrap();
+
+    session.set_window_size(120, 40).unwrap();
+    assert_eq!(session.get_window_size().unwrap(), (120, 40));
+
+    session.send_line("").unwrap();
+    session.expect("40 120").unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn window_size() {
+    futures_lite::future::block_on(async {
+        let mut command = std::process::Command::new("sh");
+        let _ = command.args(["-c", "read x; stty size"]);
+        let mut session = OsSession::spawn(command).unwrap();
+
+        session.set_window_size(120, 40).unwrap();
+        assert_eq!(session.get_window_size().unwrap(), (120, 40));
+
+        session.send_line("").await.unwrap();
+        session.expect("40 120").await.unwrap();
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn set_line_ending() {
+    use expectrl::{LineEnding, Session};
+    use std::os::unix::net::UnixStream;
+
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    let mut session = Session::over_stream(stream).unwrap();
+
+    session.send_line("a").unwrap();
+    session.set_line_ending(LineEnding::CrLf);
+    session.send_line("b").unwrap();
+    session.set_line_ending(LineEnding::Cr);
+    session.send_line("c").unwrap();
+
+    let mut buf = [0; 7];
+    peer.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"a\nb\r\nc\r");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn set_line_ending() {
+    use expectrl::{LineEnding, Session};
+    use std::{io::Read, os::unix::net::UnixStream};
+
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    futures_lite::future::block_on(async {
+        let stream = async_io::Async::new(stream).unwrap();
+        let mut session = Session::over_stream(stream).unwrap();
+
+        session.send_line("a").await.unwrap();
+        session.set_line_ending(LineEnding::CrLf);
+        session.send_line("b").await.unwrap();
+        session.set_line_ending(LineEnding::Cr);
+        session.send_line("c").await.unwrap();
+    });
+
+    let mut buf = [0; 7];
+    peer.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"a\nb\r\nc\r");
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn send_signal() {
+    use expectrl::process::unix::Signal;
+
+    let mut command = std::process::Command::new("sh");
+    let _ = command.args([
+        "-c",
+        "trap 'echo got USR1; exit' USR1; echo ready; while true; do sleep 0.1; done",
+    ]);
+    let mut session = OsSession::spawn(command).unwrap();
+    session.expect("ready").unwrap();
+
+    session.send_signal(Signal::SIGUSR1).unwrap();
+    session.expect("got USR1").unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn send_signal() {
+    use expectrl::process::unix::Signal;
+
+    futures_lite::future::block_on(async {
+        let mut command = std::process::Command::new("sh");
+        let _ = command.args([
+            "-c",
+            "trap 'echo got USR1; exit' USR1; echo ready; while true; do sleep 0.1; done",
+        ]);
+        let mut session = OsSession::spawn(command).unwrap();
+        session.expect("ready").await.unwrap();
+
+        session.send_signal(Signal::SIGUSR1).unwrap();
+        session.expect("got USR1").await.unwrap();
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn read_available_string() {
+    use expectrl::Session;
+    use std::{io::Write, os::unix::net::UnixStream};
+
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    let mut session = Session::over_stream(stream).unwrap();
+
+    assert_eq!(session.read_available_string().unwrap(), "");
+
+    let mut output = "x".repeat(1000).into_bytes();
+    output.extend(&"é".as_bytes()[..1]);
+    peer.write_all(&output).unwrap();
+    assert_eq!(session.read_available_string().unwrap(), "x".repeat(1000));
+
+    peer.write_all(&"é".as_bytes()[1..]).unwrap();
+    assert_eq!(session.read_available_string().unwrap(), "é");
+
+    peer.write_all(b"\xff!").unwrap();
+    let err = session.read_available_string().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(session.read_available().unwrap(), b"\xff!");
+    assert_eq!(session.read_available().unwrap(), b"");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn read_available_string() {
+    use expectrl::Session;
+    use std::{io::Write, os::unix::net::UnixStream};
+
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    futures_lite::future::block_on(async {
+        let stream = async_io::Async::new(stream).unwrap();
+        let mut session = Session::over_stream(stream).unwrap();
+
+        assert_eq!(session.read_available_string().await.unwrap(), "");
+
+        let mut output = "x".repeat(1000).into_bytes();
+        output.extend(&"é".as_bytes()[..1]);
+        peer.write_all(&output).unwrap();
+        assert_eq!(
+            session.read_available_string().await.unwrap(),
+            "x".repeat(1000)
+        );
+
+        peer.write_all(&"é".as_bytes()[1..]).unwrap();
+        assert_eq!(session.read_available_string().await.unwrap(), "é");
+
+        peer.write_all(b"\xff!").unwrap();
+        let err = session.read_available_string().await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(session.read_available().await.unwrap(), b"\xff!");
+        assert_eq!(session.read_available().await.unwrap(), b"");
+    });
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn session_builder() {
+    use expectrl::{process::Termios, session::SessionBuilder};
+    use std::time::Duration;
+
+    let mut session = SessionBuilder::new("cat")
+        .timeout(Some(Duration::from_secs(5)))
+        .echo(false)
+        .window_size(100, 30)
+        .spawn()
+        .unwrap();
+
+    assert!(!session.is_echo().unwrap());
+    assert_eq!(session.get_window_size().unwrap(), (100, 30));
+
+    session.send_line("Hello World").unwrap();
+    let found = session.expect("\n").unwrap();
+    assert_eq!(found.before(), b"Hello World\r");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn session_builder() {
+    use expectrl::{process::Termios, session::SessionBuilder};
+    use std::time::Duration;
+
+    futures_lite::future::block_on(async {
+        let mut session = SessionBuilder::new("cat")
+            .timeout(Some(Duration::from_secs(5)))
+            .echo(false)
+            .window_size(100, 30)
+            .spawn()
+            .unwrap();
+
+        assert!(!session.is_echo().unwrap());
+        assert_eq!(session.get_window_size().unwrap(), (100, 30));
+
+        session.send_line("Hello World").await.unwrap();
+        let found = session.expect("\n").await.unwrap();
+        assert_eq!(found.before(), b"Hello World\r");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn session_builder_terminal_at_startup() {
+    use expectrl::session::SessionBuilder;
+
+    let mut session = SessionBuilder::new("stty size")
+        .window_size(100, 30)
+        .spawn()
+        .unwrap();
+    session.expect("30 100").unwrap();
+
+    let mut session = SessionBuilder::new("stty -a").echo(false).spawn().unwrap();
+    session.expect("-echo ").unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn session_builder_terminal_at_startup() {
+    use expectrl::session::SessionBuilder;
+
+    futures_lite::future::block_on(async {
+        let mut session = SessionBuilder::new("stty size")
+            .window_size(100, 30)
+            .spawn()
+            .unwrap();
+        session.expect("30 100").await.unwrap();
+
+        let mut session = SessionBuilder::new("stty -a").echo(false).spawn().unwrap();
+        session.expect("-echo ").await.unwrap();
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn over_stream() {
+    use expectrl::{Eof, Session};
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        let (mut conn, _) = listener.accept().unwrap();
+        conn.write_all(b"220 Ready\r\n").unwrap();
+        let mut buf = [0; 5];
+        conn.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"QUIT\n");
+        conn.write_all(b"221 Bye\r\n").unwrap();
+    });
+
+    let stream = TcpStream::connect(addr).unwrap();
+    let mut session = Session::over_stream(stream).unwrap();
+    session.expect("220 Ready").unwrap();
+    session.send_line("QUIT").unwrap();
+    session.expect("221 Bye").unwrap();
+    session.expect(Eof).unwrap();
+
+    server.join().unwrap();
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_buffered_without_read() {
+    use expectrl::{process::NonBlocking, Session};
+    use std::{
+        io::{self, Read, Write},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    struct Counting {
+        data: io::Cursor<Vec<u8>>,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl Read for Counting {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let _ = self.reads.fetch_add(1, Ordering::SeqCst);
+            match self.data.read(buf)? {
+                0 => Err(io::ErrorKind::WouldBlock.into()),
+                n => Ok(n),
+            }
+        }
+    }
+
+    impl Write for Counting {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl NonBlocking for Counting {
+        fn set_blocking(&mut self, _: bool) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    for lazy in [false, true] {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let stream = Counting {
+            data: io::Cursor::new(b"Hello World\n".to_vec()),
+            reads: reads.clone(),
+        };
+        let mut session = Session::over_stream(stream).unwrap();
+        session.set_expect_lazy(lazy);
+
+        session.expect("Hello").unwrap();
+        if lazy {
+            // a lazy expect reads just enough, so let the rest be read into the buffer
+            assert!(!session.is_matched("Bye").unwrap());
+        }
+
+        let count = reads.load(Ordering::SeqCst);
+        let found = session.expect("World").unwrap();
+        assert_eq!(found.before(), b" ");
+        assert_eq!(reads.load(Ordering::SeqCst), count);
+    }
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn expect_buffered_without_read() {
+    use expectrl::Session;
+    use futures_lite::{AsyncRead, AsyncWrite};
+    use std::{
+        io,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+    };
+
+    struct Counting {
+        data: Vec<u8>,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl AsyncRead for Counting {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            let _ = self.reads.fetch_add(1, Ordering::SeqCst);
+            if self.data.is_empty() {
+                return Poll::Pending;
+            }
+
+            let n = std::cmp::min(buf.len(), self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            let _ = self.data.drain(..n);
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    impl AsyncWrite for Counting {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    futures_lite::future::block_on(async {
+        for lazy in [false, true] {
+            let reads = Arc::new(AtomicUsize::new(0));
+            let stream = Counting {
+                data: b"Hello World\n".to_vec(),
+                reads: reads.clone(),
+            };
+            let mut session = Session::over_stream(stream).unwrap();
+            session.set_expect_lazy(lazy);
+
+            session.expect("Hello").await.unwrap();
+
+            let count = reads.load(Ordering::SeqCst);
+            let found = session.expect("World").await.unwrap();
+            assert_eq!(found.before(), b" ");
+            assert_eq!(reads.load(Ordering::SeqCst), count);
+        }
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn expect_length_prefixed() {
+    use expectrl::{Endian, LengthPrefixed, Session};
+    use std::os::unix::net::UnixStream;
+
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    let mut session = Session::over_stream(stream).unwrap();
+
+    peer.write_all(b"\0\0\0\x05Hello\0\0").unwrap();
+    peer.write_all(b"\0\x02\r\n").unwrap();
+
+    let needle = LengthPrefixed {
+        len_bytes: 4,
+        endian: Endian::Big,
+    };
+    let frame = session.expect(needle).unwrap();
+    assert_eq!(&frame[0], b"Hello");
+    assert_eq!(frame.before(), b"\0\0\0\x05");
+
+    let frame = session.expect(needle).unwrap();
+    assert_eq!(&frame[0], b"\r\n");
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn over_stream() {
+    use expectrl::{Eof, Session};
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        let (mut conn, _) = listener.accept().unwrap();
+        conn.write_all(b"220 Ready\r\n").unwrap();
+        let mut buf = [0; 5];
+        conn.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"QUIT\n");
+        conn.write_all(b"221 Bye\r\n").unwrap();
+    });
+
+    futures_lite::future::block_on(async {
+        let stream = async_io::Async::<TcpStream>::connect(addr).await.unwrap();
+        let mut session = Session::over_stream(stream).unwrap();
+        session.expect("220 Ready").await.unwrap();
+        session.send_line("QUIT").await.unwrap();
+        session.expect("221 Bye").await.unwrap();
+        session.expect(Eof).await.unwrap();
+    });
+
+    server.join().unwrap();
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn dump() {
+    let mut session = spawn("cat").unwrap();
+    session.send_line("Hello World").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    assert!(!session.is_matched("Bye").unwrap());
+    session.expect("Hello ").unwrap();
+
+    let dump = session.dump();
+    assert!(dump.contains("alive: true"), "{dump}");
+    assert!(dump.contains("expect timeout: 10s"), "{dump}");
+    assert!(dump.contains("expect mode: greedy"), "{dump}");
+    assert!(
+        dump.contains("buffer (") && dump.contains("\"World\\r\\n"),
+        "{dump}"
+    );
+    assert!(dump.contains("buffer hex: 57 6f 72 6c 64"), "{dump}");
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn dump() {
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.send_line("Hello World").await.unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        assert!(!session.is_matched("Bye").await.unwrap());
+        session.expect("Hello ").await.unwrap();
+        session.set_expect_lazy(true);
+
+        let dump = session.dump();
+        assert!(dump.contains("alive: true"), "{dump}");
+        assert!(dump.contains("expect mode: lazy"), "{dump}");
+        assert!(dump.contains("buffer hex: 57 6f 72 6c 64"), "{dump}");
+    })
+}
+
+#[cfg(unix)]
+#[cfg(not(feature = "async"))]
+#[test]
+fn on_eof() {
+    use expectrl::Eof;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let mut session = spawn("echo Hello World").unwrap();
+    session.set_on_eof({
+        let calls = calls.clone();
+        move || {
+            let _ = calls.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+
+    // the EOF may be read along with the output
+    session.expect("World").unwrap();
+    assert!(calls.load(Ordering::SeqCst) <= 1);
+
+    session.expect(Eof).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    assert!(session.expect(Eof).is_ok());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[cfg(unix)]
+#[cfg(feature = "async")]
+#[test]
+fn on_eof() {
+    use expectrl::Eof;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    futures_lite::future::block_on(async {
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let mut session = spawn("echo Hello World").unwrap();
+        session.set_on_eof({
+            let calls = calls.clone();
+            move || {
+                let _ = calls.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        // the EOF may be read along with the output
+        session.expect("World").await.unwrap();
+        assert!(calls.load(Ordering::SeqCst) <= 1);
+
+        session.expect(Eof).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        assert!(session.expect(Eof).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn eof_confirm_reads() {
+    use expectrl::{Eof, Error, Session};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let stream = ChunkedStream::new(&["Hello", "", " World"]);
+    let mut session = Session::new((), stream).unwrap();
+    assert!(matches!(session.expect("World"), Err(Error::Eof)));
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let stream = ChunkedStream::new(&["Hello", "", " World"]);
+    let mut session = Session::new((), stream).unwrap();
+    session.set_eof_confirm_reads(2);
+    session.set_on_eof({
+        let calls = calls.clone();
+        move || {
+            let _ = calls.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+    session.expect("World").unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+    session.expect(Eof).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn eof_confirm_reads() {
+    use expectrl::{Eof, Error, Session};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    futures_lite::future::block_on(async {
+        let stream = ChunkedStream::new(&["Hello", "", " World"]);
+        let mut session = Session::new((), stream).unwrap();
+        assert!(matches!(session.expect("World").await, Err(Error::Eof)));
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let stream = ChunkedStream::new(&["Hello", "", " World"]);
+        let mut session = Session::new((), stream).unwrap();
+        session.set_eof_confirm_reads(2);
+        session.set_on_eof({
+            let calls = calls.clone();
+            move || {
+                let _ = calls.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        session.expect("World").await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        session.expect(Eof).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    })
+}
+
+/// A stream which returns prepared chunks one by one,
+/// an empty chunk results in an empty read.
+struct ChunkedStream {
+    chunks: std::collections::VecDeque<Vec<u8>>,
+}
+
+impl ChunkedStream {
+    fn new(chunks: &[&str]) -> Self {
+        Self {
+            chunks: chunks.iter().map(|c| c.as_bytes().to_vec()).collect(),
+        }
+    }
+
+    fn next_chunk(&mut self, buf: &mut [u8]) -> usize {
+        match self.chunks.front_mut() {
+            Some(chunk) => {
+                let n = std::cmp::min(buf.len(), chunk.len());
+                buf[..n].copy_from_slice(&chunk[..n]);
+                let _ = chunk.drain(..n);
+                if chunk.is_empty() {
+                    let _ = self.chunks.pop_front();
+                }
+
+                n
+            }
+            None => 0,
+        }
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl std::io::Read for ChunkedStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(self.next_chunk(buf))
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl std::io::Write for ChunkedStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl expectrl::process::NonBlocking for ChunkedStream {
+    fn set_blocking(&mut self, _: bool) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl futures_lite::AsyncRead for ChunkedStream {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        std::task::Poll::Ready(Ok(self.get_mut().next_chunk(buf)))
+    }
+}
+
+#[cfg(feature = "async")]
+impl futures_lite::AsyncWrite for ChunkedStream {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        std::task::Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn custom_non_blocking_stream() {
+    use expectrl::{process::NoProcess, Eof, Error, Session};
+    use std::{cell::RefCell, rc::Rc};
+
+    let output = Rc::new(RefCell::new(LoopbackState::default()));
+    let stream = LoopbackStream(output.clone());
+
+    let mut session = Session::new(NoProcess, stream).unwrap();
+    session.set_expect_timeout(Some(std::time::Duration::from_millis(100)));
+
+    session.send_line("Hello World").unwrap();
+    let m = session.expect("World").unwrap();
+    assert_eq!(m.before(), b"Hello ");
+
+    assert!(matches!(session.expect("Hi"), Err(Error::ExpectTimeout)));
+
+    output.borrow_mut().closed = true;
+    session.expect(Eof).unwrap();
+}
+
+/// A stream which reads back what was written to it,
+/// it returns [std::io::ErrorKind::WouldBlock] when there's nothing to read in non blocking mode.
+#[cfg(not(feature = "async"))]
+struct LoopbackStream(std::rc::Rc<std::cell::RefCell<LoopbackState>>);
+
+#[cfg(not(feature = "async"))]
+#[derive(Default)]
+struct LoopbackState {
+    buf: std::collections::VecDeque<u8>,
+    blocking: bool,
+    closed: bool,
+}
+
+#[cfg(not(feature = "async"))]
+impl std::io::Read for LoopbackStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut state = self.0.borrow_mut();
+        if state.buf.is_empty() && !state.closed {
+            assert!(!state.blocking, "a blocking read would never return");
+            return Err(std::io::ErrorKind::WouldBlock.into());
+        }
+
+        state.buf.read(buf)
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl std::io::Write for LoopbackStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().buf.extend(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl expectrl::process::NonBlocking for LoopbackStream {
+    fn set_blocking(&mut self, on: bool) -> std::io::Result<()> {
+        self.0.borrow_mut().blocking = on;
+        Ok(())
+    }
+}
+
 #[test]
 fn test_spawn_no_command() {
     #[cfg(unix)]
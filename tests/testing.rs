@@ -0,0 +1,55 @@
+#![cfg(unix)]
+
+use expectrl::{testing::CliTest, Eof};
+
+#[cfg(not(feature = "async"))]
+use expectrl::Expect;
+
+#[cfg(feature = "async")]
+use expectrl::AsyncExpect;
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn cli_test() {
+    let test = CliTest::in_temp_dir().unwrap();
+    test.write("input.txt", "Hello World").unwrap();
+
+    let mut session = test
+        .spawn_with("sh", |cmd| {
+            let _ = cmd.args(["-c", "cp input.txt output.txt; echo copied"]);
+        })
+        .unwrap();
+    session.expect("copied").unwrap();
+    session.expect(Eof).unwrap();
+
+    assert!(test.exists("output.txt"));
+    assert_eq!(test.read_to_string("output.txt").unwrap(), "Hello World");
+
+    let path = test.path().to_owned();
+    test.close().unwrap();
+    assert!(!path.exists());
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn cli_test() {
+    futures_lite::future::block_on(async {
+        let test = CliTest::in_temp_dir().unwrap();
+        test.write("input.txt", "Hello World").unwrap();
+
+        let mut session = test
+            .spawn_with("sh", |cmd| {
+                let _ = cmd.args(["-c", "cp input.txt output.txt; echo copied"]);
+            })
+            .unwrap();
+        session.expect("copied").await.unwrap();
+        session.expect(Eof).await.unwrap();
+
+        assert!(test.exists("output.txt"));
+        assert_eq!(test.read_to_string("output.txt").unwrap(), "Hello World");
+
+        let path = test.path().to_owned();
+        test.close().unwrap();
+        assert!(!path.exists());
+    })
+}
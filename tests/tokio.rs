@@ -0,0 +1,51 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use expectrl::{spawn, stream::tokio::TokioStream, AsyncExpect, Eof, Session};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn expect_on_tokio_runtime() {
+    let mut session = spawn("cat").unwrap();
+    session.send_line("Hello World").await.unwrap();
+    session.expect("Hello World").await.unwrap();
+}
+
+#[tokio::test]
+async fn expect_timeout_on_tokio_runtime() {
+    let mut session = spawn("sleep 3").unwrap();
+    session.set_expect_timeout(Some(Duration::from_millis(100)));
+    match session.expect(Eof).await {
+        Err(expectrl::Error::ExpectTimeout) => {}
+        r => panic!("should raise TimeOut {:?}", r),
+    }
+}
+
+#[tokio::test]
+async fn over_tokio_stream() {
+    let (local, mut remote) = tokio::net::UnixStream::pair().unwrap();
+    let mut session = Session::over_stream(TokioStream::new(local)).unwrap();
+
+    remote.write_all(b"login: ").await.unwrap();
+    session.expect("login: ").await.unwrap();
+
+    session.send_line("guest").await.unwrap();
+    let mut buf = [0; 6];
+    let _ = remote.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"guest\n");
+}
+
+#[test]
+fn expect_outside_of_tokio_runtime() {
+    futures_lite::future::block_on(async {
+        let mut session = spawn("cat").unwrap();
+        session.set_expect_timeout(Some(Duration::from_millis(300)));
+        session.send_line("Hello World").await.unwrap();
+        session.expect("Hello World").await.unwrap();
+        assert!(matches!(
+            session.expect("Bye").await,
+            Err(expectrl::Error::ExpectTimeout)
+        ));
+    })
+}